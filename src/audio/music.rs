@@ -3,12 +3,15 @@ use std::time::Duration;
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioControl, AudioEasing, AudioTween};
 
-const MAIN_TRACK: &'static str = "music/Three Red Hearts - Go (No Vocal).ogg";
+use crate::game::settings::GameSettings;
 
-pub fn play_music(audio: Res<Audio>, asset_server: Res<AssetServer>) {
+const MAIN_TRACK: &str = "music/Three Red Hearts - Go (No Vocal).ogg";
+
+pub fn play_music(audio: Res<Audio>, asset_server: Res<AssetServer>, settings: Res<GameSettings>) {
     audio
         .play(asset_server.load(MAIN_TRACK))
         .looped()
+        .with_volume(settings.volume)
         .fade_in(AudioTween::new(
             Duration::new(1, 0),
             AudioEasing::InOutPowi(5),
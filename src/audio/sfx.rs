@@ -7,6 +7,8 @@ use bevy_kira_audio::AudioControl;
 use strum::EnumIter;
 use strum::IntoEnumIterator;
 
+use crate::game::settings::GameSettings;
+
 #[derive(EnumIter, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum SoundEffect {
     UiHover,
@@ -49,6 +51,7 @@ pub struct PlaySoundEffect(pub SoundEffect);
 pub fn on_play_sfx(
     audio: Res<Audio>,
     sfx_table: Res<SfxTable>,
+    settings: Res<GameSettings>,
     mut events: EventReader<PlaySoundEffect>,
 ) {
     for evt in events.read() {
@@ -60,6 +63,6 @@ pub fn on_play_sfx(
                     .expect("Failed to find sound effect. This should never happen.")
                     .clone_weak(),
             )
-            .with_volume(Volume::Amplitude(2.0));
+            .with_volume(Volume::Amplitude(2.0 * settings.volume));
     }
 }
@@ -2,18 +2,14 @@ use bevy::{asset::LoadState, prelude::*, render::render_asset::RenderAssetUsages
 use image::DynamicImage;
 use noise::NoiseFn;
 
-use super::board::state::GameState;
+use crate::state::PauseState;
+
+use super::board::{state::GameState, Board};
 
 /// Represents a sprite that will be a gradient background that spans the camera
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct GradientBackground(pub u32);
 
-impl Default for GradientBackground {
-    fn default() -> Self {
-        Self(0)
-    }
-}
-
 impl GradientBackground {
     pub fn build(&self, seed: u32) -> DynamicImage {
         let grad = colorgrad::magma();
@@ -35,24 +31,26 @@ impl GradientBackground {
     pub fn load(&self, asset_server: &Res<AssetServer>, seed: u32) -> Handle<Image> {
         let image = self.build(seed);
         let image = Image::from_dynamic(image, false, RenderAssetUsages::default());
-        let handle = asset_server.add(image);
-        handle
+
+        asset_server.add(image)
     }
 }
 
 fn update_gradient_size(
     assets: Res<Assets<Image>>,
     mut gradient_trans: Query<(&mut Transform, &Handle<Image>), With<GradientBackground>>,
-    single_camera: Query<&Camera>,
+    single_camera: Query<(&Camera, &OrthographicProjection)>,
 ) {
-    if let Ok(single_camera) = single_camera.get_single() {
+    if let Ok((single_camera, projection)) = single_camera.get_single() {
+        // Zooming out shows more of the world, which the gradient has to keep covering
         if let Some(size) = single_camera.physical_viewport_size() {
             gradient_trans
                 .iter_mut()
                 .for_each(|(mut trans, sprite_image)| {
                     if let Some(image) = assets.get(sprite_image) {
-                        trans.scale = (Vec2::new(size.x as f32, size.y as f32) / image.size_f32())
-                            .extend(0.0);
+                        trans.scale = (Vec2::new(size.x as f32, size.y as f32) * projection.scale
+                            / image.size_f32())
+                        .extend(0.0);
                     }
                 });
         }
@@ -70,13 +68,18 @@ fn update_gradient(
     mut loading_handle: Local<Option<Handle<Image>>>,
     // The seed to add to slowly via game state
     mut seed: Local<u32>,
-    // Game state used to accelerate the seed
-    game_state: Res<GameState>,
+    // Games being played, the highest multiplier accelerating the seed
+    boards: Query<&GameState, With<Board>>,
 ) {
     *time_passed += time.delta_seconds() * 5.0;
 
-    *seed +=
-        (time.delta_seconds() * game_state.data_board.score().current_mult()).floor() as u32 * 100;
+    let mult = boards
+        .iter()
+        .map(|game_state| game_state.session.board().score().current_mult())
+        .reduce(f32::max)
+        .unwrap_or(1.0);
+
+    *seed += (time.delta_seconds() * mult).floor() as u32 * 100;
 
     if *time_passed >= 0.1 {
         *loading_handle = Some(gradient_sprite.single().1.load(&asset_server, *seed))
@@ -114,6 +117,9 @@ impl Plugin for BackgroundPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_gradient)
             .add_systems(Update, update_gradient_size)
-            .add_systems(FixedUpdate, update_gradient);
+            .add_systems(
+                FixedUpdate,
+                update_gradient.run_if(in_state(PauseState::Running)),
+            );
     }
 }
@@ -0,0 +1,483 @@
+use std::{collections::BTreeMap, io::ErrorKind, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::persist::{self, PersistError};
+
+use super::{
+    board::Player,
+    ui::{ControlIntention, ControlPlatform},
+};
+
+/// A key or gamepad button that can be bound to a [`ControlIntention`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+}
+
+impl InputBinding {
+    /// Whether the input is shown in hints for a platform, keys on PC and buttons on gamepads
+    fn shown_on(&self, platform: ControlPlatform) -> bool {
+        match self {
+            InputBinding::Key(_) => platform == ControlPlatform::Pc,
+            InputBinding::Button(_) => platform != ControlPlatform::Pc,
+        }
+    }
+
+    /// Short name of the input, for when there's no glyph for it
+    pub fn label(&self) -> String {
+        match self {
+            InputBinding::Key(key) => {
+                let name = format!("{key:?}");
+                ["Key", "Digit", "Arrow"]
+                    .into_iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+            InputBinding::Button(button) => match button {
+                GamepadButtonType::South => String::from("A"),
+                GamepadButtonType::East => String::from("B"),
+                GamepadButtonType::West => String::from("X"),
+                GamepadButtonType::North => String::from("Y"),
+                GamepadButtonType::LeftTrigger => String::from("LB"),
+                GamepadButtonType::RightTrigger => String::from("RB"),
+                GamepadButtonType::LeftTrigger2 => String::from("LT"),
+                GamepadButtonType::RightTrigger2 => String::from("RT"),
+                GamepadButtonType::DPadUp => String::from("Up"),
+                GamepadButtonType::DPadDown => String::from("Down"),
+                GamepadButtonType::DPadLeft => String::from("Left"),
+                GamepadButtonType::DPadRight => String::from("Right"),
+                button => format!("{button:?}"),
+            },
+        }
+    }
+
+    /// Path to the input's glyph for a platform, if there is one
+    pub fn glyph(&self, platform: ControlPlatform) -> Option<&'static str> {
+        let path = match (platform, self) {
+            (ControlPlatform::Pc, InputBinding::Key(key)) => match key {
+                KeyCode::KeyW => "sprite/controls/pc/keyboard_w.png",
+                KeyCode::KeyS => "sprite/controls/pc/keyboard_s.png",
+                KeyCode::KeyA => "sprite/controls/pc/keyboard_a.png",
+                KeyCode::KeyD => "sprite/controls/pc/keyboard_d.png",
+                KeyCode::KeyE => "sprite/controls/pc/keyboard_e.png",
+                KeyCode::KeyR => "sprite/controls/pc/keyboard_r.png",
+                _ => return None,
+            },
+            (ControlPlatform::Steamdeck, InputBinding::Button(button)) => match button {
+                GamepadButtonType::DPadUp => {
+                    "sprite/controls/steamdeck/steamdeck_dpad_up_outline.png"
+                }
+                GamepadButtonType::DPadDown => {
+                    "sprite/controls/steamdeck/steamdeck_dpad_down_outline.png"
+                }
+                GamepadButtonType::LeftTrigger => {
+                    "sprite/controls/steamdeck/steamdeck_button_l1.png"
+                }
+                GamepadButtonType::RightTrigger => {
+                    "sprite/controls/steamdeck/steamdeck_button_r1.png"
+                }
+                GamepadButtonType::South => "sprite/controls/steamdeck/steamdeck_button_a.png",
+                _ => return None,
+            },
+            (ControlPlatform::Xbox, InputBinding::Button(button)) => match button {
+                GamepadButtonType::DPadUp => "sprite/controls/xbox/xbox_dpad_up_outline.png",
+                GamepadButtonType::DPadDown => "sprite/controls/xbox/xbox_dpad_down_outline.png",
+                GamepadButtonType::LeftTrigger => "sprite/controls/xbox/xbox_lb.png",
+                GamepadButtonType::RightTrigger => "sprite/controls/xbox/xbox_rb.png",
+                GamepadButtonType::South => "sprite/controls/xbox/xbox_button_a.png",
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(path)
+    }
+}
+
+/// Which player a key plays for when two share the keyboard in versus.
+/// The second player gets the arrows, numpad and everything right of the middle of the keyboard.
+pub fn keyboard_half(key: KeyCode) -> Player {
+    match key {
+        KeyCode::ArrowUp
+        | KeyCode::ArrowDown
+        | KeyCode::ArrowLeft
+        | KeyCode::ArrowRight
+        | KeyCode::ShiftRight
+        | KeyCode::ControlRight
+        | KeyCode::AltRight
+        | KeyCode::SuperRight
+        | KeyCode::Enter
+        | KeyCode::Backspace
+        | KeyCode::Backslash
+        | KeyCode::BracketLeft
+        | KeyCode::BracketRight
+        | KeyCode::Semicolon
+        | KeyCode::Quote
+        | KeyCode::Comma
+        | KeyCode::Period
+        | KeyCode::Slash
+        | KeyCode::Minus
+        | KeyCode::Equal
+        | KeyCode::KeyY
+        | KeyCode::KeyU
+        | KeyCode::KeyI
+        | KeyCode::KeyO
+        | KeyCode::KeyP
+        | KeyCode::KeyH
+        | KeyCode::KeyJ
+        | KeyCode::KeyK
+        | KeyCode::KeyL
+        | KeyCode::KeyN
+        | KeyCode::KeyM
+        | KeyCode::Digit6
+        | KeyCode::Digit7
+        | KeyCode::Digit8
+        | KeyCode::Digit9
+        | KeyCode::Digit0
+        | KeyCode::Insert
+        | KeyCode::Delete
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::PageUp
+        | KeyCode::PageDown => Player::Two,
+        KeyCode::NumLock
+        | KeyCode::Numpad0
+        | KeyCode::Numpad1
+        | KeyCode::Numpad2
+        | KeyCode::Numpad3
+        | KeyCode::Numpad4
+        | KeyCode::Numpad5
+        | KeyCode::Numpad6
+        | KeyCode::Numpad7
+        | KeyCode::Numpad8
+        | KeyCode::Numpad9
+        | KeyCode::NumpadAdd
+        | KeyCode::NumpadSubtract
+        | KeyCode::NumpadMultiply
+        | KeyCode::NumpadDivide
+        | KeyCode::NumpadDecimal
+        | KeyCode::NumpadEnter => Player::Two,
+        _ => Player::One,
+    }
+}
+
+/// Which keys and buttons trigger each [`ControlIntention`]
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    bindings: BTreeMap<ControlIntention, Vec<InputBinding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let bindings = ControlIntention::iter()
+            .map(|intention| {
+                let (keys, buttons): (&[KeyCode], &[GamepadButtonType]) = match intention {
+                    ControlIntention::ShiftUp => (
+                        &[KeyCode::KeyW, KeyCode::ArrowUp],
+                        &[GamepadButtonType::DPadUp],
+                    ),
+                    ControlIntention::ShiftDown => (
+                        &[KeyCode::KeyS, KeyCode::ArrowDown],
+                        &[GamepadButtonType::DPadDown],
+                    ),
+                    ControlIntention::RotateLeft => (
+                        &[KeyCode::KeyA, KeyCode::ArrowLeft],
+                        &[GamepadButtonType::LeftTrigger],
+                    ),
+                    ControlIntention::RotateRight => (
+                        &[KeyCode::KeyD, KeyCode::ArrowRight],
+                        &[GamepadButtonType::RightTrigger],
+                    ),
+                    ControlIntention::Flip => {
+                        (&[KeyCode::KeyF, KeyCode::Slash], &[GamepadButtonType::West])
+                    }
+                    ControlIntention::SkipRotation => (
+                        &[KeyCode::Space, KeyCode::Enter],
+                        &[GamepadButtonType::East],
+                    ),
+                    ControlIntention::RankBoost => (
+                        &[KeyCode::KeyE, KeyCode::ControlRight],
+                        &[GamepadButtonType::South],
+                    ),
+                    ControlIntention::Hold => (
+                        &[KeyCode::KeyC, KeyCode::ShiftLeft, KeyCode::ShiftRight],
+                        &[GamepadButtonType::LeftTrigger2],
+                    ),
+                    ControlIntention::Restart => (&[KeyCode::KeyR], &[GamepadButtonType::Select]),
+                    ControlIntention::Pause => (
+                        &[KeyCode::Escape, KeyCode::KeyP],
+                        &[GamepadButtonType::Start],
+                    ),
+                    ControlIntention::Hint => (&[KeyCode::KeyH], &[GamepadButtonType::North]),
+                };
+
+                let inputs = keys
+                    .iter()
+                    .copied()
+                    .map(InputBinding::Key)
+                    .chain(buttons.iter().copied().map(InputBinding::Button))
+                    .collect();
+
+                (intention, inputs)
+            })
+            .collect();
+
+        Self { bindings }
+    }
+}
+
+impl ActionMap {
+    /// Every input bound to an intention
+    pub fn bindings(&self, intention: ControlIntention) -> &[InputBinding] {
+        self.bindings
+            .get(&intention)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Every intention an input is bound to
+    pub fn intentions(&self, input: InputBinding) -> impl Iterator<Item = ControlIntention> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, inputs)| inputs.contains(&input))
+            .map(|(intention, _)| *intention)
+    }
+
+    /// The first input bound to an intention that can be shown for a platform
+    pub fn hint(
+        &self,
+        intention: ControlIntention,
+        platform: ControlPlatform,
+    ) -> Option<InputBinding> {
+        self.bindings(intention)
+            .iter()
+            .copied()
+            .find(|input| input.shown_on(platform))
+    }
+
+    /// Adds an input to an intention, taking it off any other intention so a press only ever does one thing
+    pub fn bind(&mut self, intention: ControlIntention, input: InputBinding) {
+        for inputs in self.bindings.values_mut() {
+            inputs.retain(|x| *x != input);
+        }
+
+        self.bindings.entry(intention).or_default().push(input);
+    }
+
+    /// Removes every input from an intention
+    pub fn clear(&mut self, intention: ControlIntention) {
+        self.bindings.entry(intention).or_default().clear();
+    }
+
+    /// Gives intentions that have no entry their default bindings. Cleared intentions are left unbound,
+    /// and defaults already bound to another intention are left off, so a press only ever does one thing
+    fn fill_defaults(&mut self) {
+        for (intention, inputs) in ActionMap::default().bindings {
+            if self.bindings.contains_key(&intention) {
+                continue;
+            }
+
+            let unbound = inputs
+                .into_iter()
+                .filter(|input| self.intentions(*input).next().is_none())
+                .collect();
+            self.bindings.insert(intention, unbound);
+        }
+    }
+}
+
+/// Where the action map is saved
+fn bindings_path() -> PathBuf {
+    persist::config_dir().join("controls.ron")
+}
+
+/// Loads the saved action map, falling back to the defaults if one hasn't been saved yet.
+/// Intentions missing from the file, like ones added since it was saved, keep their default bindings.
+pub fn load_action_map() -> Result<ActionMap, PersistError> {
+    let mut action_map: ActionMap = match persist::load_ron(&bindings_path()) {
+        Err(PersistError::Io(err)) if err.kind() == ErrorKind::NotFound => {
+            return Ok(ActionMap::default())
+        }
+        result => result?,
+    };
+
+    action_map.fill_defaults();
+
+    Ok(action_map)
+}
+
+/// Writes the action map file
+pub fn save_action_map(action_map: &ActionMap) {
+    match persist::save_ron(&bindings_path(), action_map) {
+        Ok(()) => info!("Saved controls to {}", bindings_path().display()),
+        Err(err) => error!("{err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{gamepad::GamepadButtonType, keyboard::KeyCode};
+    use strum::IntoEnumIterator;
+
+    use crate::game::ui::{ControlIntention, ControlPlatform};
+
+    use super::{ActionMap, InputBinding};
+
+    #[test]
+    fn verify_defaults() {
+        let action_map = ActionMap::default();
+
+        // Every intention can be triggered from both the keyboard and a gamepad
+        for intention in ControlIntention::iter() {
+            assert!(action_map.hint(intention, ControlPlatform::Pc).is_some());
+            assert!(action_map.hint(intention, ControlPlatform::Xbox).is_some());
+        }
+
+        // No input is bound to more than one intention
+        for intention in ControlIntention::iter() {
+            for input in action_map.bindings(intention) {
+                assert_eq!(action_map.intentions(*input).count(), 1, "{input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn verify_intentions() {
+        let action_map = ActionMap::default();
+
+        assert_eq!(
+            action_map
+                .intentions(InputBinding::Key(KeyCode::KeyA))
+                .collect::<Vec<_>>(),
+            vec![ControlIntention::RotateLeft]
+        );
+        assert_eq!(
+            action_map
+                .intentions(InputBinding::Button(GamepadButtonType::Start))
+                .collect::<Vec<_>>(),
+            vec![ControlIntention::Pause]
+        );
+
+        // Unbound inputs do nothing
+        assert_eq!(
+            action_map
+                .intentions(InputBinding::Key(KeyCode::KeyZ))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn verify_bind() {
+        let mut action_map = ActionMap::default();
+        let key = InputBinding::Key(KeyCode::KeyA);
+
+        // Taken off rotating left, as a press only ever does one thing
+        action_map.bind(ControlIntention::Flip, key);
+        assert_eq!(
+            action_map.intentions(key).collect::<Vec<_>>(),
+            vec![ControlIntention::Flip]
+        );
+        assert!(!action_map
+            .bindings(ControlIntention::RotateLeft)
+            .contains(&key));
+        assert_eq!(
+            action_map.bindings(ControlIntention::Flip).last(),
+            Some(&key)
+        );
+
+        // Binding it again doesn't add it twice
+        action_map.bind(ControlIntention::Flip, key);
+        assert_eq!(
+            action_map
+                .bindings(ControlIntention::Flip)
+                .iter()
+                .filter(|x| **x == key)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn verify_clear() {
+        let mut action_map = ActionMap::default();
+
+        action_map.clear(ControlIntention::Hold);
+        assert!(action_map.bindings(ControlIntention::Hold).is_empty());
+        assert_eq!(
+            action_map.hint(ControlIntention::Hold, ControlPlatform::Pc),
+            None
+        );
+        assert_eq!(
+            action_map
+                .intentions(InputBinding::Key(KeyCode::KeyC))
+                .count(),
+            0
+        );
+
+        // Other intentions keep their bindings
+        assert_eq!(
+            action_map.bindings(ControlIntention::Pause),
+            ActionMap::default().bindings(ControlIntention::Pause)
+        );
+    }
+
+    #[test]
+    fn verify_fill_defaults() {
+        // Saved before holding was added, with pausing cleared
+        let mut action_map: ActionMap =
+            ron::from_str("(bindings: {ShiftUp: [Key(KeyI)], Pause: []})").unwrap();
+        action_map.fill_defaults();
+
+        let defaults = ActionMap::default();
+
+        // Saved bindings are kept, even cleared ones
+        assert_eq!(
+            action_map.bindings(ControlIntention::ShiftUp),
+            &[InputBinding::Key(KeyCode::KeyI)]
+        );
+        assert!(action_map.bindings(ControlIntention::Pause).is_empty());
+
+        // Missing intentions get their defaults
+        assert_eq!(
+            action_map.bindings(ControlIntention::Hold),
+            defaults.bindings(ControlIntention::Hold)
+        );
+        assert!(ControlIntention::iter()
+            .filter(|x| !matches!(x, ControlIntention::ShiftUp | ControlIntention::Pause))
+            .all(|x| action_map.bindings(x) == defaults.bindings(x)));
+    }
+
+    #[test]
+    fn verify_fill_defaults_taken() {
+        // Saved before holding was added, with its default key since bound to restarting
+        let key = InputBinding::Key(KeyCode::KeyC);
+        let mut action_map: ActionMap =
+            ron::from_str("(bindings: {Restart: [Key(KeyC)]})").unwrap();
+        action_map.fill_defaults();
+
+        // The key stays with restarting, and holding gets the rest of its defaults
+        assert_eq!(
+            action_map.intentions(key).collect::<Vec<_>>(),
+            vec![ControlIntention::Restart]
+        );
+        assert_eq!(
+            action_map.bindings(ControlIntention::Hold),
+            ActionMap::default()
+                .bindings(ControlIntention::Hold)
+                .iter()
+                .copied()
+                .filter(|x| *x != key)
+                .collect::<Vec<_>>()
+        );
+        assert!(action_map
+            .hint(ControlIntention::Hold, ControlPlatform::Xbox)
+            .is_some());
+    }
+}
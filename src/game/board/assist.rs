@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::{
+    game::{
+        controls::{
+            FlipPressed, HintPressed, RotateLeftPressed, RotateRightPressed, SkipRotationPressed,
+        },
+        replay::ReplayPlayback,
+        settings::{Assist, GameSettings},
+    },
+    logic::ai::{Rotation, Solver},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    rotate::RotateBoard,
+    state::{GameMode, GameState},
+    Board, Player,
+};
+
+/// Seconds auto-play waits on each turn, so it can be followed
+const AUTOPLAY_DELAY: f32 = 0.4;
+
+/// Picks a rotation with the solver and presses it for the player, either on every turn or when a hint is asked for
+#[allow(clippy::too_many_arguments)]
+fn drive_assist(
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+    mut hint_pressed: EventReader<HintPressed>,
+    mut rotate_left: EventWriter<RotateLeftPressed>,
+    mut rotate_right: EventWriter<RotateRightPressed>,
+    mut flip: EventWriter<FlipPressed>,
+    mut skip_rotation: EventWriter<SkipRotationPressed>,
+    // Versus is played by the players on their own
+    mut board: Query<(&Player, &mut GameState, Has<RotateBoard>), With<Board>>,
+    mut wait: Local<f32>,
+    solver: Local<Solver>,
+) {
+    let hinted = hint_pressed.read().next().is_some();
+    hint_pressed.clear();
+
+    let Ok((player, mut game_state, rotating)) = board.get_single_mut() else {
+        return;
+    };
+
+    // Only between turns, once the last one has finished animating
+    let idle = game_state.mode == GameMode::Playing
+        && game_state.enable_input
+        && !game_state.dropping
+        && !game_state.session.mid_turn()
+        && game_state.queued_rotations.is_empty()
+        && !rotating;
+
+    if !idle {
+        *wait = 0.0;
+        return;
+    }
+
+    let play = match settings.assist {
+        Assist::Off => false,
+        Assist::Hint => hinted,
+        Assist::AutoPlay => {
+            *wait += time.delta_seconds();
+            *wait >= AUTOPLAY_DELAY
+        }
+    };
+
+    if !play {
+        return;
+    }
+    *wait = 0.0;
+
+    match solver.best_rotation_for(&game_state.session, &Rotation::iter().collect::<Vec<_>>()) {
+        Some(Rotation::Left) => {
+            rotate_left.send(RotateLeftPressed(*player));
+        }
+        Some(Rotation::Right) => {
+            rotate_right.send(RotateRightPressed(*player));
+        }
+        Some(Rotation::Flip) => {
+            flip.send(FlipPressed(*player));
+        }
+        Some(Rotation::None) => {
+            skip_rotation.send(SkipRotationPressed(*player));
+        }
+        // Nothing can be placed either way
+        None => return,
+    }
+
+    game_state.assisted = true;
+}
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            drive_assist.run_if(
+                in_state(AppState::InGame)
+                    .and_then(in_state(PauseState::Running))
+                    .and_then(not(resource_exists::<ReplayPlayback>)),
+            ),
+        );
+    }
+}
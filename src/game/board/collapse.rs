@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::{
+    logic::{board::TileMove, tile::Tile},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    effects::TranslateEffect,
+    sprite::{tile_tint, BoardSprites},
+    state::{BoardTile, GameState},
+    tile_dimensions, Board,
+};
+
+/// Tiles slid across a board by gravity after a clear
+#[derive(Event)]
+pub struct TilesCollapsed(pub Entity, pub Vec<(TileMove, Tile)>);
+
+/// A tile sliding to its new place on the board. The board tile it's heading to stays open until it arrives.
+#[derive(Component)]
+pub struct CollapsingTile {
+    pub x: u8,
+    pub y: u8,
+}
+
+fn handle_collapse_animation(
+    mut commands: Commands,
+    mut tiles_collapsed: EventReader<TilesCollapsed>,
+    boards: Query<&GameState, With<Board>>,
+    board_tiles: Query<(&Transform, &BoardTile, &Parent)>,
+    sprites: Res<BoardSprites>,
+) {
+    for TilesCollapsed(board, moves) in tiles_collapsed.read() {
+        let Ok(game_state) = boards.get(*board) else {
+            continue;
+        };
+
+        let (_, scale) = tile_dimensions(game_state.session.config());
+        let find_tile = |(x, y): (usize, usize)| {
+            board_tiles
+                .iter()
+                .find(|(_, tile, parent)| {
+                    parent.get() == *board && tile.x as usize == x && tile.y as usize == y
+                })
+                .map(|(trans, _, _)| trans.translation.truncate())
+        };
+
+        for (tile_move, tile) in moves {
+            let (Some(from), Some(to)) = (find_tile(tile_move.from), find_tile(tile_move.to))
+            else {
+                continue;
+            };
+
+            let ent = commands
+                .spawn(SpriteBundle {
+                    texture: sprites.closed.clone(),
+                    sprite: Sprite {
+                        color: tile_tint(*tile),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(from.extend(2.0))
+                        .with_scale(scale.extend(1.0)),
+                    ..default()
+                })
+                .insert(TranslateEffect::new(from, to, 0.2).delete_on_complete())
+                .insert(CollapsingTile {
+                    x: tile_move.to.0 as u8,
+                    y: tile_move.to.1 as u8,
+                })
+                .id();
+
+            commands.entity(*board).add_child(ent);
+        }
+    }
+}
+
+pub struct CollapseAnimationPlugin;
+
+impl Plugin for CollapseAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TilesCollapsed>().add_systems(
+            Update,
+            handle_collapse_animation
+                .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
+        );
+    }
+}
@@ -1,85 +1,100 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::{settings::GameSettings, spawn::SpawnTile},
-    state::AppState,
+    game::{spawn::SpawnTile, GameElement},
+    state::{AppState, PauseState},
 };
 
 use super::{
     effects::TranslateEffect,
     rotate::DropBlockEvent,
-    sprite::BoardSprites,
+    sprite::{tile_tint, BoardSprites},
     state::{BoardTile, GameState},
-    tile_dimensions,
+    tile_dimensions, Board, Player,
 };
 
-#[derive(Event, Default)]
-pub struct DropAnimation;
+/// Starts dropping a board's next tile in from its spawner
+#[derive(Event)]
+pub struct DropAnimation(pub Entity);
 
+/// A tile flying from its spawner onto the board it's dropping into
 #[derive(Component)]
-pub struct DroppingAnimationTile;
+pub struct DroppingAnimationTile(pub Entity);
 
 fn handle_dropping_animation_setup(
     mut commands: Commands,
-    mut game_state: ResMut<GameState>,
-    spawn_tiles: Query<(&Transform, &SpawnTile), Without<BoardTile>>,
-    board_tiles: Query<(&Transform, &BoardTile), Without<SpawnTile>>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
+    spawn_tiles: Query<(&Transform, &SpawnTile, &Player), Without<BoardTile>>,
+    board_tiles: Query<(&GlobalTransform, &BoardTile, &Parent), Without<SpawnTile>>,
     sprites: Res<BoardSprites>,
-    game_settings: Res<GameSettings>,
     mut drop_animation: EventReader<DropAnimation>,
     mut block_drop: EventWriter<DropBlockEvent>,
 ) {
-    if drop_animation.is_empty() {
-        return;
-    }
-    drop_animation.clear();
+    for DropAnimation(board) in drop_animation.read() {
+        let Ok((player, mut game_state)) = boards.get_mut(*board) else {
+            continue;
+        };
 
-    // Configure translate effect at same time, since will be handled before drop occurs
-    // Precalc move, no effect if not valid move
-    if let Ok((x, y)) = game_state.data_board.clone().place(game_state.drop()) {
-        let board_tile_trans = board_tiles
-            .iter()
-            .find(|(_, tile)| tile.x as usize == x && tile.y as usize == y);
-        let spawn_tile_trans = spawn_tiles
-            .iter()
-            .find(|(_, tile)| tile.0 == game_state.drop());
-        if let Some((spawner_trans, _)) = spawn_tile_trans {
-            if let Some((board_trans, _)) = board_tile_trans {
-                let (_, scale) = tile_dimensions(&game_settings);
-                game_state.dropping = true;
-                commands
-                    .spawn(SpriteBundle {
-                        texture: sprites.closed.clone(),
-                        transform: spawner_trans.clone().with_scale(scale.extend(1.0)),
-                        ..default()
-                    })
-                    .insert(
-                        TranslateEffect::new(
-                            spawner_trans.translation.clone().truncate(),
-                            board_trans.translation.clone().truncate(),
-                            0.1,
+        // Configure translate effect at same time, since will be handled before drop occurs
+        // Precalc move, no effect if not valid move
+        // The display board is what the board tiles are laid out by, the board's rotation is then applied on top
+        if let Some((x, y)) = game_state
+            .session
+            .board()
+            .landing(game_state.session.drop())
+        {
+            let board_tile_trans = board_tiles.iter().find(|(_, tile, parent)| {
+                parent.get() == *board && tile.x as usize == x && tile.y as usize == y
+            });
+            let spawn_tile_trans = spawn_tiles.iter().find(|(_, tile, spawner_player)| {
+                *spawner_player == player && tile.0 == game_state.session.drop()
+            });
+            if let Some((spawner_trans, _, _)) = spawn_tile_trans {
+                if let Some((board_trans, _, _)) = board_tile_trans {
+                    let (_, scale) = tile_dimensions(game_state.session.config());
+                    game_state.dropping = true;
+                    commands
+                        .spawn(SpriteBundle {
+                            texture: sprites.closed.clone(),
+                            sprite: Sprite {
+                                color: tile_tint(game_state.session.next_tile()),
+                                ..default()
+                            },
+                            transform: (*spawner_trans).with_scale(scale.extend(1.0)),
+                            ..default()
+                        })
+                        .insert(
+                            TranslateEffect::new(
+                                spawner_trans.translation.truncate(),
+                                board_trans.translation().truncate(),
+                                0.1,
+                            )
+                            .delete_on_complete(),
                         )
-                        .delete_on_complete(),
-                    )
-                    .insert(DroppingAnimationTile);
+                        .insert((DroppingAnimationTile(*board), GameElement));
 
-                return;
+                    continue;
+                }
             }
         }
-    }
 
-    // Fall through case, start block drop
-    block_drop.send_default();
+        // Fall through case, start block drop
+        block_drop.send(DropBlockEvent(*board));
+    }
 }
 
 fn handle_transition_to_block_drop_event(
-    game_state: Res<GameState>,
-    inflight_tile: Query<Entity, With<DroppingAnimationTile>>,
+    boards: Query<(Entity, &GameState), With<Board>>,
+    inflight_tiles: Query<&DroppingAnimationTile>,
     mut block_drop: EventWriter<DropBlockEvent>,
 ) {
-    if game_state.dropping {
-        if inflight_tile.is_empty() {
-            block_drop.send_default();
+    for (board, game_state) in &boards {
+        let inflight = inflight_tiles
+            .iter()
+            .any(|DroppingAnimationTile(target)| *target == board);
+
+        if game_state.dropping && !inflight {
+            block_drop.send(DropBlockEvent(board));
         }
     }
 }
@@ -94,7 +109,7 @@ impl Plugin for DroppingAnimationPlugin {
                 handle_dropping_animation_setup,
                 handle_transition_to_block_drop_event,
             )
-                .run_if(in_state(AppState::InGame)),
+                .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
         );
     }
 }
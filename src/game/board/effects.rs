@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use crate::{audio::PlaySoundEffect, state::AppState};
+use crate::{
+    audio::PlaySoundEffect,
+    state::{AppState, PauseState},
+};
 
 #[derive(Component)]
 pub struct TranslateEffect {
@@ -146,7 +149,8 @@ impl Plugin for EffectsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (handle_elastic_force, handle_translate_effect).run_if(in_state(AppState::InGame)),
+            (handle_elastic_force, handle_translate_effect)
+                .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
         );
     }
 }
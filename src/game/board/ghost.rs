@@ -0,0 +1,186 @@
+use bevy::{prelude::*, utils::HashMap};
+use nalgebra::DMatrix;
+use strum::IntoEnumIterator;
+
+use crate::{
+    game::{settings::GameSettings, ui::DEFAULT_FONT_PATH},
+    logic::{
+        ai::{self, Rotation},
+        tile::Tile,
+    },
+    state::AppState,
+};
+
+use super::{
+    sprite::{tile_tint, BoardSprites},
+    state::{start_game, BoardTile, GameMode, GameState},
+    tile_dimensions, Board,
+};
+
+/// Opacity of the ghost for the board as it is
+const CURRENT_GHOST_ALPHA: f32 = 0.5;
+/// Opacity of the ghosts for each rotation, fainter as they need an input first
+const ROTATED_GHOST_ALPHA: f32 = 0.3;
+
+/// A translucent tile previewing where the next drop will land after one of the rotations
+#[derive(Component)]
+pub struct GhostTile(Rotation);
+
+impl GhostTile {
+    fn label(&self) -> &'static str {
+        match self.0 {
+            Rotation::None => "",
+            Rotation::Left => "L",
+            Rotation::Right => "R",
+            Rotation::Flip => "F",
+        }
+    }
+
+    fn alpha(&self) -> f32 {
+        match self.0 {
+            Rotation::None => CURRENT_GHOST_ALPHA,
+            Rotation::Left | Rotation::Right | Rotation::Flip => ROTATED_GHOST_ALPHA,
+        }
+    }
+}
+
+/// Adds a hidden ghost for each rotation to every board, so they turn along with it
+fn setup_ghost_tiles(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sprites: Res<BoardSprites>,
+    boards: Query<(Entity, &GameState), With<Board>>,
+) {
+    for (board, game_state) in &boards {
+        let (_, scale) = tile_dimensions(game_state.session.config());
+
+        for rotation in Rotation::iter() {
+            let ghost = GhostTile(rotation);
+            let label = ghost.label();
+
+            let ent = commands
+                .spawn(SpriteBundle {
+                    texture: sprites.closed.clone(),
+                    transform: Transform::from_scale(scale.extend(1.0)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                })
+                .insert(ghost)
+                .with_children(|builder| {
+                    builder.spawn(Text2dBundle {
+                        text: Text::from_section(
+                            label,
+                            TextStyle {
+                                font: asset_server.load(DEFAULT_FONT_PATH),
+                                font_size: 32.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                        ..default()
+                    });
+                })
+                .id();
+
+            commands.entity(board).add_child(ent);
+        }
+    }
+}
+
+/// What a board's ghosts were last placed for
+struct GhostsPlaced {
+    shown: bool,
+    /// Inputs applied so far, as every turn of the board and move of the drop is one
+    inputs: usize,
+    /// The board's tiles, as garbage can be pushed in without an input
+    tiles: DMatrix<Tile>,
+}
+
+/// Moves each ghost onto the tile the next drop would land on after its rotation,
+/// hiding it when the drop can't be placed or can't be rotated for.
+/// Ghosts are only moved once the board, its drop or whether they're shown has changed.
+fn update_ghost_tiles(
+    settings: Res<GameSettings>,
+    boards: Query<(Entity, &GameState), With<Board>>,
+    board_tiles: Query<(&Transform, &BoardTile, &Parent), Without<GhostTile>>,
+    mut ghosts: Query<(
+        &GhostTile,
+        &Parent,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    mut placed: Local<HashMap<Entity, GhostsPlaced>>,
+) {
+    for (board, game_state) in &boards {
+        let session = &game_state.session;
+
+        // Only while the player is picking a rotation
+        let shown = settings.landing_preview
+            && game_state.mode == GameMode::Playing
+            && game_state.enable_input
+            && !game_state.dropping
+            && !session.mid_turn();
+
+        let unchanged = placed.get(&board).is_some_and(|placed| {
+            placed.shown == shown
+                && placed.inputs == session.history().len()
+                && placed.tiles == *session.board().board()
+        });
+        if unchanged {
+            continue;
+        }
+
+        placed.insert(
+            board,
+            GhostsPlaced {
+                shown,
+                inputs: session.history().len(),
+                tiles: session.board().board().clone(),
+            },
+        );
+
+        // Where each of the board's tiles is drawn, by display position
+        let tiles = board_tiles
+            .iter()
+            .filter(|(_, _, parent)| parent.get() == board)
+            .map(|(trans, tile, _)| ((tile.x as usize, tile.y as usize), trans.translation))
+            .collect::<HashMap<_, _>>();
+        let tile = session.next_tile();
+
+        for (ghost, _, mut trans, mut sprite, mut visibility) in ghosts
+            .iter_mut()
+            .filter(|(_, parent, _, _, _)| parent.get() == board)
+        {
+            let target = shown
+                .then(|| ai::landing(session.board(), ghost.0, session.drop()))
+                .flatten()
+                .and_then(|landing| tiles.get(&landing));
+
+            match target {
+                Some(translation) => {
+                    // Drawn over the board's tiles
+                    trans.translation = translation.truncate().extend(2.0);
+                    sprite.color = tile_tint(tile).with_a(ghost.alpha());
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
+
+pub struct GhostTilePlugin;
+
+impl Plugin for GhostTilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            setup_ghost_tiles.after(start_game),
+        )
+        .add_systems(
+            Update,
+            update_ghost_tiles.run_if(in_state(AppState::InGame)),
+        );
+    }
+}
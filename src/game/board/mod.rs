@@ -1,19 +1,30 @@
-use bevy::prelude::*;
-
-use crate::{game::board::state::BoardTile, state::AppState};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use strum::EnumIter;
+
+use crate::{
+    game::{
+        board::state::{BoardTile, GameState},
+        GameElement,
+    },
+    logic::session::SessionConfig,
+};
 
 use self::{
+    assist::AssistPlugin,
+    collapse::CollapseAnimationPlugin,
     dropping::DroppingAnimationPlugin,
     effects::EffectsPlugin,
+    ghost::GhostTilePlugin,
     rotate::RotateBoardPlugin,
     sprite::{BoardSprites, SpritePlugin},
     state::GameStatePlugin,
 };
 
-use super::settings::GameSettings;
-
+pub mod assist;
+pub mod collapse;
 pub mod dropping;
 pub mod effects;
+pub mod ghost;
 pub mod rotate;
 pub mod sprite;
 pub mod state;
@@ -21,46 +32,100 @@ pub mod state;
 pub const BOARD_DIM: f32 = 200.0;
 pub const SPRITE_WIDTH: f32 = 64.0;
 
-pub fn get_square_dim(settings: &GameSettings) -> f32 {
-    BOARD_DIM / settings.board_dim as f32
+/// The size of a single tile, fitting the longest side of the board into [`BOARD_DIM`]
+pub fn get_square_dim(config: &SessionConfig) -> f32 {
+    BOARD_DIM / config.width.max(config.height) as f32
 }
 
 #[derive(Component)]
 pub struct Board;
 
-pub fn tile_dimensions(game_settings: &GameSettings) -> (f32, Vec2) {
+/// Which seat a board, and everything shown for it, belongs to. Games played alone only have the first.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, EnumIter)]
+pub enum Player {
+    #[default]
+    One,
+    Two,
+}
+
+impl Player {
+    /// The player on the other board in a versus match
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+
+    /// The player's seat in a netplay match, 0 for the host and 1 for the peer that joined
+    pub fn seat(&self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Player::One => "Player 1",
+            Player::Two => "Player 2",
+        }
+    }
+}
+
+/// Looks up the board each player is playing on
+#[derive(SystemParam)]
+pub struct Boards<'w, 's> {
+    boards: Query<'w, 's, (&'static Player, &'static GameState), With<Board>>,
+}
+
+impl Boards<'_, '_> {
+    /// The game on a player's board, if they have one
+    pub fn get(&self, player: Player) -> Option<&GameState> {
+        self.boards
+            .iter()
+            .find(|(board_player, _)| **board_player == player)
+            .map(|(_, game_state)| game_state)
+    }
+
+    /// Every board's player and game
+    pub fn iter(&self) -> impl Iterator<Item = (Player, &GameState)> {
+        self.boards
+            .iter()
+            .map(|(player, game_state)| (*player, game_state))
+    }
+}
+
+pub fn tile_dimensions(config: &SessionConfig) -> (f32, Vec2) {
     // Calculate children, everything is center aligned in bevy
-    let square_dim = get_square_dim(&game_settings);
+    let square_dim = get_square_dim(config);
 
     let scale = Vec2::from_array([square_dim, square_dim]) / SPRITE_WIDTH;
 
     (square_dim, scale)
 }
 
-fn setup_board(
-    mut commands: Commands,
-    sprites: Res<BoardSprites>,
-    game_settings: Res<GameSettings>,
-) {
-    let ent = commands
-        .spawn(Board)
-        .insert((
-            GlobalTransform::default(),
-            InheritedVisibility::default(),
-            Transform::default(),
-        ))
-        .id();
-
-    let (square_dim, scale) = tile_dimensions(&game_settings);
+/// Spawns a player's board laid out from the game's config, with the game's state on it
+pub fn spawn_board(
+    commands: &mut Commands,
+    sprites: &BoardSprites,
+    player: Player,
+    game_state: GameState,
+    translation: Vec3,
+) -> Entity {
+    // The display board is never rotated, so it keeps the configured dimensions
+    let config = game_state.session.config();
+    let (square_dim, scale) = tile_dimensions(config);
 
     let mut children = Vec::new();
 
-    let offset = (BOARD_DIM / 2.0) - (square_dim / 2.0);
+    let x_offset = (config.width as f32 * square_dim / 2.0) - (square_dim / 2.0);
+    let y_offset = (config.height as f32 * square_dim / 2.0) - (square_dim / 2.0);
 
-    for y in 0..game_settings.board_dim {
-        for x in 0..game_settings.board_dim {
-            let sprite_x = x as f32 * square_dim - offset;
-            let sprite_y = -(y as f32 * square_dim) + offset;
+    for y in 0..config.height as u8 {
+        for x in 0..config.width as u8 {
+            let sprite_x = x as f32 * square_dim - x_offset;
+            let sprite_y = -(y as f32 * square_dim) + y_offset;
 
             debug!("{sprite_x}, {sprite_y}");
 
@@ -78,7 +143,15 @@ fn setup_board(
         }
     }
 
-    commands.entity(ent).insert_children(0, &children);
+    commands
+        .spawn((Board, player, game_state, GameElement))
+        .insert((
+            GlobalTransform::default(),
+            InheritedVisibility::default(),
+            Transform::from_translation(translation),
+        ))
+        .insert_children(0, &children)
+        .id()
 }
 
 pub struct BoardPlugin;
@@ -91,7 +164,9 @@ impl Plugin for BoardPlugin {
             GameStatePlugin,
             EffectsPlugin,
             DroppingAnimationPlugin,
-        ))
-        .add_systems(OnEnter(AppState::InGame), setup_board);
+            CollapseAnimationPlugin,
+            AssistPlugin,
+            GhostTilePlugin,
+        ));
     }
 }
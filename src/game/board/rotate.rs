@@ -1,17 +1,19 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 
 use crate::{
     audio::{PlaySoundEffect, SoundEffect},
-    game::controls::{MinusOffsetPressed, PlusOffsetPressed},
-    logic::insertion::InsertionDirection,
-    state::AppState,
+    game::controls::{HoldPressed, MinusOffsetPressed, PlusOffsetPressed},
+    logic::session::GameAction,
+    state::{AppState, PauseState},
 };
 
 use super::{
-    super::controls::{RotateLeftPressed, RotateRightPressed},
+    super::controls::{FlipPressed, RotateLeftPressed, RotateRightPressed, SkipRotationPressed},
     dropping::DropAnimation,
     state::GameState,
-    Board,
+    Board, Player,
 };
 
 #[derive(Component)]
@@ -56,136 +58,199 @@ impl RotateBoard {
     }
 }
 
+/// Seconds a rotation takes to animate
+const ROTATE_SECS: f32 = 0.5;
+/// Seconds before a turn played without rotating drops
+const SKIP_ROTATION_SECS: f32 = 0.1;
+
+/// Rotations pressed while the board is busy that are held until it's free again
+const MAX_QUEUED_ROTATIONS: usize = 2;
+
+/// Turns each board for the rotations its player pressed, holding onto rotations pressed mid-turn
+/// so they're played as soon as the turn has finished
+#[allow(clippy::too_many_arguments)]
 fn handle_rotate_events(
     mut commands: Commands,
-    mut game_state: ResMut<GameState>,
     mut rotate_left: EventReader<RotateLeftPressed>,
     mut rotate_right: EventReader<RotateRightPressed>,
+    mut flip: EventReader<FlipPressed>,
+    mut skip_rotation: EventReader<SkipRotationPressed>,
     mut sfx: EventWriter<PlaySoundEffect>,
-    board: Query<Entity, (With<Board>, Without<RotateBoard>)>,
+    mut boards: Query<(Entity, &Player, &mut GameState, Has<RotateBoard>), With<Board>>,
 ) {
-    let left_received = rotate_left.read().next().is_some();
-    rotate_left.clear();
-
-    let right_received = rotate_right.read().next().is_some();
-    rotate_right.clear();
-
-    // Can be disabled
-    if !game_state.enable_input {
-        return;
-    }
-
-    if let Ok(ent) = board.get_single() {
-        let angle = if left_received {
-            Some(-90.0)
-        } else if right_received {
-            Some(90.0)
+    let left_received = rotate_left
+        .read()
+        .map(|RotateLeftPressed(player)| *player)
+        .collect::<HashSet<_>>();
+    let right_received = rotate_right
+        .read()
+        .map(|RotateRightPressed(player)| *player)
+        .collect::<HashSet<_>>();
+    let flip_received = flip
+        .read()
+        .map(|FlipPressed(player)| *player)
+        .collect::<HashSet<_>>();
+    let skip_received = skip_rotation
+        .read()
+        .map(|SkipRotationPressed(player)| *player)
+        .collect::<HashSet<_>>();
+
+    for (ent, player, mut game_state, rotating) in &mut boards {
+        let pressed = if left_received.contains(player) {
+            Some(GameAction::RotateLeft)
+        } else if right_received.contains(player) {
+            Some(GameAction::RotateRight)
+        } else if flip_received.contains(player) {
+            Some(GameAction::Flip)
+        } else if skip_received.contains(player) {
+            Some(GameAction::SkipRotation)
         } else {
             None
         };
 
-        if let Some(angle) = angle {
-            sfx.send(PlaySoundEffect(SoundEffect::Rotate));
+        // Can be disabled, and the board can't turn under a tile that's mid-drop or partway through a turn
+        let ready = game_state.enable_input
+            && !game_state.dropping
+            && !game_state.session.mid_turn()
+            && !rotating;
 
-            commands
-                .entity(ent)
-                .insert(RotateBoard::new_from_current_angle(
-                    game_state.rotation_state,
-                    angle,
-                    0.5,
-                ));
+        if let Some(action) = pressed {
+            if game_state.queued_rotations.len() < MAX_QUEUED_ROTATIONS {
+                game_state.queued_rotations.push_back(action);
+            }
+        }
 
-            game_state.rotation_state += angle;
-            game_state.rotation_state = game_state.rotation_state % 360.0;
+        if !ready {
+            continue;
         }
+
+        let Some(action) = game_state.queued_rotations.pop_front() else {
+            continue;
+        };
+
+        start_rotation(&mut commands, ent, &mut game_state, action, &mut sfx);
+    }
+}
+
+/// Turns a board for a rotation or a turn played without one, starting its animation
+fn start_rotation(
+    commands: &mut Commands,
+    board: Entity,
+    game_state: &mut GameState,
+    action: GameAction,
+    sfx: &mut EventWriter<PlaySoundEffect>,
+) {
+    let (angle, secs) = match action {
+        GameAction::RotateLeft => (-90.0, ROTATE_SECS),
+        GameAction::RotateRight => (90.0, ROTATE_SECS),
+        GameAction::Flip => (180.0, ROTATE_SECS),
+        // Still goes through a turn of the animation, so the drop follows the same as any other turn
+        GameAction::SkipRotation => (0.0, SKIP_ROTATION_SECS),
+        _ => return,
+    };
+
+    // The logical board turns right away so inputs are recorded when pressed.
+    // The display board is unaffected, the animation catches the sprites up.
+    // Rotations can't fail
+    let _ = game_state.session.step(action);
+
+    if action != GameAction::SkipRotation {
+        sfx.send(PlaySoundEffect(SoundEffect::Rotate));
     }
+
+    commands
+        .entity(board)
+        .insert(RotateBoard::new_from_current_angle(
+            game_state.rotation_state,
+            angle,
+            secs,
+        ));
+
+    game_state.rotation_state += angle;
+    game_state.rotation_state %= 360.0;
 }
 
 fn rotate_board(
     mut commands: Commands,
     time: Res<Time>,
-    mut game_state: ResMut<GameState>,
-    mut board: Query<(Entity, &mut Transform, &mut RotateBoard), With<Board>>,
+    mut boards: Query<(Entity, &mut Transform, &mut RotateBoard), With<Board>>,
     mut drop_block: EventWriter<DropAnimation>,
 ) {
-    if let Ok((ent, mut trans, mut rotate_board)) = board.get_single_mut() {
+    for (ent, mut trans, mut rotate_board) in &mut boards {
         if let Some(degrees) = rotate_board.rotate(time.delta_seconds()) {
             trans.rotate_z(degrees);
         } else {
-            let mut angle = (rotate_board.target_degrees % 360.0).round() as i32;
-            if angle < 0 {
-                angle += 360;
-            }
-
-            let quat = match angle.abs() {
-                0 => Quat::from_xyzw(0.0, 0.0, 0.0, -1.0),
-                90 => Quat::from_xyzw(0.0, 0.0, 2.0_f32.sqrt() / 2.0, -2.0_f32.sqrt() / 2.0),
-                180 => Quat::from_xyzw(0.0, 0.0, 1.0, 0.0),
-                270 => Quat::from_xyzw(0.0, 0.0, -2.0_f32.sqrt() / 2.0, -2.0_f32.sqrt() / 2.0),
-                x => panic!("Unsure how to rotate {x}"),
-            };
-
-            trans.rotation = quat;
+            // Snaps to the nearest quarter turn so the lerp's error doesn't build up over the game
+            let angle = (rotate_board.target_degrees / 90.0).round() * 90.0;
+            trans.rotation = Quat::from_rotation_z(-angle.to_radians());
 
             commands.entity(ent).remove::<RotateBoard>();
 
-            if rotate_board.target_degrees > rotate_board.current_degrees {
-                game_state.data_board.rotate_right();
-            } else {
-                game_state.data_board.rotate_left();
-            }
-
-            drop_block.send_default();
+            drop_block.send(DropAnimation(ent));
         }
     }
 }
 
 fn offset(
-    mut state: ResMut<GameState>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
     mut plus_offset: EventReader<PlusOffsetPressed>,
     mut minus_offset: EventReader<MinusOffsetPressed>,
 ) {
-    let direction =
-        InsertionDirection::for_board_insertion(state.data_board.board(), state.next_drop).ok();
-
-    let plus_received = plus_offset.read().next().is_some();
-    plus_offset.clear();
-
-    let minus_received = minus_offset.read().next().is_some();
-    minus_offset.clear();
-
-    // Can be disabled
-    if !state.enable_input {
-        return;
-    }
+    let plus_received = plus_offset
+        .read()
+        .map(|PlusOffsetPressed(player)| *player)
+        .collect::<HashSet<_>>();
+    let minus_received = minus_offset
+        .read()
+        .map(|MinusOffsetPressed(player)| *player)
+        .collect::<HashSet<_>>();
+
+    for (player, mut state) in &mut boards {
+        // Can be disabled, and the turn's drops can't move once they've started falling
+        if !state.enable_input || state.dropping || state.session.mid_turn() {
+            continue;
+        }
 
-    let offset = if plus_received {
-        1
-    } else if minus_received {
-        -1
-    } else {
-        0
-    };
+        let action = if plus_received.contains(player) {
+            GameAction::ShiftUp
+        } else if minus_received.contains(player) {
+            GameAction::ShiftDown
+        } else {
+            continue;
+        };
 
-    if offset == 0 {
-        return;
+        // Shifting can't fail
+        let _ = state.session.step(action);
     }
+}
 
-    let oriented_offset = if let Some(direction) = direction {
-        match direction {
-            InsertionDirection::FromLeft | InsertionDirection::FromTop => offset,
-            InsertionDirection::FromRight | InsertionDirection::FromBottom => -offset,
+fn hold(
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
+    mut hold_pressed: EventReader<HoldPressed>,
+) {
+    let received = hold_pressed
+        .read()
+        .map(|HoldPressed(player)| *player)
+        .collect::<HashSet<_>>();
+
+    for (player, mut state) in &mut boards {
+        // Same as shifting, the slot can't change under drops that have started falling
+        if !received.contains(player)
+            || !state.enable_input
+            || state.dropping
+            || !state.session.can_hold()
+        {
+            continue;
         }
-    } else {
-        offset
-    };
 
-    state.offset += oriented_offset;
-    state.offset = state.offset.clamp(-1, 1);
+        // Swaps at most once per turn, and is otherwise ignored
+        let _ = state.session.step(GameAction::Hold);
+    }
 }
 
-#[derive(Event, Default)]
-pub struct DropBlockEvent;
+/// Drops the turn's tiles onto a board once it's finished turning
+#[derive(Event)]
+pub struct DropBlockEvent(pub Entity);
 
 pub struct RotateBoardPlugin;
 
@@ -193,8 +258,8 @@ impl Plugin for RotateBoardPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DropBlockEvent>().add_systems(
             Update,
-            (offset, (handle_rotate_events, rotate_board).chain())
-                .run_if(in_state(AppState::InGame)),
+            (offset, hold, (handle_rotate_events, rotate_board).chain())
+                .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
         );
     }
 }
@@ -2,15 +2,40 @@ use bevy::{
     app::{App, Plugin},
     asset::{AssetServer, Handle},
     ecs::system::Resource,
-    render::texture::Image,
+    render::{color::Color, texture::Image},
 };
 
+use crate::logic::tile::{Tile, TileColor};
+
 #[derive(Resource)]
 pub struct BoardSprites {
     pub open: Handle<Image>,
     pub closed: Handle<Image>,
 }
 
+/// Tint for spawners that can't take a tile
+pub const BLOCKED_TINT: Color = Color::rgb(0.9, 0.2, 0.2);
+/// Tint for spawners that will be blocked by their next tile
+pub const DANGER_TINT: Color = Color::rgb(1.0, 0.65, 0.2);
+/// Tint for the spawner of the held slot
+pub const HELD_TINT: Color = Color::rgb(0.3, 0.85, 0.9);
+
+/// Color a tile's sprite is tinted with. Empty tiles are left untinted.
+pub fn tile_tint(tile: Tile) -> Color {
+    match tile {
+        Tile::Empty => Color::WHITE,
+        Tile::Color(TileColor::Red) => Color::rgb(0.95, 0.4, 0.4),
+        Tile::Color(TileColor::Green) => Color::rgb(0.45, 0.9, 0.5),
+        Tile::Color(TileColor::Blue) => Color::rgb(0.45, 0.6, 1.0),
+        Tile::Color(TileColor::Yellow) => Color::rgb(1.0, 0.9, 0.4),
+        Tile::Wild => Color::rgb(0.85, 0.6, 1.0),
+        Tile::Bomb => Color::rgb(0.4, 0.4, 0.4),
+        Tile::Stone => Color::rgb(0.25, 0.22, 0.2),
+        Tile::Armored => Color::rgb(0.6, 0.55, 0.5),
+        Tile::Cracked => Color::rgb(0.8, 0.75, 0.7),
+    }
+}
+
 pub struct SpritePlugin;
 
 impl Plugin for SpritePlugin {
@@ -1,19 +1,38 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
-use rand::Rng;
+use strum::IntoEnumIterator;
 
 use crate::{
     audio::{PlaySoundEffect, SoundEffect},
     game::{
         board::effects::ElasticForce,
         controls::{RankBoostPressed, RestartPressed},
+        netplay::NetplayMatch,
+        puzzle::PuzzleAttempt,
+        replay::ReplayPlayback,
         settings::GameSettings,
         ui::ResetScoreboard,
+        versus::{seat_translation, VersusMatch},
+        GameElement,
+    },
+    logic::{
+        error::GameError,
+        insertion::InsertionDirection,
+        session::{GameAction, GameSession, SessionConfig, StepOutcome},
+        tile::Tile,
     },
-    logic::{board::GameBoard, error::GameError, insertion::InsertionDirection},
-    state::AppState,
+    state::{AppState, PauseState},
 };
 
-use super::{rotate::DropBlockEvent, sprite::BoardSprites, Board};
+use super::{
+    collapse::{CollapsingTile, TilesCollapsed},
+    dropping::{DropAnimation, DroppingAnimationTile},
+    rotate::{DropBlockEvent, RotateBoard},
+    spawn_board,
+    sprite::{tile_tint, BoardSprites},
+    Board, Player,
+};
 
 #[derive(Component)]
 pub struct BoardTile {
@@ -27,20 +46,33 @@ impl BoardTile {
     }
 }
 
+/// Lines cleared on a board by a single drop, including any chain it set off
+#[derive(Event)]
+pub struct LinesCleared {
+    pub player: Player,
+    pub lines: u32,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum GameMode {
     Playing,
     GameOver,
+    /// The game reached its mode's end condition, like finishing a sprint or running out the clock
+    Results,
 }
 
-#[derive(Debug, Resource)]
+impl GameMode {
+    /// Whether the game has ended, either way
+    pub fn is_over(&self) -> bool {
+        *self != GameMode::Playing
+    }
+}
+
+/// The game being played on a board, kept on the board's entity
+#[derive(Debug, Component)]
 pub struct GameState {
-    /// The current state of the game board
-    pub data_board: GameBoard,
-    /// Next drop placement
-    pub next_drop: usize,
-    /// Offset from next drop placement
-    pub offset: i8,
+    /// The current game being played, including the board and drop generator
+    pub session: GameSession,
     /// 0 - 360 degrees rotation state
     pub rotation_state: f32,
     /// Placement History
@@ -51,78 +83,100 @@ pub struct GameState {
     pub mode: GameMode,
     /// Dropping animation is playing
     pub dropping: bool,
+    /// Rotations pressed while the board was busy, played in order once it's free
+    pub queued_rotations: VecDeque<GameAction>,
+    /// The solver has played a turn, so the game isn't recorded on the leaderboard
+    pub assisted: bool,
+    /// Garbage lines sent by the opponent in versus, pushed in once the board is between turns
+    pub incoming_garbage: u32,
 }
 
 impl GameState {
-    pub fn update_next_drop(&mut self, settings: &GameSettings) -> usize {
-        let mut rng = rand::thread_rng();
-
-        self.next_drop = rng.gen_range(0..settings.board_dim as usize * 4);
-        self.offset = 0;
-
-        self.next_drop
-    }
-
-    pub fn new(n: usize) -> Self {
+    pub fn new(config: SessionConfig, seed: u64) -> Self {
         Self {
-            data_board: GameBoard::new(n).with_rows_clearing(),
-            next_drop: 0,
-            offset: 0,
+            session: GameSession::new(config, seed),
             rotation_state: 0.0,
             placement_history: Vec::new(),
             enable_input: true,
             mode: GameMode::Playing,
             dropping: false,
+            queued_rotations: VecDeque::new(),
+            assisted: false,
+            incoming_garbage: 0,
         }
     }
 
-    /// Calculated the drop location given the next drop and the offset
-    pub fn drop(&self) -> usize {
-        let max_index = (self.data_board.board().ncols() * 4).saturating_sub(1);
-        if self.next_drop == 0 && self.offset == -1 {
-            max_index
-        } else {
-            if self.next_drop == max_index && self.offset == 1 {
-                0
-            } else {
-                self.next_drop.saturating_add_signed(self.offset.into())
-            }
-        }
+    /// Ends the game, locking out any further input
+    pub fn game_over(&mut self) {
+        self.mode = GameMode::GameOver;
+        self.enable_input = false;
     }
 
-    pub fn place(&mut self) -> Result<(), GameError> {
-        let drop = self.drop();
+    /// Ends the game on its mode's terms, locking out any further input
+    pub fn finish(&mut self) {
+        self.mode = GameMode::Results;
+        self.enable_input = false;
+    }
 
-        self.data_board.place(drop)?;
-        self.placement_history.push(drop);
+    /// Drops the turn's next tile, returning whether it was placed or skipped for lack of space
+    pub fn place(&mut self) -> Result<bool, GameError> {
+        let drop = self.session.drop();
+
+        let outcome = self.session.step(GameAction::Drop)?;
         self.dropping = false;
 
-        Ok(())
+        if outcome == StepOutcome::Blocked {
+            return Ok(false);
+        }
+
+        self.placement_history.push(drop);
+        Ok(true)
     }
 }
 
 fn update_board_children(
-    game_state: Res<GameState>,
-    mut children_query: Query<(&BoardTile, &mut Handle<Image>)>,
+    boards: Query<&GameState, With<Board>>,
+    mut children_query: Query<(&Parent, &BoardTile, &mut Handle<Image>, &mut Sprite)>,
+    collapsing: Query<(&Parent, &CollapsingTile)>,
     sprites: Res<BoardSprites>,
 ) {
-    let board = game_state.data_board.display_board();
-    children_query.iter_mut().for_each(|(tile, mut handle)| {
-        if let Some(tile_value) = board.column(tile.x.into()).get::<usize>(tile.y.into()) {
-            let sprite = if *tile_value > 0 {
-                sprites.closed.clone()
-            } else {
-                sprites.open.clone()
+    let arriving = |board: Entity, tile: &BoardTile| {
+        collapsing.iter().any(|(parent, collapsing)| {
+            parent.get() == board && collapsing.x == tile.x && collapsing.y == tile.y
+        })
+    };
+    children_query
+        .iter_mut()
+        .for_each(|(parent, tile, mut handle, mut sprite)| {
+            let Ok(game_state) = boards.get(parent.get()) else {
+                return;
             };
+            let board = game_state.session.board().display_board();
 
-            *handle = sprite;
-        }
-    });
+            if let Some(tile_value) = board.column(tile.x.into()).get::<usize>(tile.y.into()) {
+                // Tiles still sliding into place are shown once they arrive
+                let tile_value = if arriving(parent.get(), tile) {
+                    &Tile::Empty
+                } else {
+                    tile_value
+                };
+
+                *handle = if tile_value.is_filled() {
+                    sprites.closed.clone()
+                } else {
+                    sprites.open.clone()
+                };
+
+                sprite.color = tile_tint(*tile_value);
+            }
+        });
 }
 
 fn push_effect_vector(state: &GameState, base_vec: Vec2) -> Result<Vec2, GameError> {
-    let direction =
-        InsertionDirection::for_board_insertion(state.data_board.board(), state.drop())?;
+    let direction = InsertionDirection::for_board_insertion(
+        state.session.board().board(),
+        state.session.drop(),
+    )?;
 
     let offset_vector = Vec2::from_array(match direction {
         InsertionDirection::FromTop => [0.0, -1.0],
@@ -136,75 +190,236 @@ fn push_effect_vector(state: &GameState, base_vec: Vec2) -> Result<Vec2, GameErr
 
 fn handle_block_drops(
     mut drop_block: EventReader<DropBlockEvent>,
-    mut state: ResMut<GameState>,
-    settings: Res<GameSettings>,
+    mut boards: Query<(&Player, &mut GameState, &Transform), With<Board>>,
     mut command: Commands,
-    board_query: Query<(Entity, &Transform), With<Board>>,
+    mut tiles_collapsed: EventWriter<TilesCollapsed>,
+    mut drop_animation: EventWriter<DropAnimation>,
+    mut lines_cleared: EventWriter<LinesCleared>,
+    playback: Option<Res<ReplayPlayback>>,
 ) {
-    for _ in drop_block.read() {
+    for DropBlockEvent(board) in drop_block.read() {
+        let Ok((player, mut state, trans)) = boards.get_mut(*board) else {
+            continue;
+        };
+
+        // Replays hold each drop back until its recorded time, keeping the board busy until then
+        if playback
+            .as_ref()
+            .is_some_and(|playback| !playback.drop_due(&state.session))
+        {
+            state.dropping = true;
+            continue;
+        }
+
+        // Taken before placing, as the next drop may come from another side
+        let push = push_effect_vector(&state, Vec2::splat(200.0)).unwrap();
+        let cleared = state.session.board().lines_cleared();
+
         // Mutable operation, updates board state
-        if let Err(err) = state.place() {
-            match err {
-                GameError::InvalidPlacementLocation(placement) => {
-                    panic!(
-                        "Reached invalid placement: {placement}. State: {:#?}",
-                        state
-                    );
+        match state.place() {
+            Err(GameError::InvalidPlacementLocation(placement)) => {
+                panic!(
+                    "Reached invalid placement: {placement}. State: {:#?}",
+                    *state
+                );
+            }
+            Err(GameError::NoSpace) => state.game_over(),
+            Ok(placed) => {
+                if placed {
+                    // Apply place effect
+                    command
+                        .entity(*board)
+                        .insert(ElasticForce::new(trans.translation.truncate(), push));
+
+                    let game_board = state.session.board();
+                    if !game_board.last_collapse().is_empty() {
+                        let moves = game_board
+                            .last_collapse()
+                            .iter()
+                            .map(|&tile_move| {
+                                let (x, y) = tile_move.to;
+                                (tile_move, game_board.display_board()[(y, x)])
+                            })
+                            .collect();
+                        tiles_collapsed.send(TilesCollapsed(*board, moves));
+                    }
+
+                    let lines = game_board.lines_cleared() - cleared;
+                    if lines > 0 {
+                        lines_cleared.send(LinesCleared {
+                            player: *player,
+                            lines,
+                        });
+                    }
                 }
-                GameError::NoSpace => {
-                    state.mode = GameMode::GameOver;
-                    state.enable_input = false;
+
+                if state.session.mid_turn() {
+                    // The rest of the turn's drops follow one after another
+                    drop_animation.send(DropAnimation(*board));
+                } else if state.session.finished() {
+                    // Ends as soon as a puzzle is solved or out of moves, or a sprint reaches its lines
+                    state.finish();
+                } else if !state.session.has_legal_move() {
+                    // End the game as soon as the next turn can't be placed, rather than waiting for it to fail
+                    info!("No legal moves left");
+                    state.game_over();
                 }
             }
-        } else {
-            // Apply place effect, can assume single board and successful placement direction
-            let (board, trans) = board_query.single();
-            command.entity(board).insert(ElasticForce::new(
-                trans.translation.clone().truncate(),
-                push_effect_vector(&state, Vec2::splat(200.0)).unwrap(),
-            ));
-
-            // Update next baord drop
-            state.update_next_drop(&settings);
         }
 
-        debug!("New Board {}", state.data_board.display_board());
-        debug!("Next Drop is {}", state.next_drop);
+        debug!("New Board {}", state.session.board().display_board());
+        debug!("Next Drop is {}", state.session.next_drop());
     }
 }
 
+/// The config new games are started with: the puzzle being attempted, a versus match, or the settings as they are
+fn game_config(
+    settings: &GameSettings,
+    attempt: Option<&PuzzleAttempt>,
+    versus: Option<&VersusMatch>,
+) -> SessionConfig {
+    match (attempt, versus) {
+        (Some(attempt), _) => attempt.0.session_config(settings.session_config()),
+        (None, Some(versus)) => versus.session_config(settings.session_config()),
+        (None, None) => settings.session_config(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_restart(
-    mut game_state: ResMut<GameState>,
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    attempt: Option<Res<PuzzleAttempt>>,
+    versus: Option<Res<VersusMatch>>,
     mut restart_pressed: EventReader<RestartPressed>,
     mut reset_scoreboard: EventWriter<ResetScoreboard>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut boards: Query<(Entity, &mut GameState, &mut Transform), With<Board>>,
+    inflight_tiles: Query<Entity, With<DroppingAnimationTile>>,
 ) {
     let pressed = restart_pressed.read().next().is_some();
     restart_pressed.clear();
 
-    if pressed && game_state.mode == GameMode::GameOver {
-        reset_scoreboard.send_default();
-        *game_state = GameState::new(game_state.data_board.width());
+    // Games can be restarted once over, or from the pause menu
+    let paused = *pause.get() != PauseState::Running;
+    let over = boards
+        .iter()
+        .all(|(_, game_state, _)| game_state.mode.is_over());
+    if !pressed || !(paused || over) {
+        return;
+    }
+
+    reset_scoreboard.send_default();
+    commands.remove_resource::<ReplayPlayback>();
+    // Puzzles restart from the top of the same level
+    let config = game_config(&settings, attempt.as_deref(), versus.as_deref());
+    // Boards in a versus match start from the same seed, but holds and garbage draw from it too,
+    // so the players' drops only match until either of them holds or takes garbage
+    let seed = rand::random();
+    next_pause.set(PauseState::Running);
+
+    // A paused game can be stopped mid-turn, so anything still animating is dropped with it
+    for (ent, mut game_state, mut trans) in &mut boards {
+        *game_state = GameState::new(config.clone(), seed);
+        commands.entity(ent).remove::<RotateBoard>();
+        trans.rotation = Quat::IDENTITY;
+    }
+    for ent in &inflight_tiles {
+        commands.entity(ent).despawn_recursive();
+    }
+}
+
+/// Starts a fresh game, spawning a board for each player.
+/// Replays play back the game they recorded, and puzzles set up their level.
+#[allow(clippy::too_many_arguments)]
+pub fn start_game(
+    mut commands: Commands,
+    sprites: Res<BoardSprites>,
+    settings: Res<GameSettings>,
+    playback: Option<Res<ReplayPlayback>>,
+    attempt: Option<Res<PuzzleAttempt>>,
+    versus: Option<Res<VersusMatch>>,
+    netplay: Option<Res<NetplayMatch>>,
+    mut reset_scoreboard: EventWriter<ResetScoreboard>,
+) {
+    reset_scoreboard.send_default();
+
+    let (config, seed) = match (playback, netplay) {
+        (Some(playback), _) => (playback.replay.config.clone(), playback.replay.seed),
+        // Both peers play the match the host started
+        (None, Some(netplay)) => (netplay.config().clone(), netplay.seed()),
+        (None, None) => (
+            game_config(&settings, attempt.as_deref(), versus.as_deref()),
+            rand::random(),
+        ),
+    };
+
+    let players = match versus {
+        Some(_) => Player::iter().collect(),
+        None => vec![Player::One],
+    };
+
+    for player in players {
+        spawn_board(
+            &mut commands,
+            &sprites,
+            player,
+            GameState::new(config.clone(), seed),
+            seat_translation(player, versus.is_some()),
+        );
+    }
+}
+
+/// Despawns everything spawned for the game, boards included, so nothing from it carries over into the menus or the next game
+fn end_game(mut commands: Commands, elements: Query<Entity, With<GameElement>>) {
+    for ent in &elements {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    commands.remove_resource::<ReplayPlayback>();
+    commands.remove_resource::<PuzzleAttempt>();
+    commands.remove_resource::<VersusMatch>();
+    commands.remove_resource::<NetplayMatch>();
+}
+
+/// Passes time on the GameScore system inside each data board.
+fn pass_score_time(mut boards: Query<&mut GameState, With<Board>>, time: Res<Time>) {
+    for mut game_state in &mut boards {
+        game_state.session.tick(time.delta_seconds());
     }
 }
 
-/// Passes time on the GameScore system inside the data board.
-fn pass_score_time(mut game_state: ResMut<GameState>, time: Res<Time>) {
-    game_state
-        .data_board
-        .score_mut()
-        .update(time.delta_seconds());
+/// Ends games played against the clock once it runs out, letting a turn that's already underway land first
+fn end_on_time(mut boards: Query<(&mut GameState, Has<RotateBoard>), With<Board>>) {
+    for (mut game_state, rotating) in &mut boards {
+        let idle = !game_state.dropping && !game_state.session.mid_turn() && !rotating;
+
+        if game_state.mode == GameMode::Playing
+            && idle
+            && game_state.session.time_left() == Some(0.0)
+        {
+            game_state.finish();
+        }
+    }
 }
 
 fn handle_rank_boost(
-    mut game_state: ResMut<GameState>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
     mut rank_boost_pressed: EventReader<RankBoostPressed>,
     mut sfx: EventWriter<PlaySoundEffect>,
 ) {
-    let pressed = rank_boost_pressed.read().next().is_some();
-    rank_boost_pressed.clear();
+    let pressed = rank_boost_pressed
+        .read()
+        .map(|RankBoostPressed(player)| *player)
+        .collect::<Vec<_>>();
+
+    for (player, mut game_state) in &mut boards {
+        if !pressed.contains(player) {
+            continue;
+        }
 
-    if pressed {
-        if game_state.data_board.score_mut().rank_boost() {
+        if game_state.session.step(GameAction::RankBoost) == Ok(StepOutcome::Boosted(true)) {
             info!("Boosted!");
             sfx.send(PlaySoundEffect(SoundEffect::RankBoost));
         } else {
@@ -217,13 +432,25 @@ pub struct GameStatePlugin;
 
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
-        let default_settings = GameSettings::default();
-
-        app.insert_resource(GameState::new(default_settings.board_dim as usize))
+        app.add_event::<LinesCleared>()
+            .add_systems(OnEnter(AppState::InGame), start_game)
+            .add_systems(OnExit(AppState::InGame), end_game)
             .add_systems(
                 Update,
-                (handle_restart, pass_score_time, handle_rank_boost)
-                    .run_if(in_state(AppState::InGame)),
+                (
+                    // Netplay matches can't be restarted, as the other peer would have to agree to it
+                    handle_restart.run_if(
+                        in_state(AppState::InGame).and_then(not(resource_exists::<NetplayMatch>)),
+                    ),
+                    (
+                        // Replays catch the clock up to each input themselves
+                        pass_score_time.run_if(not(resource_exists::<ReplayPlayback>)),
+                        end_on_time,
+                        handle_rank_boost,
+                    )
+                        .chain()
+                        .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
+                ),
             )
             .add_systems(
                 PostUpdate,
@@ -231,35 +458,3 @@ impl Plugin for GameStatePlugin {
             );
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::GameState;
-
-    #[test]
-    fn verify_drop() {
-        let mut state = GameState::new(4);
-
-        // Min Wrapping
-        state.offset = -1;
-        assert_eq!(state.drop(), 15);
-
-        // Max Wrapping
-        state.next_drop = 15;
-        state.offset = 1;
-        assert_eq!(state.drop(), 0);
-
-        [-1, 0, 1].into_iter().for_each(|offset| {
-            state.offset = offset;
-
-            (0..15).into_iter().for_each(|index| {
-                if (index == 0 && offset == -1) || (index == 15 && offset == 1) {
-                    // Skip Edge
-                } else {
-                    state.next_drop = index;
-                    assert_eq!(state.drop(), (index as i32 + offset as i32) as usize);
-                }
-            });
-        });
-    }
-}
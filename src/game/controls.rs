@@ -1,44 +1,140 @@
 use bevy::{
+    ecs::system::SystemParam,
     input::{
         gamepad::{GamepadButtonInput, GamepadConnection, GamepadConnectionEvent},
         keyboard::KeyboardInput,
-        ButtonState,
     },
     prelude::*,
     utils::HashMap,
 };
 
-use crate::state::AppState;
+use strum::IntoEnumIterator;
 
-use super::ui::ControlPlatform;
+use crate::state::{AppState, PauseState};
+
+use super::{
+    bindings::{keyboard_half, load_action_map, ActionMap, InputBinding},
+    board::Player,
+    netplay::NetplayMatch,
+    replay::ReplayPlayback,
+    settings::GameSettings,
+    ui::{ControlIntention, ControlPlatform},
+    versus::VersusMatch,
+};
+
+// Inputs for a board carry the player they're for, the rest are for the whole game
+
+#[derive(Event, Default)]
+pub struct PlusOffsetPressed(pub Player);
+
+#[derive(Event, Default)]
+pub struct MinusOffsetPressed(pub Player);
 
 #[derive(Event, Default)]
-pub struct PlusOffsetPressed;
+pub struct RotateLeftPressed(pub Player);
 
 #[derive(Event, Default)]
-pub struct MinusOffsetPressed;
+pub struct RotateRightPressed(pub Player);
 
 #[derive(Event, Default)]
-pub struct RotateLeftPressed;
+pub struct FlipPressed(pub Player);
 
 #[derive(Event, Default)]
-pub struct RotateRightPressed;
+pub struct SkipRotationPressed(pub Player);
 
 #[derive(Event, Default)]
 pub struct PrintHistoryPressed;
 
 #[derive(Event, Default)]
-pub struct RankBoostPressed;
+pub struct RankBoostPressed(pub Player);
+
+#[derive(Event, Default)]
+pub struct HoldPressed(pub Player);
 
 #[derive(Event, Default)]
 pub struct RestartPressed;
 
+#[derive(Event, Default)]
+pub struct PausePressed;
+
+#[derive(Event, Default)]
+pub struct HintPressed;
+
+/// Sends the event for each [`ControlIntention`]
+#[derive(SystemParam)]
+pub struct IntentionWriters<'w> {
+    plus_offset: EventWriter<'w, PlusOffsetPressed>,
+    minus_offset: EventWriter<'w, MinusOffsetPressed>,
+    rotate_left: EventWriter<'w, RotateLeftPressed>,
+    rotate_right: EventWriter<'w, RotateRightPressed>,
+    flip: EventWriter<'w, FlipPressed>,
+    skip_rotation: EventWriter<'w, SkipRotationPressed>,
+    rank_boost: EventWriter<'w, RankBoostPressed>,
+    hold: EventWriter<'w, HoldPressed>,
+    restart: EventWriter<'w, RestartPressed>,
+    pause: EventWriter<'w, PausePressed>,
+    hint: EventWriter<'w, HintPressed>,
+}
+
+impl IntentionWriters<'_> {
+    pub fn send(&mut self, intention: ControlIntention, player: Player) {
+        match intention {
+            ControlIntention::ShiftUp => {
+                self.plus_offset.send(PlusOffsetPressed(player));
+            }
+            ControlIntention::ShiftDown => {
+                self.minus_offset.send(MinusOffsetPressed(player));
+            }
+            ControlIntention::RotateLeft => {
+                self.rotate_left.send(RotateLeftPressed(player));
+            }
+            ControlIntention::RotateRight => {
+                self.rotate_right.send(RotateRightPressed(player));
+            }
+            ControlIntention::Flip => {
+                self.flip.send(FlipPressed(player));
+            }
+            ControlIntention::SkipRotation => {
+                self.skip_rotation.send(SkipRotationPressed(player));
+            }
+            ControlIntention::RankBoost => {
+                self.rank_boost.send(RankBoostPressed(player));
+            }
+            ControlIntention::Hold => {
+                self.hold.send(HoldPressed(player));
+            }
+            ControlIntention::Restart => {
+                self.restart.send_default();
+            }
+            ControlIntention::Pause => {
+                self.pause.send_default();
+            }
+            ControlIntention::Hint => {
+                self.hint.send_default();
+            }
+        }
+    }
+}
+
 /// Tracks the type of discovered gamepads
 #[derive(Resource, Default)]
 pub struct GamepadDiscoveryTable(pub HashMap<usize, ControlPlatform>);
 
+impl GamepadDiscoveryTable {
+    /// The player a gamepad plays for in versus, handed out by gamepad id, lowest first.
+    /// Gamepads past the last player aren't given a board.
+    pub fn player(&self, gamepad: usize) -> Option<Player> {
+        let mut gamepads = self.0.keys().copied().collect::<Vec<_>>();
+        gamepads.sort();
+
+        let seat = gamepads.iter().position(|id| *id == gamepad)?;
+        Player::iter().nth(seat)
+    }
+}
+
 fn handle_gamepad_connections(
     mut discovery_table: ResMut<GamepadDiscoveryTable>,
+    settings: Res<GameSettings>,
     mut next_control_state: ResMut<NextState<ControlPlatform>>,
     mut gamepad_connection_events: EventReader<GamepadConnectionEvent>,
 ) {
@@ -63,7 +159,9 @@ fn handle_gamepad_connections(
 
             if let Some(discovered_platform) = discovered_platform {
                 info!("Discovered type {discovered_platform:?}");
-                next_control_state.set(discovered_platform);
+                if settings.control_platform.is_none() {
+                    next_control_state.set(discovered_platform);
+                }
                 discovery_table
                     .0
                     .insert(connection.gamepad.id, discovered_platform);
@@ -72,8 +170,10 @@ fn handle_gamepad_connections(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_input(
-    // Transition Control Platform
+    // Transition Control Platform, unless one is picked in the settings
+    settings: Res<GameSettings>,
     mut next_control_state: ResMut<NextState<ControlPlatform>>,
     // Keyboard
     mut keyboard_input_events: EventReader<KeyboardInput>,
@@ -83,94 +183,112 @@ fn handle_input(
     discovery_table: Res<GamepadDiscoveryTable>,
 
     // Control Intentions
-    mut plus_offset: EventWriter<PlusOffsetPressed>,
-    mut minus_offset: EventWriter<MinusOffsetPressed>,
-    mut rotate_left: EventWriter<RotateLeftPressed>,
-    mut rotate_right: EventWriter<RotateRightPressed>,
+    action_map: Res<ActionMap>,
+    mut intentions: IntentionWriters,
     mut print_history: EventWriter<PrintHistoryPressed>,
-    mut restart: EventWriter<RestartPressed>,
-    mut rank_boost: EventWriter<RankBoostPressed>,
+
+    // Replays drive the board themselves and a paused board can't be played, only restarting and pausing are allowed
+    replay: Option<Res<ReplayPlayback>>,
+    pause: Res<State<PauseState>>,
+
+    // Versus splits the keyboard in half and gives each player a gamepad
+    versus: Option<Res<VersusMatch>>,
+    // Netplay gives every input to the board on this peer, the other is played by the other peer
+    netplay: Option<Res<NetplayMatch>>,
 ) {
+    let netplay = netplay.map(|x| x.local);
+    let locked = replay.is_some() || *pause.get() != PauseState::Running;
+    let follow_device = settings.control_platform.is_none();
+
+    let mut pressed = Vec::new();
+
     for event in keyboard_input_events.read() {
         if !event.state.is_pressed() {
             continue;
         }
 
         // Something was pressed
-        next_control_state.set(ControlPlatform::Pc);
+        if follow_device {
+            next_control_state.set(ControlPlatform::Pc);
+        }
 
-        match event.key_code {
-            KeyCode::ArrowUp | KeyCode::KeyW => {
-                plus_offset.send_default();
-            }
-            KeyCode::ArrowLeft | KeyCode::KeyA => {
-                rotate_left.send_default();
-            }
-            KeyCode::ArrowRight | KeyCode::KeyD => {
-                rotate_right.send_default();
-            }
-            KeyCode::ArrowDown | KeyCode::KeyS => {
-                minus_offset.send_default();
-            }
-            KeyCode::Digit0 => {
-                print_history.send_default();
-            }
-            KeyCode::ControlRight | KeyCode::KeyE => {
-                rank_boost.send_default();
-            }
-            KeyCode::KeyR => {
-                restart.send_default();
-            }
-            _ => {}
+        // Debug key, not rebindable
+        if event.key_code == KeyCode::Digit0 {
+            print_history.send_default();
         }
+
+        let player = match (netplay, &versus) {
+            (Some(local), _) => local,
+            (None, Some(_)) => keyboard_half(event.key_code),
+            (None, None) => Player::One,
+        };
+
+        pressed.push((InputBinding::Key(event.key_code), player));
     }
 
     for event in gamepad_input_events.read() {
         let gamepad = discovery_table.0.get(&event.button.gamepad.id);
 
         if let Some(gamepad) = gamepad {
-            next_control_state.set(*gamepad);
+            if follow_device {
+                next_control_state.set(*gamepad);
+            }
         } else {
             // If it can't find it, that means this isn't a valid gamepad
             continue;
         }
 
-        match (event.state, event.button.button_type) {
-            (ButtonState::Released, _) => {}
-            (ButtonState::Pressed, GamepadButtonType::DPadUp) => {
-                plus_offset.send_default();
-            }
-            (ButtonState::Pressed, GamepadButtonType::LeftTrigger) => {
-                rotate_left.send_default();
-            }
-            (ButtonState::Pressed, GamepadButtonType::RightTrigger) => {
-                rotate_right.send_default();
-            }
-            (ButtonState::Pressed, GamepadButtonType::DPadDown) => {
-                minus_offset.send_default();
-            }
-            (ButtonState::Pressed, GamepadButtonType::Start) => {
-                restart.send_default();
-            }
-            (ButtonState::Pressed, GamepadButtonType::South) => {
-                rank_boost.send_default();
-            }
-            _ => {}
+        let player = match (netplay, &versus) {
+            (Some(local), _) => Some(local),
+            (None, Some(_)) => discovery_table.player(event.button.gamepad.id),
+            (None, None) => Some(Player::One),
+        };
+
+        if let (true, Some(player)) = (event.state.is_pressed(), player) {
+            pressed.push((InputBinding::Button(event.button.button_type), player));
         }
     }
+
+    for (intention, player) in pressed.into_iter().flat_map(|(input, player)| {
+        action_map
+            .intentions(input)
+            .map(move |intention| (intention, player))
+    }) {
+        if locked
+            && !matches!(
+                intention,
+                ControlIntention::Restart | ControlIntention::Pause
+            )
+        {
+            continue;
+        }
+
+        intentions.send(intention, player);
+    }
 }
 
 pub struct ControlsPlugin;
 
 impl Plugin for ControlsPlugin {
     fn build(&self, app: &mut App) {
+        let action_map = load_action_map().unwrap_or_else(|err| {
+            error!("{err}");
+            ActionMap::default()
+        });
+
         app.add_event::<PlusOffsetPressed>()
             .add_event::<MinusOffsetPressed>()
             .add_event::<RotateLeftPressed>()
             .add_event::<RotateRightPressed>()
+            .add_event::<FlipPressed>()
+            .add_event::<SkipRotationPressed>()
             .add_event::<PrintHistoryPressed>()
             .add_event::<RestartPressed>()
             .add_event::<RankBoostPressed>()
+            .add_event::<HoldPressed>()
+            .add_event::<PausePressed>()
+            .add_event::<HintPressed>()
+            .insert_resource(action_map)
             .init_resource::<GamepadDiscoveryTable>()
             .add_systems(
                 PreUpdate,
@@ -2,16 +2,26 @@ use bevy::prelude::*;
 
 use crate::state::AppState;
 
-use super::{board::state::GameState, controls::PrintHistoryPressed};
+use super::{
+    board::{state::GameState, Board, Player},
+    controls::PrintHistoryPressed,
+};
 
-fn print_history(game_state: Res<GameState>, mut input_pressed: EventReader<PrintHistoryPressed>) {
+fn print_history(
+    boards: Query<(&Player, &GameState), With<Board>>,
+    mut input_pressed: EventReader<PrintHistoryPressed>,
+) {
     let pressed = input_pressed.read().count() > 0;
 
     if pressed {
-        debug!(
-            "{:?}, Next Drop {}",
-            game_state.placement_history, game_state.next_drop
-        );
+        for (player, game_state) in &boards {
+            debug!(
+                "{player:?}: {:?}, Next Drop {}, Seed {}",
+                game_state.placement_history,
+                game_state.session.next_drop(),
+                game_state.session.seed()
+            );
+        }
     }
 }
 
@@ -0,0 +1,165 @@
+use std::{
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::{
+    logic::{
+        leaderboard::{Leaderboard, LeaderboardEntry},
+        mode::GameModeKind,
+        score::ScoringPreset,
+        session::SessionConfig,
+    },
+    persist::{self, PersistError},
+    state::AppState,
+};
+
+use super::{
+    board::{
+        state::{GameMode, GameState},
+        Board,
+    },
+    replay::{save_replay_on_game_over, ReplayPlayback, ReplaySaved},
+};
+
+/// The local leaderboard, saved in the data dir
+#[derive(Resource, Default)]
+pub struct HighScores(pub Leaderboard);
+
+/// Where the last finished game placed on the leaderboard
+#[derive(Resource, Default)]
+pub struct GameResult {
+    /// Placement within the game's mode, 0 being a new personal best
+    pub placement: Option<usize>,
+}
+
+impl GameResult {
+    pub fn new_best(&self) -> bool {
+        self.placement == Some(0)
+    }
+}
+
+fn leaderboard_path() -> PathBuf {
+    persist::data_dir().join("leaderboard.ron")
+}
+
+fn load_leaderboard() -> Result<Leaderboard, PersistError> {
+    match persist::load_ron(&leaderboard_path()) {
+        Err(PersistError::Io(err)) if err.kind() == ErrorKind::NotFound => {
+            Ok(Leaderboard::default())
+        }
+        result => result,
+    }
+}
+
+/// The leaderboard category a game is played in.
+/// Games scored under different rules or played to different ends can't be compared,
+/// so each preset is its own mode within each of the game modes.
+pub fn mode_name(config: &SessionConfig) -> String {
+    let preset = ScoringPreset::iter()
+        .find(|preset| preset.rules() == config.scoring)
+        .map(|preset| format!("{preset:?}"))
+        .unwrap_or_else(|| String::from("Custom"));
+
+    let name = match config.mode {
+        GameModeKind::Endless => preset,
+        kind => format!("{kind:?} {preset}"),
+    };
+
+    // Garbage makes for a harder game, so it's ranked on its own
+    match config.garbage {
+        Some(_) => format!("{name} Garbage"),
+        None => name,
+    }
+}
+
+fn record_score_on_game_over(
+    // Versus matches aren't ranked, only games played alone on a single board
+    board: Query<&GameState, With<Board>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut replay_saved: EventReader<ReplaySaved>,
+    mut high_scores: ResMut<HighScores>,
+    mut result: ResMut<GameResult>,
+    mut recorded: Local<bool>,
+) {
+    let replay = replay_saved.read().last().map(|saved| saved.0.clone());
+
+    let Ok(game_state) = board.get_single() else {
+        return;
+    };
+
+    if !game_state.mode.is_over() {
+        *recorded = false;
+        if result.placement.is_some() {
+            *result = GameResult::default();
+        }
+        return;
+    }
+
+    // Watching a replay doesn't count as playing, nor does letting the solver play.
+    // Puzzles track completion instead of scores
+    if *recorded
+        || playback.is_some()
+        || game_state.assisted
+        || game_state.session.config().puzzle.is_some()
+    {
+        return;
+    }
+    *recorded = true;
+
+    let session = &game_state.session;
+    let kind = session.config().mode;
+
+    // Timed games only count once they've been finished
+    if kind.ranked_by_time() && game_state.mode != GameMode::Results {
+        return;
+    }
+
+    let score = session.board().score();
+    let entry = LeaderboardEntry {
+        score: score.score(),
+        rank: score.rank(),
+        board_width: session.config().width,
+        board_height: session.config().height,
+        mode: mode_name(session.config()),
+        kind,
+        time: session.elapsed(),
+        date: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default(),
+        replay,
+    };
+
+    result.placement = high_scores.0.submit(entry);
+
+    if result.placement.is_some() {
+        if let Err(err) = persist::save_ron(&leaderboard_path(), &high_scores.0) {
+            error!("{err}");
+        }
+    }
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        let leaderboard = load_leaderboard().unwrap_or_else(|err| {
+            error!("{err}");
+            Leaderboard::default()
+        });
+
+        app.insert_resource(HighScores(leaderboard))
+            .init_resource::<GameResult>()
+            .add_systems(
+                PostUpdate,
+                record_score_on_game_over
+                    .after(save_replay_on_game_over)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
@@ -1,19 +1,35 @@
-use bevy::app::{PluginGroup, PluginGroupBuilder};
+use bevy::{
+    app::{PluginGroup, PluginGroupBuilder},
+    prelude::Component,
+};
 
 use crate::audio::AudioPlugin;
 
 use self::{
     background::BackgroundPlugin, board::BoardPlugin, controls::ControlsPlugin, debug::DebugPlugin,
-    settings::SettingsPlugin, spawn::SpawnPlugin,
+    leaderboard::LeaderboardPlugin, netplay::NetplayPlugin, pause::PausePlugin,
+    puzzle::PuzzlePlugin, replay::ReplayPlugin, settings::SettingsPlugin, spawn::SpawnPlugin,
+    versus::VersusPlugin,
 };
 
 mod background;
+pub mod bindings;
 mod board;
-mod controls;
+pub mod controls;
 mod debug;
+pub mod leaderboard;
+pub mod netplay;
+mod pause;
+pub mod puzzle;
+pub mod replay;
 pub mod settings;
 mod spawn;
 pub mod ui;
+pub mod versus;
+
+/// Marks an entity that only lives as long as the game, despawned when leaving it
+#[derive(Component)]
+pub struct GameElement;
 
 pub struct GamePlugins;
 
@@ -28,8 +44,14 @@ impl PluginGroup for GamePlugins {
             .add(BackgroundPlugin)
             .add(SettingsPlugin)
             .add(ControlsPlugin)
+            .add(PausePlugin)
             .add(BoardPlugin)
             .add(SpawnPlugin)
             .add(DebugPlugin)
+            .add(ReplayPlugin)
+            .add(LeaderboardPlugin)
+            .add(PuzzlePlugin)
+            .add(VersusPlugin)
+            .add(NetplayPlugin)
     }
 }
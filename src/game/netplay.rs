@@ -0,0 +1,445 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    logic::{
+        lockstep::{checksum, play_turn, Lockstep, LockstepError, NetMessage, TurnInputs},
+        session::{GameSession, SessionConfig},
+    },
+    net::{NetError, Peer},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    board::{
+        rotate::RotateBoard,
+        state::{GameMode, GameState},
+        Board, Player,
+    },
+    settings::GameSettings,
+    versus::VersusMatch,
+};
+
+/// The config a match is played with over the network, from the host's settings.
+/// Garbage is only sent between boards, as injections depend on the rank and so on the timing of each turn.
+pub fn session_config(settings: &GameSettings) -> SessionConfig {
+    VersusMatch.session_config(SessionConfig {
+        garbage: None,
+        ..settings.session_config()
+    })
+}
+
+/// A versus match played against another peer, kept in lockstep one turn at a time.
+///
+/// Only inputs are sent. Each peer plays the other's turns on its own copy of their board,
+/// like a replay, and checks it ends up with the same board they did.
+#[derive(Resource)]
+pub struct NetplayMatch {
+    peer: Peer,
+    /// The player on this peer, the other board is played by the other peer
+    pub local: Player,
+    config: SessionConfig,
+    seed: u64,
+    /// Turns the other peer has sent that haven't been played on their board yet
+    remote_turns: VecDeque<TurnInputs>,
+    /// Turns each board has played, and the garbage waiting on them
+    lockstep: Lockstep,
+    /// Where this peer's current turn starts in its board's history
+    turn_start: usize,
+    /// Lines this peer's board had cleared when its current turn started
+    turn_lines: u32,
+    /// Why the match was cut short, if it was
+    pub failure: Option<NetError>,
+}
+
+impl NetplayMatch {
+    /// A match hosted on this peer, played on the left board
+    pub fn host(peer: Peer, config: SessionConfig, seed: u64) -> Self {
+        Self::new(peer, Player::One, config, seed)
+    }
+
+    /// A match joined from this peer, played on the right board
+    pub fn join(peer: Peer, config: SessionConfig, seed: u64) -> Self {
+        Self::new(peer, Player::Two, config, seed)
+    }
+
+    fn new(peer: Peer, local: Player, config: SessionConfig, seed: u64) -> Self {
+        Self {
+            peer,
+            local,
+            config,
+            seed,
+            remote_turns: VecDeque::new(),
+            lockstep: Lockstep::default(),
+            turn_start: 0,
+            turn_lines: 0,
+            failure: None,
+        }
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Whether the other peer has yet to send a turn this peer has already played
+    pub fn awaiting_turn(&self) -> bool {
+        self.failure.is_none() && !self.lockstep.can_play(self.local.seat())
+    }
+
+    /// Turns a board has finished, the one it was lost on included
+    fn turns(&self, player: Player) -> u32 {
+        self.lockstep.turns(player.seat())
+    }
+
+    /// Ends this peer's turn, sending garbage for the lines it cleared. Returns the turn as it's sent to the other peer.
+    fn end_local_turn(&mut self, session: &GameSession) -> TurnInputs {
+        let history = session.history();
+        let actions = history[self.turn_start..]
+            .iter()
+            .map(|input| input.action)
+            .collect();
+
+        let lines = session.board().lines_cleared();
+        let turn = self
+            .lockstep
+            .end_turn(self.local.seat(), lines - self.turn_lines);
+        self.turn_start = history.len();
+        self.turn_lines = lines;
+
+        TurnInputs {
+            turn,
+            actions,
+            checksum: checksum(session.board()),
+        }
+    }
+}
+
+/// Whether a board is between turns, with nothing animating
+fn idle(game_state: &GameState, rotating: bool) -> bool {
+    !game_state.dropping && !game_state.session.mid_turn() && !rotating
+}
+
+/// Starts the match once connected, from the command line
+fn start_netplay(mut commands: Commands, mut next_state: ResMut<NextState<AppState>>) {
+    commands.insert_resource(VersusMatch);
+    next_state.set(AppState::InGame);
+}
+
+/// Collects the turns the other peer has sent
+fn receive_turns(mut netplay: ResMut<NetplayMatch>, boards: Query<&GameState, With<Board>>) {
+    // Nothing's needed once the match is over, other than the turn it was lost on, and the other player is free to leave
+    let over = boards.iter().any(|x| x.mode.is_over()) && !netplay.awaiting_turn();
+    if netplay.failure.is_some() || over {
+        return;
+    }
+
+    let remote = netplay.local.opponent();
+    match netplay.peer.poll() {
+        Ok(messages) => {
+            for message in messages {
+                match message {
+                    // Turns can only arrive in order over TCP, so anything else is a broken peer
+                    NetMessage::Turn(turn)
+                        if turn.turn
+                            == netplay.turns(remote) + netplay.remote_turns.len() as u32 =>
+                    {
+                        netplay.remote_turns.push_back(turn)
+                    }
+                    NetMessage::Turn(turn) => {
+                        netplay.failure = Some(LockstepError::InvalidTurn(turn.turn).into());
+                    }
+                    NetMessage::Start { .. } => warn!("Match was started twice"),
+                }
+            }
+        }
+        Err(err) => netplay.failure = Some(err),
+    }
+}
+
+/// Sends each turn played on this peer's board once it's finished dropping.
+/// A turn the game was lost on is sent as far as it got.
+fn send_turns(
+    mut netplay: ResMut<NetplayMatch>,
+    boards: Query<(&Player, &GameState, Has<RotateBoard>), With<Board>>,
+) {
+    let local = netplay.local;
+    let Some((_, game_state, rotating)) = boards.iter().find(|(x, _, _)| **x == local) else {
+        return;
+    };
+
+    let session = &game_state.session;
+    let finished =
+        session.turns_played() as u32 > netplay.turns(local) && idle(game_state, rotating);
+    let lost =
+        game_state.mode == GameMode::GameOver && session.history().len() > netplay.turn_start;
+
+    if netplay.failure.is_some() || !(finished || lost) {
+        return;
+    }
+
+    let turn = netplay.end_local_turn(session);
+    if let Err(err) = netplay.peer.send(&NetMessage::Turn(turn)) {
+        netplay.failure = Some(err);
+    }
+}
+
+/// Plays the other peer's next turn on their board once it can be, ending the match if the board
+/// doesn't match theirs. Turns are stepped straight through, so every action lands exactly where
+/// it was taken on their board, rank boosts and holds between a rotation and its drops included.
+fn play_remote_turns(
+    mut netplay: ResMut<NetplayMatch>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
+) {
+    let remote = netplay.local.opponent();
+    let Some((_, mut game_state)) = boards.iter_mut().find(|(x, _)| **x == remote) else {
+        return;
+    };
+
+    // The next turn waits on any garbage landing from this peer's turn before it
+    let ready = netplay.failure.is_none()
+        && game_state.mode == GameMode::Playing
+        && netplay.lockstep.can_play(remote.seat());
+    if !ready {
+        return;
+    }
+    let Some(turn) = netplay.remote_turns.pop_front() else {
+        return;
+    };
+
+    let session = &mut game_state.session;
+    let cleared = session.board().lines_cleared();
+    let lost = match play_turn(session, turn.turn, &turn.actions) {
+        Ok(lost) => lost,
+        Err(err) => {
+            netplay.failure = Some(err.into());
+            return;
+        }
+    };
+
+    if checksum(session.board()) != turn.checksum {
+        netplay.failure = Some(LockstepError::Desync(turn.turn).into());
+        return;
+    }
+
+    netplay
+        .lockstep
+        .end_turn(remote.seat(), session.board().lines_cleared() - cleared);
+
+    if lost {
+        info!("No legal moves left");
+        game_state.game_over();
+    }
+}
+
+/// Pushes garbage onto each board once the turn it lands on has finished, and holds this peer's board
+/// until the other has caught up, so the garbage lands at the same point on both peers
+fn lockstep_boards(
+    mut netplay: ResMut<NetplayMatch>,
+    mut boards: Query<(&Player, &mut GameState, Has<RotateBoard>), With<Board>>,
+) {
+    for (player, mut game_state, rotating) in &mut boards {
+        if game_state.mode != GameMode::Playing {
+            continue;
+        }
+
+        if idle(&game_state, rotating) {
+            let lines = netplay.lockstep.take_garbage(player.seat());
+            if lines > 0 {
+                game_state.session.receive_garbage(lines);
+
+                if !game_state.session.has_legal_move() {
+                    info!("Buried by garbage");
+                    game_state.game_over();
+                    continue;
+                }
+            }
+        }
+
+        // The next turn waits on the other board finishing this one,
+        // and the other peer's board is only ever played by the turns they send
+        game_state.enable_input =
+            *player == netplay.local && netplay.lockstep.can_play(player.seat());
+    }
+}
+
+/// Ends the match on both boards when the connection is lost or the boards desync
+fn end_on_failure(netplay: Res<NetplayMatch>, mut boards: Query<&mut GameState, With<Board>>) {
+    let Some(failure) = &netplay.failure else {
+        return;
+    };
+
+    let mut playing = boards
+        .iter_mut()
+        .filter(|game_state| game_state.mode == GameMode::Playing)
+        .peekable();
+
+    if playing.peek().is_some() {
+        error!("{failure}");
+    }
+
+    for mut game_state in playing {
+        game_state.game_over();
+    }
+}
+
+pub struct NetplayPlugin;
+
+impl Plugin for NetplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            start_netplay
+                .run_if(in_state(AppState::MainMenu).and_then(resource_exists::<NetplayMatch>)),
+        )
+        .add_systems(
+            PreUpdate,
+            (
+                receive_turns,
+                send_turns,
+                play_remote_turns,
+                lockstep_boards,
+                end_on_failure,
+            )
+                .chain()
+                .run_if(
+                    in_state(AppState::InGame)
+                        .and_then(in_state(PauseState::Running))
+                        .and_then(resource_exists::<NetplayMatch>),
+                ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use bevy::prelude::*;
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        game::board::{state::GameState, Board, Player},
+        logic::{
+            ai::{Rotation, Solver},
+            lockstep::{checksum, play_turn, turn_actions, NetMatch, NetMessage},
+            session::{GameAction, GameSession, SessionConfig},
+        },
+        net::Peer,
+        state::{AppState, PauseState},
+    };
+
+    use super::{NetplayMatch, NetplayPlugin};
+
+    const SEED: u64 = 5;
+
+    /// A turn with a rank boost and a hold taken between its rotation and its drops
+    fn held_turn(session: &GameSession, rotation: GameAction) -> Vec<GameAction> {
+        let mut session = session.clone();
+        let mut actions = vec![rotation, GameAction::RankBoost, GameAction::Hold];
+        for action in &actions {
+            session.step(*action).unwrap();
+        }
+
+        actions.extend(turn_actions(&session, rotation).into_iter().skip(1));
+        actions
+    }
+
+    fn board(app: &mut App, player: Player) -> Mut<GameState> {
+        app.world
+            .query::<(&Player, &mut GameState)>()
+            .iter_mut(&mut app.world)
+            .find(|(x, _)| **x == player)
+            .map(|(_, game_state)| game_state)
+            .unwrap()
+    }
+
+    /// Plays the host's board in the game against a peer playing headless, whose turns rank boost
+    /// and hold mid-turn, checking both peers keep both boards the same
+    #[test]
+    fn verify_remote_turns() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut peer = Peer::join(listener.local_addr().unwrap()).unwrap();
+        let host = Peer::accept(&listener).unwrap();
+
+        let solver = Solver::default();
+        let options = Rotation::iter().collect::<Vec<_>>();
+        let config = SessionConfig::new(6, 6);
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, NetplayPlugin))
+            .insert_state(AppState::InGame)
+            .init_state::<PauseState>()
+            .insert_resource(NetplayMatch::host(host, config.clone(), SEED));
+        for player in Player::iter() {
+            app.world
+                .spawn((Board, player, GameState::new(config.clone(), SEED)));
+        }
+
+        let mut net_match = NetMatch::new(config, SEED);
+
+        for _ in 0..30 {
+            // The host's turn, played straight onto their board as the drops would land
+            let mut game_state = board(&mut app, Player::One);
+            assert!(game_state.enable_input);
+
+            let session = &mut game_state.session;
+            let rotation = solver
+                .best_rotation_for(session, &options)
+                .unwrap_or(Rotation::None);
+            let actions = turn_actions(session, rotation.action());
+            let turn = session.turns_played() as u32;
+            if play_turn(session, turn, &actions).unwrap() {
+                game_state.game_over();
+            }
+            app.update();
+
+            let NetMessage::Turn(sent) = peer.recv().unwrap() else {
+                panic!("Expected a turn");
+            };
+            net_match
+                .play_turn(0, &sent.actions, Some(sent.checksum))
+                .unwrap();
+            if net_match.over() {
+                break;
+            }
+
+            let rotation = solver
+                .best_rotation_for(net_match.session(1), &options)
+                .unwrap_or(Rotation::None);
+            let actions = held_turn(net_match.session(1), rotation.action());
+            let turn = net_match.play_turn(1, &actions, None).unwrap();
+            peer.send(&NetMessage::Turn(turn)).unwrap();
+
+            // The host's board waits on the turn arriving and being played
+            for _ in 0..200 {
+                app.update();
+                if !app.world.resource::<NetplayMatch>().awaiting_turn() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            let netplay = app.world.resource::<NetplayMatch>();
+            assert!(netplay.failure.is_none(), "{:?}", netplay.failure);
+            assert!(!netplay.awaiting_turn());
+
+            for player in Player::iter() {
+                assert_eq!(
+                    checksum(board(&mut app, player).session.board()),
+                    checksum(net_match.session(player.seat()).board())
+                );
+            }
+
+            if net_match.over() {
+                break;
+            }
+        }
+
+        assert!(net_match.turns(1) > 5);
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::state::{AppState, PauseState};
+
+use super::controls::PausePressed;
+
+/// Opens the pause menu, or steps back out of it
+fn toggle_pause(
+    mut pause_pressed: EventReader<PausePressed>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    let pressed = pause_pressed.read().next().is_some();
+    pause_pressed.clear();
+
+    if !pressed {
+        return;
+    }
+
+    next_pause.set(match pause.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+        PauseState::Settings => PauseState::Paused,
+    });
+}
+
+/// Leaving the game always leaves it unpaused
+fn unpause(mut next_pause: ResMut<NextState<PauseState>>) {
+    next_pause.set(PauseState::Running);
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, toggle_pause.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), unpause);
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+
+use crate::{
+    logic::puzzle::{PuzzleLevel, PuzzleProgress, PuzzleStatus},
+    persist::{self, PersistError},
+    state::AppState,
+};
+
+use super::{
+    board::{state::GameState, Board},
+    replay::ReplayPlayback,
+};
+
+/// Requests a puzzle to be played by its index in [`Puzzles::levels`]
+#[derive(Event)]
+pub struct PlayPuzzle(pub usize);
+
+/// The puzzle levels shipped with the game and how far through them the player is
+#[derive(Resource)]
+pub struct Puzzles {
+    /// Every level that loaded, in file name order
+    pub levels: Vec<PuzzleLevel>,
+    pub progress: PuzzleProgress,
+}
+
+/// The puzzle being played, kept so it can be restarted
+#[derive(Resource)]
+pub struct PuzzleAttempt(pub PuzzleLevel);
+
+/// Where level files are read from, in the assets folder
+fn levels_dir() -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join("levels")
+}
+
+fn progress_path() -> PathBuf {
+    persist::data_dir().join("puzzles.ron")
+}
+
+/// Loads every level file, skipping any that fail to load
+fn load_levels() -> Vec<PuzzleLevel> {
+    let entries = match fs::read_dir(levels_dir()) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!(
+                "Failed to read levels from {}: {err}",
+                levels_dir().display()
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| match persist::load_ron(&path) {
+            Ok(level) => Some(level),
+            Err(err) => {
+                error!("Failed to load level {}: {err}", path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+fn load_progress() -> Result<PuzzleProgress, PersistError> {
+    match persist::load_ron(&progress_path()) {
+        Err(PersistError::Io(err)) if err.kind() == ErrorKind::NotFound => {
+            Ok(PuzzleProgress::default())
+        }
+        result => result,
+    }
+}
+
+fn start_puzzle(
+    mut commands: Commands,
+    mut play_puzzle: EventReader<PlayPuzzle>,
+    puzzles: Res<Puzzles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(level) = play_puzzle
+        .read()
+        .last()
+        .and_then(|PlayPuzzle(index)| puzzles.levels.get(*index))
+    else {
+        return;
+    };
+
+    commands.insert_resource(PuzzleAttempt(level.clone()));
+    next_state.set(AppState::InGame);
+}
+
+/// Marks the level as solved once the game ends with its goal reached
+fn record_puzzle_on_game_over(
+    board: Query<&GameState, With<Board>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut puzzles: ResMut<Puzzles>,
+    mut recorded: Local<bool>,
+) {
+    let Ok(game_state) = board.get_single() else {
+        return;
+    };
+
+    if !game_state.mode.is_over() {
+        *recorded = false;
+        return;
+    }
+
+    // Watching a replay doesn't count as solving it, nor does letting the solver play
+    if *recorded || playback.is_some() || game_state.assisted {
+        return;
+    }
+    *recorded = true;
+
+    let session = &game_state.session;
+    let Some(level) = &session.config().puzzle else {
+        return;
+    };
+
+    if session.puzzle_status() == Some(PuzzleStatus::Solved)
+        && puzzles
+            .progress
+            .complete(level.name(), session.turns_played())
+    {
+        if let Err(err) = persist::save_ron(&progress_path(), &puzzles.progress) {
+            error!("{err}");
+        }
+    }
+}
+
+pub struct PuzzlePlugin;
+
+impl Plugin for PuzzlePlugin {
+    fn build(&self, app: &mut App) {
+        let progress = load_progress().unwrap_or_else(|err| {
+            error!("{err}");
+            PuzzleProgress::default()
+        });
+
+        app.insert_resource(Puzzles {
+            levels: load_levels(),
+            progress,
+        })
+        .add_event::<PlayPuzzle>()
+        .add_systems(Update, start_puzzle.run_if(in_state(AppState::LevelSelect)))
+        .add_systems(
+            PostUpdate,
+            record_puzzle_on_game_over.run_if(in_state(AppState::InGame)),
+        );
+    }
+}
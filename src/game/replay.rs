@@ -0,0 +1,257 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    logic::{
+        replay::Replay,
+        session::{GameAction, GameSession},
+    },
+    persist::{self, PersistError},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    board::{state::GameState, Board, Player},
+    controls::IntentionWriters,
+    ui::ControlIntention,
+};
+
+/// Requests a saved replay to be played back by file name, or the most recent one if none is given
+#[derive(Event, Default)]
+pub struct WatchReplay(pub Option<String>);
+
+/// A finished game's replay was saved, with its file name
+#[derive(Event)]
+pub struct ReplaySaved(pub String);
+
+/// An active replay being played back on the board
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// Seconds of the replay played back so far
+    clock: f32,
+    /// The input whose control has been pressed, waiting on the board to take it
+    pressed: Option<usize>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            clock: 0.0,
+            pressed: None,
+        }
+    }
+
+    /// Whether the session's next recorded input is a drop and its time has come
+    pub fn drop_due(&self, session: &GameSession) -> bool {
+        self.replay
+            .inputs
+            .get(session.history().len())
+            .is_some_and(|input| input.action == GameAction::Drop && input.time <= self.clock)
+    }
+}
+
+fn replay_dir() -> PathBuf {
+    persist::data_dir().join("replays")
+}
+
+/// Loads the replay that was saved last
+fn load_latest_replay() -> Result<Option<Replay>, PersistError> {
+    let entries = match fs::read_dir(replay_dir()) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let latest = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "ron"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified);
+
+    latest.map(|(_, path)| persist::load_ron(&path)).transpose()
+}
+
+/// Loads a saved replay by file name
+fn load_replay(file: &str) -> Result<Option<Replay>, PersistError> {
+    match persist::load_ron(&replay_dir().join(file)) {
+        Ok(replay) => Ok(Some(replay)),
+        Err(PersistError::Io(err)) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn save_replay_on_game_over(
+    // Versus has a board for each player, and garbage from the other board can't be replayed on its own
+    board: Query<&GameState, With<Board>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut replay_saved: EventWriter<ReplaySaved>,
+    mut saved: Local<bool>,
+) {
+    let Ok(game_state) = board.get_single() else {
+        return;
+    };
+
+    if !game_state.mode.is_over() {
+        *saved = false;
+        return;
+    }
+
+    if *saved {
+        return;
+    }
+    *saved = true;
+
+    if let Some(playback) = playback {
+        info!(
+            "Replay finished with {} points (recorded {})",
+            game_state.session.board().score().score(),
+            playback.replay.final_score
+        );
+        return;
+    }
+
+    let replay = Replay::from_session(&game_state.session);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+    let file = format!("{timestamp}-{}.ron", replay.seed);
+    let path = replay_dir().join(&file);
+
+    match persist::save_ron(&path, &replay) {
+        Ok(()) => {
+            info!("Saved replay to {}", path.display());
+            replay_saved.send(ReplaySaved(file));
+        }
+        Err(err) => error!("{err}"),
+    }
+}
+
+fn start_replay(
+    mut commands: Commands,
+    mut watch_replay: EventReader<WatchReplay>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(WatchReplay(file)) = watch_replay.read().last() else {
+        return;
+    };
+
+    let replay = match file {
+        Some(file) => load_replay(file),
+        None => load_latest_replay(),
+    };
+
+    match replay {
+        Ok(Some(replay)) => {
+            commands.insert_resource(ReplayPlayback::new(replay));
+            next_state.set(AppState::InGame);
+        }
+        Ok(None) => info!("No replays to watch"),
+        Err(err) => error!("{err}"),
+    }
+}
+
+/// The control that's pressed to take an action, drops following rotations on their own
+fn control_for(action: GameAction) -> Option<ControlIntention> {
+    match action {
+        GameAction::RotateLeft => Some(ControlIntention::RotateLeft),
+        GameAction::RotateRight => Some(ControlIntention::RotateRight),
+        GameAction::Flip => Some(ControlIntention::Flip),
+        GameAction::SkipRotation => Some(ControlIntention::SkipRotation),
+        GameAction::ShiftUp => Some(ControlIntention::ShiftUp),
+        GameAction::ShiftDown => Some(ControlIntention::ShiftDown),
+        GameAction::RankBoost => Some(ControlIntention::RankBoost),
+        GameAction::Hold => Some(ControlIntention::Hold),
+        GameAction::Drop => None,
+    }
+}
+
+/// Presses the recorded inputs back through the controls at the game time they were applied.
+///
+/// The session's clock is caught up to each input in a single tick, the same as `Replay::simulate`,
+/// and doesn't move again until the board has taken it, so an input that waits on an animation still
+/// lands at its time. Drops are left to the board's animations, which hold each one back until it's due.
+fn drive_replay(
+    time: Res<Time>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut board: Query<&mut GameState, With<Board>>,
+    mut controls: IntentionWriters,
+) {
+    let Ok(mut game_state) = board.get_single_mut() else {
+        return;
+    };
+
+    if game_state.mode.is_over() {
+        return;
+    }
+
+    playback.clock += time.delta_seconds();
+
+    let session = &mut game_state.session;
+    let next = session.history().len();
+    let Some(input) = playback.replay.inputs.get(next).copied() else {
+        // Every input has been played, the clock only runs on for games played against it
+        session.tick((playback.clock - session.elapsed()).max(0.0));
+        return;
+    };
+
+    if input.time > playback.clock {
+        return;
+    }
+
+    session.tick((input.time - session.elapsed()).max(0.0));
+
+    let Some(control) = control_for(input.action) else {
+        return;
+    };
+
+    // Rotations pressed while the board is busy are queued, so they're pressed once. Other controls
+    // are ignored while it's busy, so they're pressed every frame until the board takes them.
+    let rotation = matches!(
+        control,
+        ControlIntention::RotateLeft
+            | ControlIntention::RotateRight
+            | ControlIntention::Flip
+            | ControlIntention::SkipRotation
+    );
+    if rotation && playback.pressed == Some(next) {
+        return;
+    }
+
+    playback.pressed = Some(next);
+    controls.send(control, Player::One);
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WatchReplay>()
+            .add_event::<ReplaySaved>()
+            .add_systems(
+                Update,
+                start_replay
+                    .run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::Leaderboard))),
+            )
+            .add_systems(
+                PreUpdate,
+                drive_replay.run_if(
+                    in_state(AppState::InGame)
+                        .and_then(in_state(PauseState::Running))
+                        .and_then(resource_exists::<ReplayPlayback>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                save_replay_on_game_over.run_if(in_state(AppState::InGame)),
+            );
+    }
+}
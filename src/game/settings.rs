@@ -1,9 +1,33 @@
-use bevy::{
-    app::{App, Plugin},
-    ecs::system::Resource,
-    math::Vec2,
+use std::{io::ErrorKind, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioControl};
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+use crate::{
+    logic::{
+        garbage::GarbageRules,
+        generator::GeneratorKind,
+        mode::GameModeKind,
+        score::{ScoringPreset, ScoringRules},
+        session::SessionConfig,
+        tile::{ClearRules, TileSet},
+    },
+    persist::{self, PersistError},
 };
 
+use super::ui::ControlPlatform;
+
+/// Smallest board side that can be picked
+pub const MIN_BOARD_DIM: u8 = 3;
+/// Largest board side that can be picked
+pub const MAX_BOARD_DIM: u8 = 8;
+/// Most tiles that can be dropped after each rotation
+pub const MAX_DROPS_PER_TURN: u8 = 4;
+/// Most upcoming turns that can be previewed
+pub const MAX_PREVIEW_TURNS: u8 = 5;
+
 pub struct Resolution {
     pub large: Vec2,
     pub medium: Vec2,
@@ -20,27 +44,230 @@ impl Default for Resolution {
     }
 }
 
-#[derive(Resource)]
+/// Scoring rules picked by preset, or tuned by hand in the settings file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScoringSetting {
+    Preset(ScoringPreset),
+    Custom(ScoringRules),
+}
+
+impl Default for ScoringSetting {
+    fn default() -> Self {
+        Self::Preset(ScoringPreset::default())
+    }
+}
+
+impl ScoringSetting {
+    pub fn rules(&self) -> ScoringRules {
+        match self {
+            ScoringSetting::Preset(preset) => preset.rules(),
+            ScoringSetting::Custom(rules) => *rules,
+        }
+    }
+}
+
+/// Which of the [`Resolution`] presets the window uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum WindowSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+/// How much the solver helps while playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum Assist {
+    #[default]
+    Off,
+    /// Rotates for the player when the hint control is pressed
+    Hint,
+    /// Plays every turn on its own
+    AutoPlay,
+}
+
+/// Where settings are saved. Settings without a menu entry, like scoring, can be edited here by hand
+fn settings_path() -> PathBuf {
+    persist::config_dir().join("settings.ron")
+}
+
+/// Loads the settings file, falling back to the defaults if one hasn't been saved yet.
+/// Values the settings screen couldn't have picked are pulled back into range.
+pub fn load_settings() -> Result<GameSettings, PersistError> {
+    let mut settings = match persist::load_ron(&settings_path()) {
+        Err(PersistError::Io(err)) if err.kind() == ErrorKind::NotFound => GameSettings::default(),
+        result => result?,
+    };
+
+    if settings.clamp_to_limits() {
+        warn!("Settings were out of range and have been adjusted");
+    }
+
+    Ok(settings)
+}
+
+/// Writes the settings file
+pub fn save_settings(settings: &GameSettings) {
+    match persist::save_ron(&settings_path(), settings) {
+        Ok(()) => info!("Saved settings to {}", settings_path().display()),
+        Err(err) => error!("{err}"),
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameSettings {
-    pub board_dim: u8,
+    /// How games end
+    pub mode: GameModeKind,
+    /// Columns on the board
+    pub board_width: u8,
+    /// Rows on the board
+    pub board_height: u8,
+    /// Tiles dropped after each rotation
     pub blocks_dropped_per_turn: u8,
-    pub resolution: Resolution,
+    /// Upcoming turns shown ahead of the current one
+    pub preview_turns: u8,
+    /// How drop slots are rolled
+    pub drop_generator: GeneratorKind,
+    /// Whether the game pushes garbage onto the board, more often as the rank climbs
+    pub garbage: bool,
+    /// Which tiles are rolled for drops
+    pub tile_set: TileSet,
+    /// How full rows/cols are cleared and scored
+    pub clear_rules: ClearRules,
+    /// The constants scoring is tuned with
+    pub scoring: ScoringSetting,
+    pub window_size: WindowSize,
+    /// Master volume from 0 to 1
+    pub volume: f64,
+    /// Control hints to always show, or `None` to follow the last device used
+    pub control_platform: Option<ControlPlatform>,
+    /// Assisted games aren't recorded on the leaderboard
+    pub assist: Assist,
+    /// Shows where the next tile will land for each rotation
+    pub landing_preview: bool,
+}
+
+impl GameSettings {
+    /// Pulls every value back into the range the settings screen allows, returning whether any were out of it
+    fn clamp_to_limits(&mut self) -> bool {
+        let before = (
+            self.board_width,
+            self.board_height,
+            self.blocks_dropped_per_turn,
+            self.preview_turns,
+            self.volume,
+        );
+
+        self.board_width = self.board_width.clamp(MIN_BOARD_DIM, MAX_BOARD_DIM);
+        self.board_height = self.board_height.clamp(MIN_BOARD_DIM, MAX_BOARD_DIM);
+        self.blocks_dropped_per_turn = self.blocks_dropped_per_turn.clamp(1, MAX_DROPS_PER_TURN);
+        self.preview_turns = self.preview_turns.min(MAX_PREVIEW_TURNS);
+        // Also catches a NaN, which clamping keeps
+        self.volume = if self.volume.is_nan() {
+            1.0
+        } else {
+            self.volume.clamp(0.0, 1.0)
+        };
+
+        let after = (
+            self.board_width,
+            self.board_height,
+            self.blocks_dropped_per_turn,
+            self.preview_turns,
+            self.volume,
+        );
+
+        before != after
+    }
+
+    /// The window resolution picked by [`GameSettings::window_size`]
+    pub fn resolution(&self) -> Vec2 {
+        let resolution = Resolution::default();
+
+        match self.window_size {
+            WindowSize::Small => resolution.small,
+            WindowSize::Medium => resolution.medium,
+            WindowSize::Large => resolution.large,
+        }
+    }
+
+    /// The config new game sessions are started with
+    pub fn session_config(&self) -> SessionConfig {
+        SessionConfig {
+            tile_set: self.tile_set,
+            clear_rules: self.clear_rules,
+            scoring: self.scoring.rules(),
+            drops_per_turn: self.blocks_dropped_per_turn as usize,
+            preview_turns: self.preview_turns as usize,
+            generator: self.drop_generator,
+            mode: self.mode,
+            garbage: self.garbage.then(GarbageRules::default),
+            ..SessionConfig::new(self.board_width as usize, self.board_height as usize)
+        }
+    }
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         Self {
-            board_dim: 4,
+            mode: GameModeKind::default(),
+            board_width: 4,
+            board_height: 4,
             blocks_dropped_per_turn: 1,
-            resolution: Resolution::default(),
+            preview_turns: 3,
+            drop_generator: GeneratorKind::default(),
+            garbage: false,
+            tile_set: TileSet {
+                colors: 4,
+                special_chance: 0.05,
+            },
+            clear_rules: ClearRules {
+                single_color_bonus: 2,
+                mixed_lines_clear: true,
+                gravity: false,
+            },
+            scoring: ScoringSetting::default(),
+            window_size: WindowSize::default(),
+            volume: 1.0,
+            control_platform: None,
+            assist: Assist::default(),
+            landing_preview: true,
         }
     }
 }
 
+/// Applies the settings that don't wait for the next game
+fn apply_settings(
+    settings: Res<GameSettings>,
+    audio: Res<Audio>,
+    mut windows: Query<&mut Window>,
+    mut next_control_platform: ResMut<NextState<ControlPlatform>>,
+) {
+    let resolution = settings.resolution();
+    for mut window in &mut windows {
+        window.resolution.set(resolution.x, resolution.y);
+    }
+
+    audio.set_volume(settings.volume);
+
+    if let Some(platform) = settings.control_platform {
+        next_control_platform.set(platform);
+    }
+}
+
 pub struct SettingsPlugin;
 
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<GameSettings>();
+        let settings = load_settings().unwrap_or_else(|err| {
+            error!("{err}");
+            GameSettings::default()
+        });
+
+        app.insert_resource(settings).add_systems(
+            Update,
+            apply_settings.run_if(resource_changed::<GameSettings>),
+        );
     }
 }
@@ -1,119 +1,214 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
 use crate::{logic::insertion::InsertionDirection, state::AppState};
 
 use super::{
-    board::{get_square_dim, sprite::BoardSprites, state::GameState, BOARD_DIM, SPRITE_WIDTH},
-    settings::GameSettings,
+    board::{
+        get_square_dim,
+        sprite::{tile_tint, BoardSprites, BLOCKED_TINT, DANGER_TINT, HELD_TINT},
+        Boards, Player, BOARD_DIM, SPRITE_WIDTH,
+    },
     ui::multiplier::MultiplierTextContainer,
+    versus::{seat_translation, VersusMatch},
+    GameElement,
 };
 
+/// Opacity of the marker for the next turn's drops on the spawners, later turns are fainter
+const UPCOMING_MARKER_ALPHA: f32 = 0.6;
+
 #[derive(Debug, Component)]
 pub struct SpawnTile(pub usize);
 
-fn build_spawners(mut commands: Commands, settings: Res<GameSettings>, sprites: Res<BoardSprites>) {
-    let square_dim = get_square_dim(&settings);
+/// Lays each player's spawner ring out around their board as it's currently rotated.
+///
+/// Rectangular boards swap their dimensions when rotated, so the ring is rebuilt whenever
+/// the logical board's shape no longer matches the spawners.
+fn build_spawners(
+    mut commands: Commands,
+    sprites: Res<BoardSprites>,
+    boards: Boards,
+    versus: Option<Res<VersusMatch>>,
+    spawners: Query<(Entity, &Player), With<SpawnTile>>,
+    mut layouts: Local<HashMap<Player, (usize, usize)>>,
+) {
+    for (player, game_state) in boards.iter() {
+        let width = game_state.session.board().width();
+        let height = game_state.session.board().height();
 
-    let scale = Vec2::splat(square_dim) / SPRITE_WIDTH;
+        let built = spawners.iter().any(|(_, spawner)| *spawner == player);
+        if layouts.get(&player) == Some(&(width, height)) && built {
+            continue;
+        }
+        layouts.insert(player, (width, height));
 
-    for y in [1, -1].into_iter() {
-        for x in (0..settings.board_dim).into_iter() {
-            let index = if y == -1 {
-                x + settings.board_dim * 2
-            } else {
-                x
-            };
+        for (ent, spawner) in &spawners {
+            if *spawner == player {
+                commands.entity(ent).despawn_recursive();
+            }
+        }
 
-            let x_offset = -y as f32 * ((BOARD_DIM / 2.0) - (square_dim / 2.0));
-            let spawner_x = y as f32 * (x as f32 * square_dim) + x_offset;
-            let spawner_y = BOARD_DIM * y as f32;
+        let square_dim = get_square_dim(game_state.session.config());
 
-            commands
-                .spawn(SpriteBundle {
-                    texture: sprites.open.clone_weak(),
-                    transform: Transform::from_xyz(spawner_x, spawner_y, 1.0)
+        let scale = Vec2::splat(square_dim) / SPRITE_WIDTH;
+        let seat = seat_translation(player, versus.is_some());
+
+        for y in [1, -1].into_iter() {
+            for x in 0..width {
+                let index = if y == -1 { x + width + height } else { x };
+
+                let x_offset = -y as f32 * ((width as f32 * square_dim / 2.0) - (square_dim / 2.0));
+                let spawner_x = y as f32 * (x as f32 * square_dim) + x_offset;
+                let spawner_y = y as f32 * (height as f32 * square_dim + BOARD_DIM) / 2.0;
+
+                commands
+                    .spawn(SpriteBundle {
+                        texture: sprites.open.clone_weak(),
+                        transform: Transform::from_translation(
+                            seat + Vec3::new(spawner_x, spawner_y, 1.0),
+                        )
                         .with_scale(scale.extend(1.0)),
-                    ..default()
-                })
-                .insert(SpawnTile(index as usize));
+                        ..default()
+                    })
+                    .insert((SpawnTile(index), player, GameElement));
+            }
         }
-    }
 
-    for y in (0..settings.board_dim).into_iter() {
-        for x in [1, -1].into_iter() {
-            let index = if x == -1 {
-                y + settings.board_dim * 3
-            } else {
-                y + settings.board_dim
-            };
+        for y in 0..height {
+            for x in [1, -1].into_iter() {
+                let index = if x == -1 {
+                    y + width * 2 + height
+                } else {
+                    y + width
+                };
 
-            let y_offset = x as f32 * ((BOARD_DIM / 2.0) - (square_dim / 2.0));
-            let spawner_x = BOARD_DIM * x as f32;
-            let spawner_y = -x as f32 * (y as f32 * square_dim) + y_offset;
+                let y_offset = x as f32 * ((height as f32 * square_dim / 2.0) - (square_dim / 2.0));
+                let spawner_x = x as f32 * (width as f32 * square_dim + BOARD_DIM) / 2.0;
+                let spawner_y = -x as f32 * (y as f32 * square_dim) + y_offset;
 
-            commands
-                .spawn(SpriteBundle {
-                    texture: sprites.open.clone_weak(),
-                    transform: Transform::from_xyz(spawner_x, spawner_y, 1.0)
+                commands
+                    .spawn(SpriteBundle {
+                        texture: sprites.open.clone_weak(),
+                        transform: Transform::from_translation(
+                            seat + Vec3::new(spawner_x, spawner_y, 1.0),
+                        )
                         .with_scale(scale.extend(1.0)),
-                    ..default()
-                })
-                .insert(SpawnTile(index as usize));
+                        ..default()
+                    })
+                    .insert((SpawnTile(index), player, GameElement));
+            }
         }
     }
 }
 
-/// Updates the selected board tile drop and multplier text elements
+/// Updates the spawner tiles for each of the turn's drops and the multplier text element
 fn update_board_spawner(
-    // Game state for where the dropper is
-    game_state: Res<GameState>,
+    // Each player's game for where their dropper is
+    boards: Boards,
     // Getting the spawn tile, and it's transform for updating the multiplier text and sprite to update
-    mut children_query: Query<(&SpawnTile, &Transform, &mut Handle<Image>)>,
+    mut children_query: Query<(
+        &SpawnTile,
+        &Player,
+        &Transform,
+        &mut Handle<Image>,
+        &mut Sprite,
+    )>,
     // The multplier text, which is anchored to the dropping tile to make it easy to see
-    mut multiplier_text: Query<&mut Style, With<MultiplierTextContainer>>,
+    mut multiplier_text: Query<(&Player, &mut Style), With<MultiplierTextContainer>>,
     // Global board sprite resources
     sprites: Res<BoardSprites>,
-    // Game settings for window resolution for multiplier calculation
-    game_settings: Res<GameSettings>,
+    // The camera, for where the dropping tile is on screen
+    camera: Query<(&Camera, &GlobalTransform)>,
 ) {
-    let mut mult_text = multiplier_text.single_mut();
-
-    let drop_index = game_state.drop();
-    let insert_side =
-        InsertionDirection::for_board_insertion(game_state.data_board.board(), drop_index).unwrap();
+    let Ok((camera, camera_trans)) = camera.get_single() else {
+        return;
+    };
 
     children_query
         .iter_mut()
-        .for_each(|(tile, trans, mut handle)| {
-            let sprite = if !game_state.dropping && drop_index == tile.0 {
-                // This global transform is anchored in the center of the screen, while UI is TopLeft-TopLeft, so half the screen size needs to be added.
-                // TODO this should be based on dynamic settings, but that isn't implemented yet
-                let centering_vector = game_settings.resolution.medium / 2.0;
-
-                // TODO Very rough numbers, should be done more systematically
-                const OFFSET_VAL_PX: f32 = 64.0;
-                let offset = match insert_side {
-                    InsertionDirection::FromTop => Vec2::new(-OFFSET_VAL_PX * 0.6, -OFFSET_VAL_PX),
-                    InsertionDirection::FromRight => {
-                        Vec2::new(OFFSET_VAL_PX * 0.75, -OFFSET_VAL_PX * 0.15)
-                    }
-                    InsertionDirection::FromBottom => {
-                        Vec2::new(-OFFSET_VAL_PX * 0.8, OFFSET_VAL_PX * 0.75)
-                    }
-                    InsertionDirection::FromLeft => {
-                        Vec2::new(-OFFSET_VAL_PX * 2.5, -OFFSET_VAL_PX * 0.15)
-                    }
-                };
+        .for_each(|(tile, player, trans, mut handle, mut sprite)| {
+            let Some(game_state) = boards.get(*player) else {
+                return;
+            };
 
-                mult_text.left = Val::Px(trans.translation.x + centering_vector.x + offset.x);
-                mult_text.top = Val::Px(-(trans.translation.y - centering_vector.y - offset.y));
+            let drop_index = game_state.session.drop();
+            let turn_drops = game_state.session.turn_drops();
+            let board = game_state.session.board();
+
+            let turn_drop = turn_drops
+                .iter()
+                .find(|(slot, _)| *slot == tile.0)
+                // The current drop's spawner is emptied while its tile is in flight
+                .filter(|(slot, _)| !game_state.dropping || *slot != drop_index);
+
+            let (image, tint) =
+                if let Some((_, next_tile)) = turn_drop {
+                    // The multiplier follows the drop that's up next
+                    let mult_text = multiplier_text
+                        .iter_mut()
+                        .find(|(text_player, _)| *text_player == player);
+
+                    if let (true, Some((_, mut mult_text))) = (tile.0 == drop_index, mult_text) {
+                        let insert_side =
+                            InsertionDirection::for_board_insertion(board.board(), drop_index)
+                                .unwrap();
+
+                        // TODO Very rough numbers, should be done more systematically
+                        const OFFSET_VAL_PX: f32 = 64.0;
+                        let offset = match insert_side {
+                            InsertionDirection::FromTop => {
+                                Vec2::new(-OFFSET_VAL_PX * 0.6, -OFFSET_VAL_PX)
+                            }
+                            InsertionDirection::FromRight => {
+                                Vec2::new(OFFSET_VAL_PX * 0.75, -OFFSET_VAL_PX * 0.15)
+                            }
+                            InsertionDirection::FromBottom => {
+                                Vec2::new(-OFFSET_VAL_PX * 0.8, OFFSET_VAL_PX * 0.75)
+                            }
+                            InsertionDirection::FromLeft => {
+                                Vec2::new(-OFFSET_VAL_PX * 2.5, -OFFSET_VAL_PX * 0.15)
+                            }
+                        };
+
+                        // UI is laid out from the top left of the screen, while the spawner is placed in the world
+                        if let Some(viewport) =
+                            camera.world_to_viewport(camera_trans, trans.translation)
+                        {
+                            mult_text.left = Val::Px(viewport.x + offset.x);
+                            mult_text.top = Val::Px(viewport.y + offset.y);
+                        }
+                    }
 
-                sprites.closed.clone()
-            } else {
-                sprites.open.clone()
-            };
+                    (sprites.closed.clone(), tile_tint(*next_tile))
+                } else {
+                    // The soonest upcoming turn to drop into the slot
+                    let upcoming = game_state.session.upcoming().iter().enumerate().find_map(
+                        |(turn, drops)| {
+                            drops
+                                .iter()
+                                .find(|(x, _)| *x == tile.0)
+                                .map(|(_, tile)| (turn, *tile))
+                        },
+                    );
+
+                    // Marks the held slot and warns about slots that are blocked or about to be,
+                    // otherwise shows faint markers for the upcoming turns
+                    let tint = if game_state.session.held() == Some(tile.0) {
+                        HELD_TINT
+                    } else if !board.is_legal(tile.0) {
+                        BLOCKED_TINT
+                    } else if board.danger_slots().contains(&tile.0) {
+                        DANGER_TINT
+                    } else if let Some((turn, upcoming_tile)) = upcoming {
+                        tile_tint(upcoming_tile).with_a(UPCOMING_MARKER_ALPHA / (turn + 1) as f32)
+                    } else {
+                        Color::WHITE
+                    };
+
+                    (sprites.open.clone(), tint)
+                };
 
-            *handle = sprite;
+            *handle = image;
+            sprite.color = tint;
         });
 }
 
@@ -121,10 +216,9 @@ pub struct SpawnPlugin;
 
 impl Plugin for SpawnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), build_spawners)
-            .add_systems(
-                Update,
-                update_board_spawner.run_if(in_state(AppState::InGame)),
-            );
+        app.add_systems(
+            Update,
+            (build_spawners, update_board_spawner).run_if(in_state(AppState::InGame)),
+        );
     }
 }
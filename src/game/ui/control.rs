@@ -1,11 +1,33 @@
 use std::fmt::{Display, Formatter};
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use strum::{EnumIter, IntoEnumIterator};
 
-use crate::{game::ui::DEFAULT_FONT_PATH, state::AppState};
+use crate::{
+    game::{
+        bindings::ActionMap,
+        settings::{Assist, GameSettings},
+        ui::DEFAULT_FONT_PATH,
+        GameElement,
+    },
+    state::AppState,
+};
 
-#[derive(Component, EnumIter, Debug, Clone, Copy, PartialEq, Eq, Hash, States, Default)]
+#[derive(
+    Component,
+    EnumIter,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    States,
+    Default,
+    Serialize,
+    Deserialize,
+)]
 pub enum ControlPlatform {
     #[default]
     Pc,
@@ -13,13 +35,21 @@ pub enum ControlPlatform {
     Xbox,
 }
 
-#[derive(EnumIter, Debug, Clone, Copy)]
+#[derive(
+    EnumIter, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub enum ControlIntention {
     RankBoost,
+    Hold,
     RotateRight,
     RotateLeft,
+    Flip,
+    SkipRotation,
     ShiftDown,
     ShiftUp,
+    Restart,
+    Pause,
+    Hint,
 }
 
 impl Display for ControlIntention {
@@ -29,56 +59,24 @@ impl Display for ControlIntention {
             ControlIntention::ShiftDown => write!(f, "Shift Block -"),
             ControlIntention::RotateLeft => write!(f, "Rotate Board Counter Clockwise"),
             ControlIntention::RotateRight => write!(f, "Rotate Board Clockwise"),
+            ControlIntention::Flip => write!(f, "Flip Board"),
+            ControlIntention::SkipRotation => write!(f, "Drop Without Rotating"),
             ControlIntention::RankBoost => write!(f, "Use Rank to Boost Multiplier"),
+            ControlIntention::Hold => write!(f, "Hold Drop Slot"),
+            ControlIntention::Restart => write!(f, "Restart"),
+            ControlIntention::Pause => write!(f, "Pause"),
+            ControlIntention::Hint => write!(f, "Hint"),
         }
     }
 }
 
-fn get_image_handle(
-    asset_server: &AssetServer,
-    platform: ControlPlatform,
-    control: ControlIntention,
-) -> Handle<Image> {
-    let path = match (platform, control) {
-        (ControlPlatform::Pc, ControlIntention::ShiftUp) => "sprite/controls/pc/keyboard_w.png",
-        (ControlPlatform::Pc, ControlIntention::ShiftDown) => "sprite/controls/pc/keyboard_s.png",
-        (ControlPlatform::Pc, ControlIntention::RotateLeft) => "sprite/controls/pc/keyboard_a.png",
-        (ControlPlatform::Pc, ControlIntention::RotateRight) => "sprite/controls/pc/keyboard_d.png",
-        (ControlPlatform::Pc, ControlIntention::RankBoost) => "sprite/controls/pc/keyboard_e.png",
-        (ControlPlatform::Steamdeck, ControlIntention::ShiftUp) => {
-            "sprite/controls/steamdeck/steamdeck_dpad_up_outline.png"
-        }
-        (ControlPlatform::Steamdeck, ControlIntention::ShiftDown) => {
-            "sprite/controls/steamdeck/steamdeck_dpad_down_outline.png"
-        }
-        (ControlPlatform::Steamdeck, ControlIntention::RotateLeft) => {
-            "sprite/controls/steamdeck/steamdeck_button_l1.png"
-        }
-        (ControlPlatform::Steamdeck, ControlIntention::RotateRight) => {
-            "sprite/controls/steamdeck/steamdeck_button_r1.png"
-        }
-        (ControlPlatform::Steamdeck, ControlIntention::RankBoost) => {
-            "sprite/controls/steamdeck/steamdeck_button_a.png"
-        }
-        (ControlPlatform::Xbox, ControlIntention::ShiftUp) => {
-            "sprite/controls/xbox/xbox_dpad_up_outline.png"
-        }
-        (ControlPlatform::Xbox, ControlIntention::ShiftDown) => {
-            "sprite/controls/xbox/xbox_dpad_down_outline.png"
-        }
-        (ControlPlatform::Xbox, ControlIntention::RotateLeft) => "sprite/controls/xbox/xbox_lb.png",
-        (ControlPlatform::Xbox, ControlIntention::RotateRight) => {
-            "sprite/controls/xbox/xbox_rb.png"
-        }
-        (ControlPlatform::Xbox, ControlIntention::RankBoost) => {
-            "sprite/controls/xbox/xbox_button_a.png"
-        }
-    };
-
-    asset_server.load(path)
-}
-
-pub fn build_control_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn build_control_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    action_map: Res<ActionMap>,
+    settings: Res<GameSettings>,
+    active_platform: Res<State<ControlPlatform>>,
+) {
     const MARGIN: Val = Val::Px(3.0);
     const SIZE: Val = Val::Px(36.0);
 
@@ -95,13 +93,23 @@ pub fn build_control_ui(mut commands: Commands, asset_server: Res<AssetServer>)
             },
             ..Default::default()
         })
+        .insert(GameElement)
         .with_children(|builder| {
             for platform in ControlPlatform::iter() {
                 for control in ControlIntention::iter() {
+                    if control == ControlIntention::Hint && settings.assist != Assist::Hint {
+                        continue;
+                    }
+
+                    // Unbound controls have nothing to show
+                    let Some(input) = action_map.hint(control, platform) else {
+                        continue;
+                    };
+
                     let style = Style {
                         flex_direction: FlexDirection::Row,
                         align_items: AlignItems::Center,
-                        display: if platform == ControlPlatform::Pc {
+                        display: if platform == *active_platform.get() {
                             bevy::ui::Display::default()
                         } else {
                             bevy::ui::Display::None
@@ -118,18 +126,51 @@ pub fn build_control_ui(mut commands: Commands, asset_server: Res<AssetServer>)
                             platform,
                         ))
                         .with_children(|builder| {
-                            builder.spawn((
-                                NodeBundle {
-                                    style: Style {
-                                        width: SIZE,
-                                        height: SIZE,
-                                        ..Default::default()
-                                    },
-                                    background_color: Color::WHITE.into(),
-                                    ..Default::default()
-                                },
-                                UiImage::new(get_image_handle(&asset_server, platform, control)),
-                            ));
+                            let glyph = Style {
+                                width: SIZE,
+                                height: SIZE,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            };
+
+                            match input.glyph(platform) {
+                                Some(path) => {
+                                    builder.spawn((
+                                        NodeBundle {
+                                            style: glyph,
+                                            background_color: Color::WHITE.into(),
+                                            ..Default::default()
+                                        },
+                                        UiImage::new(asset_server.load(path)),
+                                    ));
+                                }
+                                // No glyph for the input, so its name is shown on a plain key instead
+                                None => {
+                                    builder
+                                        .spawn(NodeBundle {
+                                            style: Style {
+                                                min_width: SIZE,
+                                                width: Val::Auto,
+                                                padding: UiRect::horizontal(MARGIN),
+                                                border: UiRect::all(Val::Px(2.0)),
+                                                ..glyph
+                                            },
+                                            border_color: Color::WHITE.into(),
+                                            ..Default::default()
+                                        })
+                                        .with_children(|builder| {
+                                            builder.spawn(TextBundle::from_section(
+                                                input.label(),
+                                                TextStyle {
+                                                    font: asset_server.load(DEFAULT_FONT_PATH),
+                                                    font_size: 14.0,
+                                                    ..Default::default()
+                                                },
+                                            ));
+                                        });
+                                }
+                            }
 
                             builder.spawn(TextBundle::from_section(
                                 format!("{control}"),
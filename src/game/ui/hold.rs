@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+use crate::game::board::{sprite::HELD_TINT, Boards, Player};
+
+#[derive(Component)]
+pub struct HoldText;
+
+/// Shows whether a slot is held and if it can be swapped in this turn.
+/// The held slot itself is marked on the spawners.
+pub fn display_hold(boards: Boards, mut text: Query<(&Player, &mut Text), With<HoldText>>) {
+    for (player, mut text) in &mut text {
+        let Some(state) = boards.get(*player) else {
+            continue;
+        };
+        let section = &mut text.sections[0];
+
+        (section.value, section.style.color) =
+            match (state.session.held(), state.session.can_hold()) {
+                (None, _) => (String::from("Hold Empty"), Color::GRAY),
+                (Some(_), true) => (String::from("Hold Ready"), HELD_TINT),
+                (Some(_), false) => (String::from("Hold Used"), Color::GRAY),
+            };
+    }
+}
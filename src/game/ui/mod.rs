@@ -1,7 +1,11 @@
 mod control;
+mod hold;
 pub mod multiplier;
+mod preview;
+mod puzzle;
 mod rank;
 mod score_effect;
+mod timer;
 
 use bevy_progressbar::{ProgressBar, ProgressBarBundle, ProgressBarMaterial};
 use multiplier::{
@@ -10,21 +14,36 @@ use multiplier::{
 };
 pub use score_effect::ResetScoreboard;
 
-use bevy::{app::PluginGroupBuilder, prelude::*};
+use bevy::{app::PluginGroupBuilder, prelude::*, utils::HashMap};
 use control::{build_control_ui, update_controls_ui};
+use hold::{display_hold, HoldText};
+use preview::{display_preview, setup_preview};
+use puzzle::{display_puzzle, PuzzleText};
 use rank::{detect_rank_up, display_rank, display_rank_progress, RankProgress, RankText};
 use score_effect::{OnScoreEvent, ScoreEffectPlugin};
+use timer::{display_timer, TimerText};
 
 use crate::{
     audio::{PlaySoundEffect, SoundEffect},
+    logic::{leaderboard::format_time, mode::GameModeKind, puzzle::PuzzleStatus},
     state::AppState,
 };
-pub use control::ControlPlatform;
+pub use control::{ControlIntention, ControlPlatform};
 
-use super::board::state::{GameMode, GameState};
+use super::{
+    bindings::ActionMap,
+    board::{
+        state::{start_game, GameMode},
+        Board, Boards, Player,
+    },
+    leaderboard::GameResult,
+    netplay::NetplayMatch,
+    versus::{winner, VersusMatch},
+    GameElement,
+};
 
-pub const DEFAULT_FONT_PATH: &'static str = "fonts/OxygenMono-Regular.ttf";
-pub const RANK_FONT_PATH: &'static str = "fonts/ASIX-FOUNDER.otf";
+pub const DEFAULT_FONT_PATH: &str = "fonts/OxygenMono-Regular.ttf";
+pub const RANK_FONT_PATH: &str = "fonts/ASIX-FOUNDER.otf";
 
 #[derive(Default, Component)]
 pub struct ScoreText;
@@ -44,61 +63,188 @@ struct LocalScoreboardState {
 }
 
 fn display_scoring(
-    state: Res<GameState>,
+    boards: Boards,
     time: Res<Time>,
-    mut current_state: Local<LocalScoreboardState>,
-    mut text: Query<&mut Text, With<ScoreText>>,
+    mut current_states: Local<HashMap<Player, LocalScoreboardState>>,
+    mut text: Query<(&Player, &mut Text), With<ScoreText>>,
     mut score_effect: EventWriter<OnScoreEvent>,
     mut reset_scoreboard: EventReader<ResetScoreboard>,
     mut sfx: EventWriter<PlaySoundEffect>,
 ) {
     if reset_scoreboard.read().next().is_some() {
-        *current_state = LocalScoreboardState::default();
+        current_states.clear();
     }
 
-    let state_score = state.data_board.score().score();
-    if state_score > current_state.target {
-        // There is no better spot to detect that things have been scored...
-        // I really should've been an event hook system
-        sfx.send(PlaySoundEffect(SoundEffect::Clear));
+    for (player, state) in boards.iter() {
+        let current_state = current_states.entry(player).or_default();
+        let mut text = text.iter_mut().find(|(x, _)| **x == player);
 
-        let diff = state_score.saturating_sub(current_state.target);
-        score_effect.send(OnScoreEvent(diff));
-        current_state.target = state_score;
-    }
+        let state_score = state.session.board().score().score();
+        if state_score > current_state.target {
+            // There is no better spot to detect that things have been scored...
+            // I really should've been an event hook system
+            sfx.send(PlaySoundEffect(SoundEffect::Clear));
 
-    if !current_state.first_time_set {
-        current_state.first_time_set = true;
-        let mut text = text.single_mut();
-        text.sections[0].value = format!("{:0>9}0", 0);
-    }
+            let diff = state_score.saturating_sub(current_state.target);
+            score_effect.send(OnScoreEvent(player, diff));
+            current_state.target = state_score;
+        }
 
-    // Score is always monotonically increasing, so this logic assumes always going up
-    if current_state.target > current_state.current {
-        current_state.timer += time.delta_seconds();
+        if !current_state.first_time_set {
+            current_state.first_time_set = true;
+            if let Some((_, text)) = &mut text {
+                text.sections[0].value = format!("{:0>9}0", 0);
+            }
+        }
+
+        // Score is always monotonically increasing, so this logic assumes always going up
+        if current_state.target > current_state.current {
+            current_state.timer += time.delta_seconds();
 
-        if current_state.timer >= 0.1 {
-            current_state.timer = 0.0;
-            current_state.current += 1;
-            let mut text = text.single_mut();
-            text.sections[0].value = format!("{:0>9}0", current_state.current);
+            if current_state.timer >= 0.1 {
+                current_state.timer = 0.0;
+                current_state.current += 1;
+                if let Some((_, text)) = &mut text {
+                    text.sections[0].value = format!("{:0>9}0", current_state.current);
+                }
+            }
         }
     }
 }
 
-fn display_game_over(state: Res<GameState>, mut text: Query<&mut Text, With<GameOverText>>) {
+fn display_game_over(
+    boards: Boards,
+    versus: Option<Res<VersusMatch>>,
+    netplay: Option<Res<NetplayMatch>>,
+    result: Res<GameResult>,
+    action_map: Res<ActionMap>,
+    platform: Res<State<ControlPlatform>>,
+    mut text: Query<&mut Text, With<GameOverText>>,
+) {
     let mut text = text.single_mut();
-    text.sections[0].value = if state.mode == GameMode::GameOver {
-        String::from("Game Over, Press R to Restart")
-    } else {
-        String::new()
+    let section = &mut text.sections[0];
+
+    let restart = action_map
+        .hint(ControlIntention::Restart, *platform.get())
+        .map(|input| format!("Press {} to Restart", input.label()))
+        // Netplay matches can't be restarted
+        .filter(|_| netplay.is_none());
+
+    // A netplay match cut short by the connection or a desync has no winner
+    if let Some(failure) = netplay.as_ref().and_then(|x| x.failure.as_ref()) {
+        (section.value, section.style.color) = (failure.to_string(), Color::RED);
+        return;
+    }
+
+    // A match is over for both players at once, and is won by whoever's board is still standing
+    if versus.is_some() {
+        let over = boards.iter().all(|(_, state)| state.mode.is_over());
+
+        (section.value, section.style.color) = match (over, winner(boards.iter())) {
+            (false, _) => (String::new(), Color::WHITE),
+            (true, Some(player)) => (
+                format!("{} Wins! {}", player.label(), restart.unwrap_or_default()),
+                Color::GOLD,
+            ),
+            (true, None) => (
+                format!("Draw! {}", restart.unwrap_or_default()),
+                Color::WHITE,
+            ),
+        };
+        return;
+    }
+
+    let Some(state) = boards.get(Player::One) else {
+        return;
+    };
+    let session = &state.session;
+
+    (section.value, section.style.color) = match (&state.mode, restart) {
+        (GameMode::Results, restart) => {
+            // What the game was played to
+            let summary = match (session.puzzle_status(), session.config().mode) {
+                (Some(PuzzleStatus::Solved), _) => {
+                    format!("Solved in {} moves!", session.turns_played())
+                }
+                (Some(_), _) => String::from("Out of Moves."),
+                (None, GameModeKind::Sprint) => {
+                    format!("Finished in {}!", format_time(session.elapsed()))
+                }
+                (None, _) => String::from("Time's Up!"),
+            };
+
+            let best = if result.new_best() {
+                "New Personal Best! "
+            } else {
+                ""
+            };
+
+            let color = match session.puzzle_status() {
+                Some(PuzzleStatus::Failed) => Color::RED,
+                Some(_) => Color::GOLD,
+                None if result.new_best() => Color::GOLD,
+                None => Color::WHITE,
+            };
+
+            (
+                format!("{best}{summary} {}", restart.unwrap_or_default()),
+                color,
+            )
+        }
+        (GameMode::GameOver, restart) if result.new_best() => (
+            format!("New Personal Best! {}", restart.unwrap_or_default()),
+            Color::GOLD,
+        ),
+        (GameMode::GameOver, Some(restart)) => (format!("Game Over, {restart}"), Color::RED),
+        (GameMode::GameOver, None) => (String::from("Game Over"), Color::RED),
+        (GameMode::Playing, _) => (String::new(), Color::RED),
     };
 }
 
+/// Adds the HUD for each board. In versus the second player's score sits in the top right, and each is labelled.
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ProgressBarMaterial>>,
+    boards: Query<&Player, With<Board>>,
+    versus: Option<Res<VersusMatch>>,
+) {
+    for &player in &boards {
+        setup_player(
+            &mut commands,
+            &asset_server,
+            &mut materials,
+            player,
+            versus.is_some(),
+        );
+    }
+
+    commands.spawn((
+        TextBundle::from_section(
+            "Game Over",
+            TextStyle {
+                font: asset_server.load(DEFAULT_FONT_PATH),
+                font_size: 64.0,
+                color: Color::RED,
+            },
+        )
+        .with_text_justify(JustifyText::Left)
+        .with_style(Style {
+            top: Val::Px(30.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        GameOverText,
+        GameElement,
+    ));
+}
+
+fn setup_player(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    materials: &mut ResMut<Assets<ProgressBarMaterial>>,
+    player: Player,
+    versus: bool,
 ) {
     // Multiplier Text that follows block placement
     commands
@@ -110,7 +256,7 @@ fn setup(
             },
             ..Default::default()
         })
-        .insert(MultiplierTextContainer)
+        .insert((MultiplierTextContainer, player, GameElement))
         .with_children(|builder| {
             builder.spawn((
                 TextBundle::from_section(
@@ -123,6 +269,7 @@ fn setup(
                 )
                 .with_text_justify(JustifyText::Center),
                 MultiplierText,
+                player,
             ));
 
             let style = Style {
@@ -135,24 +282,46 @@ fn setup(
                 .spawn(ProgressBarBundle::new(
                     style,
                     ProgressBar::new(vec![(1, Color::ORANGE)]),
-                    &mut materials,
+                    materials,
                 ))
-                .insert(RankBoostDuration);
+                .insert((RankBoostDuration, player));
         });
 
-    // Top Left Score Bundle
+    // Top Left Score Bundle, or Top Right for the second player
+    let corner = match player {
+        Player::One => Style::default(),
+        Player::Two => Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            right: Val::Px(0.0),
+            align_items: AlignItems::End,
+            ..Default::default()
+        },
+    };
+
     commands
         .spawn(NodeBundle {
             style: Style {
                 flex_direction: FlexDirection::Column,
                 align_content: AlignContent::End,
                 margin: UiRect::axes(Val::Px(10.0), Val::Px(10.0)),
-                ..Default::default()
+                ..corner
             },
             ..Default::default()
         })
-        .insert(ScoreTextContainer)
+        .insert((ScoreTextContainer, player, GameElement))
         .with_children(|builder| {
+            if versus {
+                builder.spawn(TextBundle::from_section(
+                    player.label(),
+                    TextStyle {
+                        font: asset_server.load(RANK_FONT_PATH),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                ));
+            }
+
             builder
                 .spawn(NodeBundle {
                     style: Style {
@@ -181,6 +350,7 @@ fn setup(
                             },
                         ),
                         RankText,
+                        player,
                     ));
                 });
 
@@ -195,9 +365,9 @@ fn setup(
                 .spawn(ProgressBarBundle::new(
                     style,
                     ProgressBar::new(vec![(1, Color::WHITE)]),
-                    &mut materials,
+                    materials,
                 ))
-                .insert(RankProgress);
+                .insert((RankProgress, player));
 
             builder.spawn((
                 TextBundle::from_section(
@@ -209,27 +379,48 @@ fn setup(
                     },
                 ),
                 ScoreText,
+                player,
             ));
-        });
 
-    commands.spawn((
-        TextBundle::from_section(
-            "Game Over",
-            TextStyle {
-                font: asset_server.load(DEFAULT_FONT_PATH),
-                font_size: 64.0,
-                color: Color::RED,
-                ..default()
-            },
-        )
-        .with_text_justify(JustifyText::Left)
-        .with_style(Style {
-            top: Val::Px(30.0),
-            left: Val::Px(10.0),
-            ..default()
-        }),
-        GameOverText,
-    ));
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(DEFAULT_FONT_PATH),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                ),
+                TimerText,
+                player,
+            ));
+
+            builder.spawn((
+                TextBundle::from_section(
+                    "Hold Empty",
+                    TextStyle {
+                        font: asset_server.load(DEFAULT_FONT_PATH),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ),
+                HoldText,
+                player,
+            ));
+
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(DEFAULT_FONT_PATH),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ),
+                PuzzleText,
+                player,
+            ));
+        });
 }
 
 pub struct UiPlugins;
@@ -246,21 +437,34 @@ struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), (setup, build_control_ui))
-            .add_systems(
-                PostUpdate,
-                (
-                    display_scoring,
-                    display_game_over,
-                    display_rank,
-                    display_rank_progress,
-                    display_mult,
-                    display_rank_boost_mult,
-                    detect_rank_up,
-                )
-                    .run_if(in_state(AppState::InGame)),
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            (
+                setup,
+                setup_preview,
+                // Hints are left out of versus, there's no room for a set for each player
+                build_control_ui.run_if(not(resource_exists::<VersusMatch>)),
             )
-            .add_systems(Update, update_controls_ui)
-            .init_state::<ControlPlatform>();
+                .after(start_game),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                display_scoring,
+                display_game_over,
+                display_rank,
+                display_rank_progress,
+                display_hold,
+                display_preview,
+                display_puzzle,
+                display_timer,
+                display_mult,
+                display_rank_boost_mult,
+                detect_rank_up,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(Update, update_controls_ui)
+        .init_state::<ControlPlatform>();
     }
 }
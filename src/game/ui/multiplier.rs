@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_progressbar::ProgressBar;
 
-use crate::game::board::state::GameState;
+use crate::game::board::{Boards, Player};
 
 #[derive(Component)]
 pub struct MultiplierText;
@@ -13,27 +13,38 @@ pub struct MultiplierTextContainer;
 
 pub struct RankBoostDuration;
 
-pub fn display_mult(state: Res<GameState>, mut text: Query<&mut Text, With<MultiplierText>>) {
-    let mut text = text.single_mut();
-
-    text.sections[0].value = format!("{:.1}x", state.data_board.score().current_mult());
+pub fn display_mult(boards: Boards, mut text: Query<(&Player, &mut Text), With<MultiplierText>>) {
+    for (player, mut text) in &mut text {
+        if let Some(state) = boards.get(*player) {
+            text.sections[0].value =
+                format!("{:.1}x", state.session.board().score().current_mult());
+        }
+    }
 }
 
 // Make the display orange during the duration of the rank boost and show progress bar
 pub fn display_rank_boost_mult(
-    state: Res<GameState>,
-    mut text: Query<&mut Text, With<MultiplierText>>,
-    mut progress_bar: Query<&mut ProgressBar, With<RankBoostDuration>>,
+    boards: Boards,
+    mut text: Query<(&Player, &mut Text), With<MultiplierText>>,
+    mut progress_bar: Query<(&Player, &mut ProgressBar), With<RankBoostDuration>>,
 ) {
-    let mut text = text.single_mut();
-    let mut progress_bar = progress_bar.single_mut();
-
-    if let Some(percent) = state.data_board.score().current_rank_boost_percentage() {
-        text.sections[0].style.color = Color::ORANGE;
-        progress_bar.set_progress(percent);
-    } else {
-        text.sections[0].style.color = Color::WHITE;
-        progress_bar.set_progress(0.0);
+    for (player, state) in boards.iter() {
+        let boost = state
+            .session
+            .board()
+            .score()
+            .current_rank_boost_percentage();
+
+        for (_, mut text) in text.iter_mut().filter(|(x, _)| **x == player) {
+            text.sections[0].style.color = match boost {
+                Some(_) => Color::ORANGE,
+                None => Color::WHITE,
+            };
+        }
+
+        for (_, mut progress_bar) in progress_bar.iter_mut().filter(|(x, _)| **x == player) {
+            progress_bar.set_progress(boost.unwrap_or(0.0));
+        }
     }
 }
 
@@ -0,0 +1,155 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    game::{
+        board::{
+            sprite::{tile_tint, BoardSprites},
+            Board, Boards, Player,
+        },
+        versus::VersusMatch,
+        GameElement,
+    },
+    logic::tile::Tile,
+};
+
+use super::DEFAULT_FONT_PATH;
+
+const TILE_SIZE: Val = Val::Px(24.0);
+const GAP: Val = Val::Px(4.0);
+
+/// Holds a row of tiles for each of a player's upcoming turns
+#[derive(Component)]
+pub struct PreviewStrip;
+
+/// Adds a preview for each board, in the top right when playing alone or below each board in versus
+pub fn setup_preview(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    boards: Query<&Player, With<Board>>,
+    versus: Option<Res<VersusMatch>>,
+) {
+    for player in &boards {
+        let (corner, align_items) = match (versus.is_some(), player) {
+            (false, _) => (
+                Style {
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                AlignItems::End,
+            ),
+            (true, Player::One) => (
+                Style {
+                    bottom: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                AlignItems::Start,
+            ),
+            (true, Player::Two) => (
+                Style {
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                AlignItems::End,
+            ),
+        };
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items,
+                    row_gap: GAP,
+                    ..corner
+                },
+                ..Default::default()
+            })
+            .insert(GameElement)
+            .with_children(|builder| {
+                builder.spawn(TextBundle::from_section(
+                    "Next",
+                    TextStyle {
+                        font: asset_server.load(DEFAULT_FONT_PATH),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ));
+
+                builder.spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items,
+                            row_gap: GAP,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    PreviewStrip,
+                    *player,
+                ));
+            });
+    }
+}
+
+/// Rebuilds each player's strip whenever their upcoming turns change
+pub fn display_preview(
+    mut commands: Commands,
+    boards: Boards,
+    sprites: Res<BoardSprites>,
+    strips: Query<(Entity, &Player, Ref<PreviewStrip>)>,
+    mut shown: Local<HashMap<Player, Vec<Vec<Tile>>>>,
+) {
+    for (strip, player, marker) in &strips {
+        let Some(state) = boards.get(*player) else {
+            continue;
+        };
+
+        let upcoming = state
+            .session
+            .upcoming()
+            .iter()
+            .map(|drops| drops.iter().map(|(_, tile)| *tile).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        // The strip is respawned with each game, so it's always filled on its first frame
+        if shown.get(player) == Some(&upcoming) && !marker.is_added() {
+            continue;
+        }
+        shown.insert(*player, upcoming.clone());
+
+        commands.entity(strip).despawn_descendants();
+        commands.entity(strip).with_children(|builder| {
+            for tiles in upcoming {
+                builder
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: GAP,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with_children(|builder| {
+                        for tile in tiles {
+                            builder.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        width: TILE_SIZE,
+                                        height: TILE_SIZE,
+                                        ..Default::default()
+                                    },
+                                    background_color: tile_tint(tile).into(),
+                                    ..Default::default()
+                                },
+                                UiImage::new(sprites.closed.clone()),
+                            ));
+                        }
+                    });
+            }
+        });
+    }
+}
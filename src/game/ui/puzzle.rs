@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+
+use crate::game::board::{Boards, Player};
+
+#[derive(Component)]
+pub struct PuzzleText;
+
+/// Shows the puzzle's goal and the moves left to reach it, left empty outside of puzzles
+pub fn display_puzzle(boards: Boards, mut text: Query<(&Player, &mut Text), With<PuzzleText>>) {
+    for (player, mut text) in &mut text {
+        let Some(state) = boards.get(*player) else {
+            continue;
+        };
+        let section = &mut text.sections[0];
+
+        let session = &state.session;
+        section.value = match &session.config().puzzle {
+            Some(level) => {
+                let moves_left = level.move_limit().saturating_sub(session.turns_played());
+                let plural = if moves_left == 1 { "" } else { "s" };
+                format!(
+                    "{}, {moves_left} move{plural} left",
+                    level.goal().describe()
+                )
+            }
+            None => String::new(),
+        };
+    }
+}
@@ -1,51 +1,62 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_progressbar::ProgressBar;
 
 use crate::{
     audio::{PlaySoundEffect, SoundEffect},
-    game::board::state::GameState,
+    game::board::{Boards, Player},
 };
 
 #[derive(Component)]
 pub struct RankText;
 
-pub fn display_rank(state: Res<GameState>, mut text: Query<&mut Text, With<RankText>>) {
-    let mut text = text.single_mut();
-    text.sections[0].value = format!("{}", state.data_board.score().rank());
+pub fn display_rank(boards: Boards, mut text: Query<(&Player, &mut Text), With<RankText>>) {
+    for (player, mut text) in &mut text {
+        if let Some(state) = boards.get(*player) {
+            text.sections[0].value = format!("{}", state.session.board().score().rank());
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct RankProgress;
 
 pub fn display_rank_progress(
-    state: Res<GameState>,
-    mut progress_bar: Query<&mut ProgressBar, With<RankProgress>>,
+    boards: Boards,
+    mut progress_bar: Query<(&Player, &mut ProgressBar), With<RankProgress>>,
     time: Res<Time>,
 ) {
-    let mut bar = progress_bar.single_mut();
+    for (player, mut bar) in &mut progress_bar {
+        let Some(state) = boards.get(*player) else {
+            continue;
+        };
 
-    let progress_to_next_rank = state.data_board.score().percent_to_next_rank();
-    let progress_diff = progress_to_next_rank - bar.get_progress();
+        let progress_to_next_rank = state.session.board().score().percent_to_next_rank();
+        let progress_diff = progress_to_next_rank - bar.get_progress();
 
-    let progress = if progress_diff.is_sign_positive() {
-        bar.get_progress() + (progress_diff * time.delta_seconds())
-    } else {
-        progress_to_next_rank
-    };
+        let progress = if progress_diff.is_sign_positive() {
+            bar.get_progress() + (progress_diff * time.delta_seconds())
+        } else {
+            progress_to_next_rank
+        };
 
-    bar.set_progress(progress);
+        bar.set_progress(progress);
+    }
 }
 
 pub fn detect_rank_up(
-    mut rank: Local<u32>,
-    state: Res<GameState>,
+    mut ranks: Local<HashMap<Player, u32>>,
+    boards: Boards,
     mut sfx: EventWriter<PlaySoundEffect>,
 ) {
-    let game_rank = state.data_board.score().rank();
-    if *rank != game_rank {
-        if game_rank > *rank {
-            sfx.send(PlaySoundEffect(SoundEffect::LevelUp));
+    for (player, state) in boards.iter() {
+        let rank = ranks.entry(player).or_default();
+
+        let game_rank = state.session.board().score().rank();
+        if *rank != game_rank {
+            if game_rank > *rank {
+                sfx.send(PlaySoundEffect(SoundEffect::LevelUp));
+            }
+            *rank = game_rank;
         }
-        *rank = game_rank;
     }
 }
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::game::board::Player;
+
 use super::{ScoreTextContainer, DEFAULT_FONT_PATH};
 
 #[derive(Debug, Default, Component)]
@@ -49,28 +51,27 @@ fn animate_fading_text(
     }
 }
 
+/// Points scored on a player's board, shown floating off their score
 #[derive(Event, Default)]
-pub struct OnScoreEvent(pub u64);
+pub struct OnScoreEvent(pub Player, pub u64);
 
 #[derive(Event, Default)]
 pub struct ResetScoreboard;
 
 pub fn on_score_event_effect(
     mut reader: EventReader<OnScoreEvent>,
-    query: Query<Entity, With<ScoreTextContainer>>,
+    query: Query<(Entity, &Player), With<ScoreTextContainer>>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
 ) {
-    if reader.is_empty() {
-        return;
-    }
-
-    let container = query.single();
+    for OnScoreEvent(player, points) in reader.read() {
+        let Some((container, _)) = query.iter().find(|(_, x)| *x == player) else {
+            continue;
+        };
 
-    for score_occured in reader.read() {
         commands.entity(container).with_children(|builder| {
             FadingText {
-                text: format!("+{}0", score_occured.0),
+                text: format!("+{points}0"),
                 style: TextStyle {
                     font: asset_server.load(DEFAULT_FONT_PATH),
                     font_size: 28.0,
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::board::{Boards, Player},
+    logic::{
+        leaderboard::format_time,
+        mode::{GameModeKind, SPRINT_LINES},
+    },
+};
+
+/// Seconds left on the clock when the timer starts warning that time's nearly up
+const TIME_WARNING_SECS: f32 = 10.0;
+
+#[derive(Component)]
+pub struct TimerText;
+
+/// Shows the game clock: time played, along with the lines left in a sprint, or the time left in an ultra game.
+/// Puzzles count moves instead, so it's left empty for them.
+pub fn display_timer(boards: Boards, mut text: Query<(&Player, &mut Text), With<TimerText>>) {
+    for (player, mut text) in &mut text {
+        let Some(state) = boards.get(*player) else {
+            continue;
+        };
+        let section = &mut text.sections[0];
+
+        let session = &state.session;
+        if session.config().puzzle.is_some() {
+            section.value = String::new();
+            continue;
+        }
+
+        (section.value, section.style.color) = match session.config().mode {
+            GameModeKind::Endless => (format_time(session.elapsed()), Color::WHITE),
+            GameModeKind::Sprint => (
+                format!(
+                    "{} {}/{SPRINT_LINES} Lines",
+                    format_time(session.elapsed()),
+                    session.board().lines_cleared().min(SPRINT_LINES)
+                ),
+                Color::WHITE,
+            ),
+            GameModeKind::Ultra => {
+                let time_left = session.time_left().unwrap_or_default();
+                let color = if time_left < TIME_WARNING_SECS {
+                    Color::ORANGE
+                } else {
+                    Color::WHITE
+                };
+
+                (format_time(time_left), color)
+            }
+        };
+    }
+}
@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+use crate::{
+    logic::{garbage, mode::GameModeKind, session::SessionConfig},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    board::{
+        rotate::RotateBoard,
+        state::{GameMode, GameState, LinesCleared},
+        Board, Player,
+    },
+    netplay::NetplayMatch,
+};
+
+/// How far each board sits from the middle of the screen in versus
+const SEAT_OFFSET: f32 = 300.0;
+/// How far the camera zooms out in versus to fit both boards
+const VERSUS_ZOOM: f32 = 1.6;
+
+/// Requests a local versus match from the main menu
+#[derive(Event, Default)]
+pub struct PlayVersus;
+
+/// A local two player match is being played, with a board for each [`Player`]
+#[derive(Resource)]
+pub struct VersusMatch;
+
+impl VersusMatch {
+    /// Matches are played on the configured board until one player runs out of space,
+    /// so there's no end condition to race to or puzzle to solve
+    pub fn session_config(&self, base: SessionConfig) -> SessionConfig {
+        SessionConfig {
+            mode: GameModeKind::Endless,
+            puzzle: None,
+            ..base
+        }
+    }
+}
+
+/// Where a player's board sits. Games played alone are in the middle of the screen.
+pub fn seat_translation(player: Player, versus: bool) -> Vec3 {
+    if !versus {
+        return Vec3::ZERO;
+    }
+
+    match player {
+        Player::One => Vec3::new(-SEAT_OFFSET, 0.0, 0.0),
+        Player::Two => Vec3::new(SEAT_OFFSET, 0.0, 0.0),
+    }
+}
+
+fn start_versus(
+    mut commands: Commands,
+    mut play_versus: EventReader<PlayVersus>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if play_versus.read().last().is_none() {
+        return;
+    }
+
+    commands.insert_resource(VersusMatch);
+    next_state.set(AppState::InGame);
+}
+
+/// Zooms out to fit both boards on screen
+fn zoom_out(mut projection: Query<&mut OrthographicProjection, With<Camera>>) {
+    for mut projection in &mut projection {
+        projection.scale = VERSUS_ZOOM;
+    }
+}
+
+fn reset_zoom(mut projection: Query<&mut OrthographicProjection, With<Camera>>) {
+    for mut projection in &mut projection {
+        projection.scale = 1.0;
+    }
+}
+
+/// Sends garbage to the opponent for lines cleared together.
+/// Garbage still on its way to the sender is cancelled out first.
+fn send_garbage(
+    mut lines_cleared: EventReader<LinesCleared>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
+) {
+    for LinesCleared { player, lines } in lines_cleared.read() {
+        let mut sent = garbage::lines_sent(*lines);
+
+        for (board_player, mut game_state) in &mut boards {
+            if board_player == player {
+                let cancelled = sent.min(game_state.incoming_garbage);
+                game_state.incoming_garbage -= cancelled;
+                sent -= cancelled;
+            }
+        }
+
+        for (board_player, mut game_state) in &mut boards {
+            if *board_player == player.opponent() {
+                game_state.incoming_garbage += sent;
+            }
+        }
+    }
+}
+
+/// Pushes garbage onto boards once they're between turns, so it never lands under a tile that's mid-drop
+fn receive_garbage(mut boards: Query<(&mut GameState, Has<RotateBoard>), With<Board>>) {
+    for (mut game_state, rotating) in &mut boards {
+        let idle = game_state.mode == GameMode::Playing
+            && !game_state.dropping
+            && !game_state.session.mid_turn()
+            && !rotating;
+
+        if !idle || game_state.incoming_garbage == 0 {
+            continue;
+        }
+
+        let lines = std::mem::take(&mut game_state.incoming_garbage);
+        game_state.session.receive_garbage(lines);
+
+        if !game_state.session.has_legal_move() {
+            info!("Buried by garbage");
+            game_state.game_over();
+        }
+    }
+}
+
+/// Ends the match as soon as a board is lost, the other player having won it
+fn end_match(
+    netplay: Option<Res<NetplayMatch>>,
+    mut boards: Query<(&Player, &mut GameState), With<Board>>,
+) {
+    if !boards
+        .iter()
+        .any(|(_, game_state)| game_state.mode == GameMode::GameOver)
+    {
+        return;
+    }
+
+    // Over netplay the other board may still have the turn to play that this one was lost on,
+    // which could lose it too, so both peers wait on it to agree on how the match ended
+    let catching_up = netplay.is_some_and(|netplay| {
+        netplay.awaiting_turn()
+            && boards.iter().any(|(player, game_state)| {
+                *player != netplay.local && game_state.mode == GameMode::Playing
+            })
+    });
+    if catching_up {
+        return;
+    }
+
+    for (_, mut game_state) in &mut boards {
+        if game_state.mode == GameMode::Playing {
+            game_state.finish();
+        }
+    }
+}
+
+/// The player who won the match, or `None` while it's still being played or if both lost at once
+pub fn winner<'a>(mut boards: impl Iterator<Item = (Player, &'a GameState)>) -> Option<Player> {
+    boards
+        .find(|(_, game_state)| game_state.mode == GameMode::Results)
+        .map(|(player, _)| player)
+}
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayVersus>()
+            .add_systems(Update, start_versus.run_if(in_state(AppState::MainMenu)))
+            .add_systems(
+                OnEnter(AppState::InGame),
+                zoom_out.run_if(resource_exists::<VersusMatch>),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_zoom)
+            .add_systems(
+                Update,
+                (
+                    // Netplay lands garbage on its own, in lockstep with the other peer
+                    (send_garbage, receive_garbage).run_if(not(resource_exists::<NetplayMatch>)),
+                    end_match,
+                )
+                    .chain()
+                    .run_if(
+                        in_state(AppState::InGame)
+                            .and_then(in_state(PauseState::Running))
+                            .and_then(resource_exists::<VersusMatch>),
+                    ),
+            );
+    }
+}
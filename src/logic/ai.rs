@@ -0,0 +1,368 @@
+use std::ops::Range;
+
+use nalgebra::DMatrix;
+use strum::{EnumIter, IntoEnumIterator};
+
+use super::{
+    board::GameBoard,
+    session::{GameAction, GameSession, SessionConfig},
+    tile::Tile,
+};
+
+/// Value given to a turn where none of the drops could be placed
+const LOSS: f32 = -1_000_000.0;
+
+/// Game time a headless player takes on each turn, which the drop timer multiplier is based on
+const TURN_SECS: f32 = 1.0;
+
+/// How the board is turned before a turn's drops fall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Rotation {
+    /// The drops fall into the board as it is, see [`GameAction::SkipRotation`]
+    None,
+    Left,
+    Right,
+    Flip,
+}
+
+impl Rotation {
+    /// The input that plays the rotation
+    pub fn action(&self) -> GameAction {
+        match self {
+            Rotation::None => GameAction::SkipRotation,
+            Rotation::Left => GameAction::RotateLeft,
+            Rotation::Right => GameAction::RotateRight,
+            Rotation::Flip => GameAction::Flip,
+        }
+    }
+
+    fn apply(&self, board: &mut GameBoard) {
+        match self {
+            Rotation::None => board.score_mut().skip_rotation(),
+            Rotation::Left => board.rotate_left(),
+            Rotation::Right => board.rotate_right(),
+            Rotation::Flip => board.flip(),
+        }
+    }
+}
+
+/// Weights for what makes a board good to play on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heuristics {
+    /// Per point scored
+    pub points: f32,
+    /// Per line, by the square of how full it is, so nearly complete lines count the most
+    pub completeness: f32,
+    /// Per empty cell that no slot can ever reach until a line around it clears
+    pub holes: f32,
+    /// Per slot that will be blocked by its next drop
+    pub danger: f32,
+    /// Per slot that is already blocked
+    pub blocked: f32,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self {
+            points: 1.0,
+            completeness: 4.0,
+            holes: -6.0,
+            danger: -1.0,
+            blocked: -3.0,
+        }
+    }
+}
+
+impl Heuristics {
+    /// Scores a board, higher is better
+    pub fn evaluate(&self, board: &GameBoard) -> f32 {
+        let cells = board.board();
+        let slots = board.slots();
+
+        let completeness =
+            cells
+                .row_iter()
+                .map(|row| row.iter().filter(|x| x.is_filled()).count() as f32 / row.len() as f32)
+                .chain(cells.column_iter().map(|col| {
+                    col.iter().filter(|x| x.is_filled()).count() as f32 / col.len() as f32
+                }))
+                .map(|filled| filled * filled)
+                .sum::<f32>();
+
+        let blocked = (0..slots).filter(|&slot| !board.is_legal(slot)).count() as f32;
+
+        self.completeness * completeness
+            + self.holes * holes(cells) as f32
+            + self.danger * board.danger_slots().len() as f32
+            + self.blocked * blocked
+    }
+}
+
+/// Empty cells with a tile between them and every side of the board
+fn holes(cells: &DMatrix<Tile>) -> usize {
+    (0..cells.nrows())
+        .flat_map(|row| (0..cells.ncols()).map(move |col| (row, col)))
+        .filter(|&(row, col)| !cells[(row, col)].is_filled())
+        .filter(|&(row, col)| {
+            let column = cells.column(col);
+            let line = cells.row(row);
+
+            column.iter().take(row).any(Tile::is_filled)
+                && column.iter().skip(row + 1).any(Tile::is_filled)
+                && line.iter().take(col).any(Tile::is_filled)
+                && line.iter().skip(col + 1).any(Tile::is_filled)
+        })
+        .count()
+}
+
+/// Picks rotations by trying each one and looking a turn ahead
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+    heuristics: Heuristics,
+}
+
+impl Solver {
+    /// Picks the rotation that leaves the best board once a turn's drops have fallen,
+    /// or `None` if none of the options place any of them.
+    ///
+    /// Drops are slots and tiles in the board's current orientation, as given by [`GameSession::turn_drops`].
+    pub fn best_rotation(
+        &self,
+        board: &GameBoard,
+        drops: &[(usize, Tile)],
+        options: &[Rotation],
+    ) -> Option<Rotation> {
+        options
+            .iter()
+            .filter_map(|&rotation| {
+                let landed = land(board, rotation, drops)?;
+                let value = self.value(board, &landed) + self.lookahead(&landed, options);
+
+                Some((rotation, value))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(rotation, _)| rotation)
+    }
+
+    /// [`Solver::best_rotation`] for a session's pending turn
+    pub fn best_rotation_for(
+        &self,
+        session: &GameSession,
+        options: &[Rotation],
+    ) -> Option<Rotation> {
+        self.best_rotation(session.board(), &session.turn_drops(), options)
+    }
+
+    /// How good a board is after a turn, counting the points scored getting there
+    fn value(&self, before: &GameBoard, after: &GameBoard) -> f32 {
+        let points = after.score().score().saturating_sub(before.score().score());
+
+        self.heuristics.points * points as f32 + self.heuristics.evaluate(after)
+    }
+
+    /// The average value of the best response to a single drop into each slot next turn.
+    /// The next tile isn't known, so a wild tile stands in for it.
+    fn lookahead(&self, board: &GameBoard, options: &[Rotation]) -> f32 {
+        let slots = board.slots();
+
+        let total = (0..slots)
+            .map(|slot| {
+                options
+                    .iter()
+                    .filter_map(|&rotation| land(board, rotation, &[(slot, Tile::Wild)]))
+                    .map(|landed| self.value(board, &landed))
+                    .max_by(f32::total_cmp)
+                    .unwrap_or(LOSS)
+            })
+            .sum::<f32>();
+
+        total / slots as f32
+    }
+}
+
+/// The board after rotating and dropping every tile of a turn, or `None` if no tile could be placed
+fn land(board: &GameBoard, rotation: Rotation, drops: &[(usize, Tile)]) -> Option<GameBoard> {
+    let mut board = board.clone();
+    rotation.apply(&mut board);
+
+    let placed = drops
+        .iter()
+        .filter(|(slot, tile)| board.place_tile(*slot, *tile).is_ok())
+        .count();
+
+    (placed > 0).then_some(board)
+}
+
+/// Where a tile dropped into a slot after a rotation would land on the display board (column, row)
+pub fn landing(board: &GameBoard, rotation: Rotation, slot: usize) -> Option<(usize, usize)> {
+    let mut board = board.clone();
+    rotation.apply(&mut board);
+
+    board.landing(slot)
+}
+
+/// Plays a session with the solver until it's lost or `max_turns` have been played.
+/// Returns the number of turns played.
+pub fn autoplay(
+    session: &mut GameSession,
+    solver: &Solver,
+    options: &[Rotation],
+    max_turns: usize,
+) -> usize {
+    for turn in 0..max_turns {
+        let Some(rotation) = solver.best_rotation_for(session, options) else {
+            return turn;
+        };
+
+        session.tick(TURN_SECS);
+        // Rotations can't fail
+        let _ = session.step(rotation.action());
+
+        loop {
+            if session.step(GameAction::Drop).is_err() {
+                return turn;
+            }
+
+            if !session.mid_turn() {
+                break;
+            }
+        }
+    }
+
+    max_turns
+}
+
+/// How the solver fared over a set of seeds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    pub games: usize,
+    pub mean_score: f64,
+    pub best_score: u64,
+    pub mean_turns: f64,
+}
+
+/// Plays a game for every seed with the solver, to compare how rules play out without rendering anything
+pub fn benchmark(
+    config: &SessionConfig,
+    seeds: Range<u64>,
+    solver: &Solver,
+    max_turns: usize,
+) -> Benchmark {
+    let options = Rotation::iter().collect::<Vec<_>>();
+    let results = seeds
+        .map(|seed| {
+            let mut session = GameSession::new(config.clone(), seed);
+            let turns = autoplay(&mut session, solver, &options, max_turns);
+
+            (session.board().score().score(), turns)
+        })
+        .collect::<Vec<_>>();
+
+    let games = results.len().max(1) as f64;
+
+    Benchmark {
+        games: results.len(),
+        mean_score: results.iter().map(|(score, _)| *score as f64).sum::<f64>() / games,
+        best_score: results.iter().map(|(score, _)| *score).max().unwrap_or(0),
+        mean_turns: results.iter().map(|(_, turns)| *turns as f64).sum::<f64>() / games,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+    use strum::IntoEnumIterator;
+
+    use crate::logic::{
+        board::GameBoard,
+        session::{GameAction, GameSession, SessionConfig},
+        tile::Tile,
+    };
+
+    use super::{autoplay, holes, land, Rotation, Solver};
+
+    #[test]
+    fn verify_holes() {
+        let x = Tile::FILLED;
+        let o = Tile::Empty;
+
+        assert_eq!(holes(&DMatrix::from_element(3, 3, o)), 0);
+
+        // The middle cell is walled in, the corners can still be reached
+        let walled = DMatrix::from_row_slice(3, 3, &[o, x, o, x, o, x, o, x, o]);
+        assert_eq!(holes(&walled), 1);
+
+        // Open to the right
+        let open = DMatrix::from_row_slice(3, 3, &[o, x, o, x, o, o, o, x, o]);
+        assert_eq!(holes(&open), 0);
+    }
+
+    #[test]
+    fn verify_best_rotation_places() {
+        let solver = Solver::default();
+
+        for seed in 0..20 {
+            let mut session = GameSession::new(SessionConfig::new(4, 4), seed);
+
+            for _ in 0..30 {
+                let drops = session.turn_drops();
+                let playable = Rotation::iter()
+                    .any(|rotation| land(session.board(), rotation, &drops).is_some());
+
+                let best =
+                    solver.best_rotation_for(&session, &Rotation::iter().collect::<Vec<_>>());
+                assert_eq!(best.is_some(), playable, "Seed {seed}");
+
+                let Some(rotation) = best else {
+                    break;
+                };
+                assert!(land(session.board(), rotation, &drops).is_some());
+
+                session.step(rotation.action()).unwrap();
+                if session.step(GameAction::Drop).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_solver_outlasts_naive_play() {
+        let solver = Solver::default();
+        let options = Rotation::iter().collect::<Vec<_>>();
+
+        let mut solved = 0;
+        let mut naive = 0;
+
+        for seed in 0..5 {
+            let mut session = GameSession::new(SessionConfig::new(4, 4), seed);
+            solved += autoplay(&mut session, &solver, &options, 100);
+
+            // Always turns right
+            let mut session = GameSession::new(SessionConfig::new(4, 4), seed);
+            for _ in 0..100 {
+                session.step(GameAction::RotateRight).unwrap();
+                if session.step(GameAction::Drop).is_err() {
+                    break;
+                }
+                naive += 1;
+            }
+        }
+
+        assert!(solved > naive, "Solver {solved} vs naive {naive}");
+    }
+
+    #[test]
+    fn verify_land_without_rotating() {
+        let mut board = GameBoard::new(3, 3);
+        board.score_mut().update(1.0);
+
+        let landed = land(&board, Rotation::None, &[(0, Tile::FILLED)]).unwrap();
+        let rotated = land(&board, Rotation::Right, &[(0, Tile::FILLED)]).unwrap();
+
+        // A tile in slot 0 slides down to the bottom of the first column
+        assert_eq!(landed.board()[(2, 0)], Tile::FILLED);
+        // Scores without the drop timer multiplier
+        assert!(landed.score().score() < rotated.score().score());
+    }
+}
@@ -1,58 +1,113 @@
-use bevy::log::debug;
-use nalgebra::{DMatrix, RowDVector};
+use std::collections::{HashMap, HashSet};
 
-use super::{error::GameError, insertion::InsertionDirection, score::GameScore};
+use bevy::log::debug;
+use nalgebra::{DMatrix, RowDVector, Scalar};
+
+use super::{
+    error::GameError,
+    insertion::InsertionDirection,
+    score::{GameScore, ScoringRules},
+    tile::{is_single_color, ClearRules, Tile},
+};
+
+/// A tile that slid across the board when gravity closed the gaps after a clear.
+///
+/// Positions are on the display board (column, row).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileMove {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+}
 
 #[derive(Debug, Clone)]
 pub struct GameBoard {
     /// Playing Board
-    board: DMatrix<u8>,
+    board: DMatrix<Tile>,
     /// Queued Board Rotations, every increment is a 90 degree turn.
     /// Positive is clockwise, negative, is counter-clockwise.
     ///
-    /// This is cleared when preparing the next drop.
+    /// Kept within a single full turn, from 0 to 3.
     offset: i8,
     /// A copy of the playing board with offset operations reversed.
     /// This should be used when displaying the board graphically and applying the rotations
     /// via graphical transformations (like rotating game pieces)
-    display_board: DMatrix<u8>,
+    display_board: DMatrix<Tile>,
     /// Enables row/col clearing
     rows_clearing: bool,
+    /// How full rows/cols are cleared and scored
+    clear_rules: ClearRules,
     /// The current score on the board
     score: GameScore,
+    /// Tiles moved by gravity during the last placement
+    last_collapse: Vec<TileMove>,
+    /// Rows and columns cleared so far
+    lines_cleared: u32,
 }
 
 impl GameBoard {
-    /// Constructs a square game board of `n` size on each side
-    pub fn new(n: usize) -> Self {
+    /// Constructs a game board `width` columns wide and `height` rows tall
+    pub fn new(width: usize, height: usize) -> Self {
         Self {
-            board: DMatrix::zeros(n, n),
+            board: DMatrix::from_element(height, width, Tile::Empty),
             offset: 0,
-            display_board: DMatrix::zeros(n, n),
+            display_board: DMatrix::from_element(height, width, Tile::Empty),
             rows_clearing: false,
-            score: GameScore::new(),
+            clear_rules: ClearRules::default(),
+            score: GameScore::new(ScoringRules::default()),
+            last_collapse: Vec::new(),
+            lines_cleared: 0,
         }
     }
 
+    /// Starts the board with tiles already in place, sized to fit them
+    pub fn with_tiles(mut self, tiles: DMatrix<Tile>) -> Self {
+        self.offset = 0;
+        self.display_board = tiles.clone();
+        self.board = tiles;
+        self
+    }
+
     /// Enables row clearing on the game board
     pub fn with_rows_clearing(mut self) -> Self {
         self.rows_clearing = true;
         self
     }
 
-    /// The `n` dimension of the board
+    /// Sets the constants scoring is tuned with
+    pub fn with_scoring_rules(mut self, rules: ScoringRules) -> Self {
+        self.score = GameScore::new(rules);
+        self
+    }
+
+    /// Sets how full rows/cols are cleared and scored
+    pub fn with_clear_rules(mut self, clear_rules: ClearRules) -> Self {
+        self.clear_rules = clear_rules;
+        self
+    }
+
+    /// The number of columns on the board, as currently rotated
     pub fn width(&self) -> usize {
         self.board.ncols()
     }
 
+    /// The number of rows on the board, as currently rotated
+    pub fn height(&self) -> usize {
+        self.board.nrows()
+    }
+
+    /// The number of drop slots around the board, starting from the top left and going clockwise
+    pub fn slots(&self) -> usize {
+        2 * (self.board.ncols() + self.board.nrows())
+    }
+
     /// The current game board. This has all rotation logic applied through it
     /// as the game is played.
-    pub fn board(&self) -> &DMatrix<u8> {
+    pub fn board(&self) -> &DMatrix<Tile> {
         &self.board
     }
 
     /// The game board to be displayed. This has no rotation logic applied to it.
-    pub fn display_board(&self) -> &DMatrix<u8> {
+    pub fn display_board(&self) -> &DMatrix<Tile> {
         &self.display_board
     }
 
@@ -66,8 +121,154 @@ impl GameBoard {
         &mut self.score
     }
 
-    /// Returns the coordinate where the tile was placed (column, row)
+    /// Tiles moved by gravity during the last placement, empty if nothing collapsed
+    pub fn last_collapse(&self) -> &[TileMove] {
+        &self.last_collapse
+    }
+
+    /// Rows and columns cleared so far, counting every line a bomb took with it
+    pub fn lines_cleared(&self) -> u32 {
+        self.lines_cleared
+    }
+
+    /// Whether every cell on the board is empty
+    pub fn is_empty(&self) -> bool {
+        self.board.iter().all(|tile| !tile.is_filled())
+    }
+
+    /// Every empty cell on the board (row, column)
+    pub fn empty_cells(&self) -> Vec<(usize, usize)> {
+        (0..self.board.ncols())
+            .flat_map(|col| (0..self.board.nrows()).map(move |row| (row, col)))
+            .filter(|&pos| !self.board[pos].is_filled())
+            .collect()
+    }
+
+    /// Puts a tile straight into a cell (row, column), without scoring or clearing anything
+    pub fn inject_tile(&mut self, pos: (usize, usize), tile: Tile) {
+        self.board[pos] = tile;
+        self.update_display_board(0);
+    }
+
+    /// Pushes a line of tiles in from a side, shifting every tile on the board one step away from it.
+    /// Returns false without changing anything if the line on the far side isn't empty
+    /// or the tiles don't fit along the side.
+    pub fn push_line(&mut self, side: InsertionDirection, tiles: &[Tile]) -> bool {
+        let (nrows, ncols) = self.board.shape();
+
+        let (far, len) = match side {
+            InsertionDirection::FromTop => (self.board.row(nrows - 1).transpose(), ncols),
+            InsertionDirection::FromBottom => (self.board.row(0).transpose(), ncols),
+            InsertionDirection::FromLeft => (self.board.column(ncols - 1).into_owned(), nrows),
+            InsertionDirection::FromRight => (self.board.column(0).into_owned(), nrows),
+        };
+
+        if tiles.len() != len || far.iter().any(|tile| tile.is_filled()) {
+            return false;
+        }
+
+        let board = self.board.clone();
+        self.board = match side {
+            InsertionDirection::FromTop => board.remove_row(nrows - 1).insert_row(0, Tile::Empty),
+            InsertionDirection::FromBottom => {
+                board.remove_row(0).insert_row(nrows - 1, Tile::Empty)
+            }
+            InsertionDirection::FromLeft => {
+                board.remove_column(ncols - 1).insert_column(0, Tile::Empty)
+            }
+            InsertionDirection::FromRight => {
+                board.remove_column(0).insert_column(ncols - 1, Tile::Empty)
+            }
+        };
+
+        let line = tiles.iter().copied();
+        match side {
+            InsertionDirection::FromTop => {
+                self.board.set_row(0, &RowDVector::from_iterator(len, line))
+            }
+            InsertionDirection::FromBottom => self
+                .board
+                .set_row(nrows - 1, &RowDVector::from_iterator(len, line)),
+            InsertionDirection::FromLeft => {
+                self.board.set_column(0, &line.collect::<Vec<_>>().into())
+            }
+            InsertionDirection::FromRight => self
+                .board
+                .set_column(ncols - 1, &line.collect::<Vec<_>>().into()),
+        }
+
+        // Tiles that slid on the last placement are no longer where they landed
+        self.last_collapse.clear();
+        self.update_display_board(0);
+
+        true
+    }
+
+    /// The tiles a drop into a slot passes through, starting from the side it's inserted from
+    fn insertion_line(board: &DMatrix<Tile>, slot: usize) -> Result<Vec<Tile>, GameError> {
+        let insertion_direction = InsertionDirection::for_board_insertion(board, slot)?;
+        let index = insertion_direction.get_side_index(board, slot);
+
+        let line = match insertion_direction {
+            InsertionDirection::FromTop => board.column(index).iter().copied().collect(),
+            InsertionDirection::FromBottom => board.column(index).iter().rev().copied().collect(),
+            InsertionDirection::FromLeft => board.row(index).iter().copied().collect(),
+            InsertionDirection::FromRight => board.row(index).iter().rev().copied().collect(),
+        };
+
+        Ok(line)
+    }
+
+    /// Whether a tile can be dropped into a slot without running out of space
+    pub fn is_legal(&self, slot: usize) -> bool {
+        Self::is_legal_on(&self.board, slot)
+    }
+
+    fn is_legal_on(board: &DMatrix<Tile>, slot: usize) -> bool {
+        Self::insertion_line(board, slot)
+            .is_ok_and(|line| line.first().is_some_and(|tile| !tile.is_filled()))
+    }
+
+    /// The legal slots once the board is turned clockwise by a number of quarter turns
+    pub fn legal_slots(&self, quarter_turns: usize) -> Vec<usize> {
+        let board = (0..quarter_turns % 4).fold(self.board.clone(), |board, _| {
+            Self::rotate_board_right(board)
+        });
+
+        (0..self.slots())
+            .filter(|&slot| Self::is_legal_on(&board, slot))
+            .collect()
+    }
+
+    /// Whether any of the given slots is legal after any of the given clockwise quarter turns
+    pub fn has_legal_move(&self, quarter_turns: &[usize], slots: &[usize]) -> bool {
+        quarter_turns.iter().any(|&turns| {
+            self.legal_slots(turns)
+                .iter()
+                .any(|slot| slots.contains(slot))
+        })
+    }
+
+    /// Slots that are still legal, but will be blocked by the next tile dropped into them
+    /// unless it clears a line
+    pub fn danger_slots(&self) -> Vec<usize> {
+        (0..self.slots())
+            .filter(|&slot| {
+                Self::insertion_line(&self.board, slot).is_ok_and(|line| {
+                    !line[0].is_filled() && line.get(1).map_or(true, |tile| tile.is_filled())
+                })
+            })
+            .collect()
+    }
+
+    /// Places a plain tile, returning the coordinate where it was placed (column, row)
+    #[cfg(test)]
     pub fn place(&mut self, slot: usize) -> Result<(usize, usize), GameError> {
+        self.place_tile(slot, Tile::FILLED)
+    }
+
+    /// Returns the coordinate where the tile was placed (column, row)
+    pub fn place_tile(&mut self, slot: usize, tile: Tile) -> Result<(usize, usize), GameError> {
         let insertion_direction = InsertionDirection::for_board_insertion(&self.board, slot)?;
         debug!("Dropping into {slot} ({:?})", insertion_direction);
         let index = insertion_direction.get_side_index(&self.board, slot);
@@ -77,74 +278,111 @@ impl GameBoard {
                 let mut column = self.board.column_mut(index);
                 let slice = column.as_mut_slice();
 
-                (index, insertion_direction.place(slice)?)
+                (index, insertion_direction.place(slice, tile)?)
             }
             InsertionDirection::FromLeft => {
                 let row = self.board.row(index);
-                let mut data = row.iter().map(|&x| x).collect::<Vec<_>>();
+                let mut data = row.iter().copied().collect::<Vec<_>>();
 
-                let res = insertion_direction.place(&mut data)?;
+                let res = insertion_direction.place(&mut data, tile)?;
 
-                self.board.set_row(
-                    index,
-                    &RowDVector::from_row_iterator(data.len(), data.into_iter()),
-                );
+                self.board
+                    .set_row(index, &RowDVector::from_row_iterator(data.len(), data));
 
                 (res, index)
             }
             InsertionDirection::FromRight => {
                 let row = self.board.row(index);
-                let mut data = row.iter().rev().map(|&x| x).collect::<Vec<_>>();
+                let mut data = row.iter().rev().copied().collect::<Vec<_>>();
 
-                let res = insertion_direction.place(&mut data)?;
+                let len = data.len();
+
+                let res = insertion_direction.place(&mut data, tile)?;
 
                 self.board.set_row(
                     index,
-                    &RowDVector::from_row_iterator(data.len(), data.into_iter().rev()),
+                    &RowDVector::from_row_iterator(len, data.into_iter().rev()),
                 );
 
-                (res, index)
+                // Placed from the far end of the row
+                (len - 1 - res, index)
             }
             InsertionDirection::FromBottom => {
                 let column = self.board.column_mut(index);
-                let mut data = column.iter().rev().map(|&x| x).collect::<Vec<_>>();
+                let mut data = column.iter().rev().copied().collect::<Vec<_>>();
 
                 let len = data.len();
 
-                let res = insertion_direction.place(&mut data)?;
+                let res = insertion_direction.place(&mut data, tile)?;
 
                 data.reverse();
 
                 self.board.set_column(index, &data.into());
 
-                (index, len.saturating_sub(res))
+                // Placed from the far end of the column
+                (index, len - 1 - res)
             }
         };
 
         self.score.add_score(1);
 
+        self.last_collapse.clear();
         if self.rows_clearing {
             self.check_full_rows(insertion_direction, index);
         }
 
-        self.score.reset_drop_timer();
-
         self.update_display_board(0);
 
         Ok(pos)
     }
 
-    /// Returns whether a row is full.
+    /// Where a tile dropped into a slot would land on the display board (column, row), without placing it
+    pub fn landing(&self, slot: usize) -> Option<(usize, usize)> {
+        let insertion_direction =
+            InsertionDirection::for_board_insertion(&self.board, slot).ok()?;
+        let index = insertion_direction.get_side_index(&self.board, slot);
+        let line = Self::insertion_line(&self.board, slot).ok()?;
+        let landed = insertion_direction.landing_index(&line).ok()?;
+
+        // Lines are read from the side the tile comes in from
+        let (col, row) = match insertion_direction {
+            InsertionDirection::FromTop => (index, landed),
+            InsertionDirection::FromLeft => (landed, index),
+            InsertionDirection::FromRight => (line.len() - 1 - landed, index),
+            InsertionDirection::FromBottom => (index, line.len() - 1 - landed),
+        };
+
+        self.display_positions().get(&(row, col)).copied()
+    }
+
+    /// Returns whether a row is full and can be cleared under the clear rules.
+    /// Lines made up only of garbage are never cleared.
     fn check_row(&self, insertion_direction: InsertionDirection, index: usize) -> bool {
-        match insertion_direction {
+        let data = match insertion_direction {
             InsertionDirection::FromTop | InsertionDirection::FromBottom => {
-                let data = self.board.column(index);
-                data.into_iter().all(|&x| x == 1)
+                self.board.column(index).iter().copied().collect::<Vec<_>>()
             }
             InsertionDirection::FromRight | InsertionDirection::FromLeft => {
-                let data = self.board.row(index);
-                data.into_iter().all(|&x| x == 1)
+                self.board.row(index).iter().copied().collect::<Vec<_>>()
             }
+        };
+
+        data.iter().all(|x| x.is_filled())
+            && data.iter().any(|x| !x.is_garbage())
+            && (self.clear_rules.mixed_lines_clear || is_single_color(&data))
+    }
+
+    /// Points for clearing a line, one per tile it clears or cracks with the single color bonus applied
+    fn line_points(&self, line: &[Tile]) -> u32 {
+        let tiles = line
+            .iter()
+            .filter(|x| x.is_filled() && x.cleared() != **x)
+            .count() as u32;
+
+        if is_single_color(line) {
+            tiles * self.clear_rules.single_color_bonus
+        } else {
+            tiles
         }
     }
 
@@ -155,10 +393,7 @@ impl GameBoard {
 
         match insertion_direction {
             InsertionDirection::FromTop | InsertionDirection::FromBottom => {
-                let data = self.board.column(index);
-                let slice_full = data.into_iter().all(|&x| x == 1);
-
-                if slice_full {
+                if self.check_row(InsertionDirection::FromTop, index) {
                     cols.push(index);
                 }
 
@@ -169,10 +404,7 @@ impl GameBoard {
                     });
             }
             InsertionDirection::FromRight | InsertionDirection::FromLeft => {
-                let data = self.board.row(index);
-                let slice_full = data.into_iter().all(|&x| x == 1);
-
-                if slice_full {
+                if self.check_row(InsertionDirection::FromLeft, index) {
                     rows.push(index);
                 }
 
@@ -184,33 +416,169 @@ impl GameBoard {
             }
         }
 
-        let dim = self.board.ncols();
-        let total_cleared = rows.len() + cols.len();
+        if !self.clear_lines(rows, cols) || !self.clear_rules.gravity {
+            return;
+        }
+
+        // Gravity closes the gaps left by a clear, which can fill more lines and set off a chain.
+        // Tracks where every moved tile started, keyed by where it is now (row, column)
+        let mut origins = HashMap::new();
+        loop {
+            let moved = self
+                .collapse(&insertion_direction)
+                .into_iter()
+                .map(|(from, to)| (to, origins.remove(&from).unwrap_or(from)))
+                .collect::<Vec<_>>();
+            origins.extend(moved);
+
+            let rows = (0..self.board.nrows())
+                .filter(|&index| self.check_row(InsertionDirection::FromLeft, index))
+                .collect::<Vec<_>>();
+            let cols = (0..self.board.ncols())
+                .filter(|&index| self.check_row(InsertionDirection::FromTop, index))
+                .collect::<Vec<_>>();
+
+            if rows.is_empty() && cols.is_empty() {
+                break;
+            }
 
-        if total_cleared > 0 {
-            self.score.add_mult(total_cleared as u32);
-            self.score.add_score((total_cleared * dim) as u32);
+            self.score.advance_chain();
+            origins.retain(|&(row, col), _| !rows.contains(&row) && !cols.contains(&col));
+            self.clear_lines(rows, cols);
         }
+        self.score.reset_chain();
+
+        let display_positions = self.display_positions();
+        self.last_collapse = origins
+            .into_iter()
+            .filter(|(to, from)| to != from)
+            .map(|(to, from)| TileMove {
+                from: display_positions[&from],
+                to: display_positions[&to],
+            })
+            .collect();
+    }
+
+    /// Scores and clears the given lines, along with any lines crossing bombs in them.
+    /// Returns whether anything was cleared.
+    fn clear_lines(&mut self, mut rows: Vec<usize>, mut cols: Vec<usize>) -> bool {
+        // Bombs also clear the line crossing through them, which can set off more bombs
+        loop {
+            let crossing_cols = rows
+                .iter()
+                .flat_map(|&row| (0..self.board.ncols()).map(move |col| (row, col)))
+                .filter(|&pos| self.board[pos] == Tile::Bomb)
+                .map(|(_, col)| col)
+                .filter(|col| !cols.contains(col))
+                .collect::<Vec<_>>();
+            let crossing_rows = cols
+                .iter()
+                .flat_map(|&col| (0..self.board.nrows()).map(move |row| (row, col)))
+                .filter(|&pos| self.board[pos] == Tile::Bomb)
+                .map(|(row, _)| row)
+                .filter(|row| !rows.contains(row))
+                .collect::<Vec<_>>();
+
+            if crossing_cols.is_empty() && crossing_rows.is_empty() {
+                break;
+            }
 
-        for index in rows {
-            self.board.set_row(
-                index,
-                &RowDVector::from_row_iterator(
-                    self.board.nrows(),
-                    (0..self.board.nrows()).map(|_| 0),
-                ),
-            );
+            crossing_cols.into_iter().for_each(|col| {
+                if !cols.contains(&col) {
+                    cols.push(col);
+                }
+            });
+            crossing_rows.into_iter().for_each(|row| {
+                if !rows.contains(&row) {
+                    rows.push(row);
+                }
+            });
         }
 
-        for index in cols {
-            self.board
-                .set_column(index, &vec![0_u8; self.board.ncols()].into());
+        let total_cleared = rows.len() + cols.len();
+        let tiles_cleared = rows
+            .iter()
+            .map(|&row| self.board.row(row).iter().copied().collect::<Vec<_>>())
+            .chain(
+                cols.iter()
+                    .map(|&col| self.board.column(col).iter().copied().collect::<Vec<_>>()),
+            )
+            .map(|line| self.line_points(&line))
+            .sum::<u32>();
+
+        if total_cleared == 0 {
+            return false;
+        }
+
+        self.lines_cleared += total_cleared as u32;
+        self.score.add_mult(total_cleared as u32);
+        self.score.add_score(tiles_cleared);
+
+        // Where a row and column cross the tile is only cleared once, so armor only cracks
+        let cells = rows
+            .iter()
+            .flat_map(|&row| (0..self.board.ncols()).map(move |col| (row, col)))
+            .chain(
+                cols.iter()
+                    .flat_map(|&col| (0..self.board.nrows()).map(move |row| (row, col))),
+            )
+            .collect::<HashSet<_>>();
+
+        for pos in cells {
+            self.board[pos] = self.board[pos].cleared();
         }
+
+        true
     }
 
-    /// Rotates the board to the clockwise logically
-    fn rotate_board_right(mut board: DMatrix<u8>) -> DMatrix<u8> {
-        board.transpose_mut();
+    /// Slides every tile toward the edge opposite the insertion side, closing any gaps.
+    /// Returns the tiles that moved as (from, to) board positions (row, column).
+    fn collapse(
+        &mut self,
+        insertion_direction: &InsertionDirection,
+    ) -> Vec<((usize, usize), (usize, usize))> {
+        let (nrows, ncols) = self.board.shape();
+
+        // Every line the tiles slide along, ordered from the far edge back to the insertion side
+        let lines: Vec<Vec<(usize, usize)>> = match insertion_direction {
+            InsertionDirection::FromTop => (0..ncols)
+                .map(|col| (0..nrows).rev().map(|row| (row, col)).collect())
+                .collect(),
+            InsertionDirection::FromBottom => (0..ncols)
+                .map(|col| (0..nrows).map(|row| (row, col)).collect())
+                .collect(),
+            InsertionDirection::FromLeft => (0..nrows)
+                .map(|row| (0..ncols).rev().map(|col| (row, col)).collect())
+                .collect(),
+            InsertionDirection::FromRight => (0..nrows)
+                .map(|row| (0..ncols).map(|col| (row, col)).collect())
+                .collect(),
+        };
+
+        let mut moves = Vec::new();
+
+        for line in lines {
+            let tiles = line
+                .iter()
+                .filter(|&&pos| self.board[pos].is_filled())
+                .copied()
+                .collect::<Vec<_>>();
+
+            for (&from, &to) in tiles.iter().zip(line.iter()) {
+                if from != to {
+                    self.board[to] = self.board[from];
+                    self.board[from] = Tile::Empty;
+                    moves.push((from, to));
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Rotates the board to the clockwise logically. Rectangular boards swap their dimensions.
+    fn rotate_board_right<T: Scalar>(board: DMatrix<T>) -> DMatrix<T> {
+        let mut board = board.transpose();
 
         let width = board.ncols();
         let half_width = width / 2;
@@ -226,16 +594,20 @@ impl GameBoard {
         self.update_display_board(1);
     }
 
-    /// Rotates the board to the counter-clockwise logically
-    fn rotate_board_left(mut board: DMatrix<u8>) -> DMatrix<u8> {
+    /// Turns the board upside down
+    pub fn flip(&mut self) {
+        self.board = Self::rotate_board_right(Self::rotate_board_right(self.board.clone()));
+        self.update_display_board(2);
+    }
+
+    /// Rotates the board to the counter-clockwise logically. Rectangular boards swap their dimensions.
+    fn rotate_board_left<T: Scalar>(mut board: DMatrix<T>) -> DMatrix<T> {
         let width = board.ncols();
         let half_width = width / 2;
 
         (0..half_width).for_each(|i| board.swap_columns(i, width - i - 1));
 
-        board.transpose_mut();
-
-        board
+        board.transpose()
     }
 
     /// Rotates the board to the counter-clockwise
@@ -247,12 +619,15 @@ impl GameBoard {
     /// Updates the display board based on a number of changes to the game board.
     /// Changes are in units of 90 degrees. Clockwise is positive, counter-clockwise is negative.
     ///
-    /// Changes are summed into offset as a cache.
+    /// Changes are summed into offset as a cache, kept within a single full turn.
     fn update_display_board(&mut self, change: i8) {
-        self.offset += change;
+        self.offset = (self.offset + change).rem_euclid(4);
 
-        let mut new_board = self.board.clone();
+        self.display_board = self.to_display(self.board.clone());
+    }
 
+    /// Reverses the offset rotations on a matrix shaped like the board
+    fn to_display<T: Scalar>(&self, mut new_board: DMatrix<T>) -> DMatrix<T> {
         if self.offset.abs() > 0 {
             let range = if self.offset > 0 {
                 0..self.offset
@@ -269,7 +644,25 @@ impl GameBoard {
             }
         }
 
-        self.display_board = new_board;
+        new_board
+    }
+
+    /// Maps every board position (row, column) to where it is on the display board (column, row)
+    fn display_positions(&self) -> HashMap<(usize, usize), (usize, usize)> {
+        let (nrows, ncols) = self.board.shape();
+        let positions = self.to_display(DMatrix::from_fn(nrows, ncols, |row, col| (row, col)));
+
+        positions
+            .column_iter()
+            .enumerate()
+            .flat_map(|(col, column)| {
+                column
+                    .iter()
+                    .enumerate()
+                    .map(move |(row, &pos)| (pos, (col, row)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 }
 
@@ -277,13 +670,31 @@ impl GameBoard {
 mod tests {
     use nalgebra::{DMatrix, RowDVector};
 
-    use crate::logic::error::GameError;
+    use crate::logic::{
+        error::GameError,
+        score::ScoringPreset,
+        tile::{ClearRules, Tile, TileColor},
+    };
+
+    use super::{GameBoard, InsertionDirection, TileMove};
 
-    use super::GameBoard;
+    /// Maps a board to 1 for filled tiles and 0 for empty ones
+    fn filled(board: &DMatrix<Tile>) -> DMatrix<u8> {
+        board.map(|x| x.is_filled() as u8)
+    }
+
+    fn gravity_board(width: usize, height: usize) -> GameBoard {
+        GameBoard::new(width, height)
+            .with_rows_clearing()
+            .with_clear_rules(ClearRules {
+                gravity: true,
+                ..ClearRules::default()
+            })
+    }
 
     #[test]
     pub fn verify_place_top() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         // top place
         game_board.place(0).unwrap();
@@ -291,7 +702,7 @@ mod tests {
         println!("{}", game_board.board);
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -302,13 +713,13 @@ mod tests {
 
     #[test]
     pub fn verify_place_right() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         // top place
         game_board.place(3).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![1, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -319,13 +730,13 @@ mod tests {
 
     #[test]
     pub fn verify_place_bottom() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         // top place
         game_board.place(8).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![1, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -336,13 +747,13 @@ mod tests {
 
     #[test]
     pub fn verify_place_left() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         // top place
         game_board.place(11).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 1]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -353,7 +764,7 @@ mod tests {
 
     #[test]
     pub fn verify_place_stacking_1() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         // top place
         game_board.place(0).unwrap();
@@ -361,7 +772,7 @@ mod tests {
         game_board.place(4).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0]),
                 RowDVector::from_vec(vec![1, 1, 0]),
@@ -372,14 +783,14 @@ mod tests {
 
     #[test]
     pub fn verify_rotate_right() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         game_board.place(1).unwrap();
         game_board.place(2).unwrap();
         game_board.place(3).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![1, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -390,7 +801,7 @@ mod tests {
         game_board.rotate_right();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 1]),
                 RowDVector::from_vec(vec![1, 0, 0]),
@@ -401,7 +812,7 @@ mod tests {
 
     #[test]
     pub fn verify_rotate_right_large() {
-        let mut game_board = GameBoard::new(5);
+        let mut game_board = GameBoard::new(5, 5);
 
         game_board.place(1).unwrap();
         game_board.place(2).unwrap();
@@ -409,7 +820,7 @@ mod tests {
         game_board.place(3).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0, 0, 0]),
@@ -422,7 +833,7 @@ mod tests {
         game_board.rotate_right();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0, 0, 0]),
                 RowDVector::from_vec(vec![1, 0, 0, 0, 0]),
@@ -435,14 +846,14 @@ mod tests {
 
     #[test]
     pub fn verify_rotate_left() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         game_board.place(1).unwrap();
         game_board.place(2).unwrap();
         game_board.place(3).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![1, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -453,7 +864,7 @@ mod tests {
         game_board.rotate_left();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 1]),
                 RowDVector::from_vec(vec![0, 0, 1]),
@@ -462,16 +873,43 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn verify_flip() {
+        let mut game_board = GameBoard::new(4, 3);
+
+        game_board.place(1).unwrap();
+        game_board.place(2).unwrap();
+
+        let upright = game_board.clone();
+        let mut turned = game_board.clone();
+        turned.rotate_right();
+        turned.rotate_right();
+
+        game_board.flip();
+
+        assert_eq!(game_board.board(), turned.board());
+        assert_eq!(game_board.board().shape(), (3, 4));
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![0, 1, 1, 0]),
+                RowDVector::from_vec(vec![0, 0, 0, 0]),
+                RowDVector::from_vec(vec![0, 0, 0, 0]),
+            ])
+        );
+        assert_eq!(game_board.display_board(), upright.board());
+    }
+
     #[test]
     pub fn verify_display_board() {
-        let mut board = GameBoard::new(3);
+        let mut board = GameBoard::new(3, 3);
         board.place(0).unwrap();
 
         assert_eq!(board.board(), board.display_board());
 
         let upright_position = board.clone();
 
-        for i in (-10..20).into_iter() {
+        for i in -10..20 {
             if i < 0 {
                 board.rotate_left();
             } else {
@@ -483,9 +921,42 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn verify_landing() {
+        let mut board = GameBoard::new(4, 3);
+        board.place(1).unwrap();
+
+        for turn in 0..200 {
+            if turn % 3 == 0 {
+                board.rotate_left();
+            } else {
+                board.rotate_right();
+            }
+
+            for slot in 0..board.slots() {
+                let Some((col, row)) = board.landing(slot) else {
+                    assert!(!board.is_legal(slot));
+                    continue;
+                };
+
+                // The only display tile the drop fills is the one it's previewed at
+                let mut placed = board.clone();
+                placed.place(slot).unwrap();
+                let changed = (0..3)
+                    .flat_map(|row| (0..4).map(move |col| (col, row)))
+                    .filter(|&(col, row)| {
+                        placed.display_board()[(row, col)] != board.display_board()[(row, col)]
+                    })
+                    .collect::<Vec<_>>();
+
+                assert_eq!(changed, vec![(col, row)], "Slot {slot} on turn {turn}");
+            }
+        }
+    }
+
     #[test]
     pub fn verify_corner_case() {
-        let mut game_board = GameBoard::new(4);
+        let mut game_board = GameBoard::new(4, 4);
 
         [4, 4, 4, 3, 3, 3, 4].into_iter().for_each(|place| {
             println!("Placing {place}");
@@ -496,7 +967,7 @@ mod tests {
 
     #[test]
     pub fn verify_corner_case_2() {
-        let mut game_board = GameBoard::new(4);
+        let mut game_board = GameBoard::new(4, 4);
 
         [0, 0, 0, 15, 15, 15, 15].into_iter().for_each(|place| {
             println!("Placing {place}");
@@ -507,7 +978,7 @@ mod tests {
 
     #[test]
     pub fn verify_board_clearing() {
-        let mut game_board = GameBoard::new(3).with_rows_clearing();
+        let mut game_board = GameBoard::new(3, 3).with_rows_clearing();
 
         game_board.place(0).unwrap();
         game_board.place(0).unwrap();
@@ -516,7 +987,7 @@ mod tests {
         game_board.place(11).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 1, 1]),
                 RowDVector::from_vec(vec![1, 0, 0]),
@@ -527,7 +998,7 @@ mod tests {
         game_board.place(0).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 0]),
@@ -538,13 +1009,13 @@ mod tests {
 
     #[test]
     pub fn verify_stacking() {
-        let mut game_board = GameBoard::new(3);
+        let mut game_board = GameBoard::new(3, 3);
 
         game_board.place(10).unwrap();
         game_board.place(6).unwrap();
 
         assert_eq!(
-            game_board.board(),
+            &filled(game_board.board()),
             &DMatrix::from_rows(&[
                 RowDVector::from_vec(vec![0, 0, 0]),
                 RowDVector::from_vec(vec![0, 0, 1]),
@@ -557,7 +1028,7 @@ mod tests {
 
     #[test]
     pub fn verify_scoring() {
-        let mut game_board = GameBoard::new(3).with_rows_clearing();
+        let mut game_board = GameBoard::new(3, 3).with_rows_clearing();
 
         game_board.place(0).unwrap();
         // With maximum drop mult (5) * 1 point (no other mults)
@@ -579,9 +1050,28 @@ mod tests {
         assert_eq!(game_board.score.score(), 13);
     }
 
+    #[test]
+    pub fn verify_scoring_rules() {
+        let mut game_board = GameBoard::new(3, 3)
+            .with_rows_clearing()
+            .with_scoring_rules(ScoringPreset::Hardcore.rules());
+
+        game_board.place(0).unwrap();
+        // With maximum drop mult (6 + 1) * 1 point
+        assert_eq!(game_board.score.score(), 7);
+
+        game_board.score.update(1000.0);
+        game_board.place(0).unwrap();
+        game_board.score.update(1000.0);
+        game_board.place(0).unwrap();
+
+        // Same as verify_scoring, the single clear adds 1 ^ 2.5 to the mult
+        assert_eq!(game_board.score.score(), 15);
+    }
+
     #[test]
     pub fn verify_scoring_2() {
-        let mut game_board = GameBoard::new(3).with_rows_clearing();
+        let mut game_board = GameBoard::new(3, 3).with_rows_clearing();
 
         // Pass the time
         game_board.score.update(1000.0);
@@ -613,4 +1103,324 @@ mod tests {
 
         assert_eq!(game_board.score.score(), 35);
     }
+
+    #[test]
+    pub fn verify_rectangular_place() {
+        let mut game_board = GameBoard::new(4, 2);
+        assert_eq!(game_board.slots(), 12);
+
+        // top, right, bottom and left
+        [1, 10, 4, 7].into_iter().for_each(|slot| {
+            game_board.place(slot).unwrap();
+        });
+
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![1, 0, 1, 0]),
+                RowDVector::from_vec(vec![1, 1, 0, 0]),
+            ])
+        );
+    }
+
+    #[test]
+    pub fn verify_rectangular_rotation() {
+        let mut game_board = GameBoard::new(3, 2);
+        game_board.place(0).unwrap();
+
+        game_board.rotate_right();
+
+        assert_eq!((game_board.width(), game_board.height()), (2, 3));
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![1, 0]),
+                RowDVector::from_vec(vec![0, 0]),
+                RowDVector::from_vec(vec![0, 0]),
+            ])
+        );
+
+        let mut upright_position = GameBoard::new(3, 2);
+        upright_position.place(0).unwrap();
+
+        for i in -5..7 {
+            if i < 0 {
+                game_board.rotate_left();
+            } else {
+                game_board.rotate_right();
+            }
+
+            assert_eq!(upright_position.board(), game_board.display_board());
+        }
+    }
+
+    #[test]
+    pub fn verify_rectangular_clearing() {
+        let mut game_board = GameBoard::new(3, 2).with_rows_clearing();
+
+        // Fill the bottom row from the top
+        [0, 1, 2].into_iter().for_each(|slot| {
+            game_board.place(slot).unwrap();
+        });
+
+        assert_eq!(filled(game_board.board()), DMatrix::zeros(2, 3));
+
+        // Fill the first column from the right and left
+        [1, 3, 8].into_iter().for_each(|slot| {
+            game_board.place(slot).unwrap();
+        });
+
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![0, 0, 0]),
+                RowDVector::from_vec(vec![0, 1, 0]),
+            ])
+        );
+    }
+
+    #[test]
+    pub fn verify_single_color_bonus() {
+        let mut game_board =
+            GameBoard::new(3, 3)
+                .with_rows_clearing()
+                .with_clear_rules(ClearRules {
+                    single_color_bonus: 2,
+                    ..ClearRules::default()
+                });
+
+        game_board.place(0).unwrap();
+        game_board.score.update(1000.0);
+        game_board.place(0).unwrap();
+        game_board.score.update(1000.0);
+        game_board.place(0).unwrap();
+
+        // Same as verify_scoring, but the cleared column of 3 is worth double
+        // so 1 + (6 * mult of 2) = delta of 13
+        assert_eq!(game_board.score.score(), 19);
+    }
+
+    #[test]
+    pub fn verify_mixed_lines() {
+        let mut game_board =
+            GameBoard::new(3, 3)
+                .with_rows_clearing()
+                .with_clear_rules(ClearRules {
+                    mixed_lines_clear: false,
+                    ..ClearRules::default()
+                });
+
+        let blue = Tile::Color(TileColor::Blue);
+        let green = Tile::Color(TileColor::Green);
+
+        [Tile::FILLED, blue, Tile::FILLED]
+            .into_iter()
+            .for_each(|tile| {
+                game_board.place_tile(0, tile).unwrap();
+            });
+
+        // Mixed colors stay on the board
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![1, 0, 0]),
+                RowDVector::from_vec(vec![1, 0, 0]),
+                RowDVector::from_vec(vec![1, 0, 0]),
+            ])
+        );
+
+        [green, Tile::Wild, green].into_iter().for_each(|tile| {
+            game_board.place_tile(2, tile).unwrap();
+        });
+
+        // Wild tiles match the rest of the column
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![1, 0, 0]),
+                RowDVector::from_vec(vec![1, 0, 0]),
+                RowDVector::from_vec(vec![1, 0, 0]),
+            ])
+        );
+    }
+
+    #[test]
+    pub fn verify_bomb_clearing() {
+        let mut game_board = GameBoard::new(3, 3).with_rows_clearing();
+
+        game_board.place(1).unwrap();
+        game_board.place_tile(0, Tile::Bomb).unwrap();
+        game_board.place(0).unwrap();
+        game_board.place(0).unwrap();
+
+        // The bomb on the bottom row clears it along with the full column
+        assert_eq!(filled(game_board.board()), DMatrix::zeros(3, 3));
+    }
+
+    #[test]
+    pub fn verify_gravity_collapse() {
+        let mut game_board = gravity_board(3, 3);
+
+        game_board.place(0).unwrap();
+        game_board.place(1).unwrap();
+        game_board.place(0).unwrap();
+        assert!(game_board.last_collapse().is_empty());
+
+        // Clears the bottom row, and the tile left above it falls into place
+        game_board.place(2).unwrap();
+
+        assert_eq!(
+            &filled(game_board.board()),
+            &DMatrix::from_rows(&[
+                RowDVector::from_vec(vec![0, 0, 0]),
+                RowDVector::from_vec(vec![0, 0, 0]),
+                RowDVector::from_vec(vec![1, 0, 0]),
+            ])
+        );
+        assert_eq!(
+            game_board.last_collapse(),
+            &[TileMove {
+                from: (0, 1),
+                to: (0, 2)
+            }]
+        );
+    }
+
+    #[test]
+    pub fn verify_gravity_chain() {
+        let mut game_board = gravity_board(3, 3);
+        game_board.board = DMatrix::from_rows(&[
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::FILLED, Tile::Empty]),
+            RowDVector::from_vec(vec![Tile::Empty, Tile::Empty, Tile::FILLED]),
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::FILLED, Tile::Empty]),
+        ]);
+
+        // Completes the top row, then the tile under it falls to complete the bottom row
+        game_board.place(2).unwrap();
+
+        assert_eq!(filled(game_board.board()), DMatrix::zeros(3, 3));
+        // Drop is 1 * mult of 5 = 5
+        // First clear is 3 * mult of 6 = 18
+        // Chain clear is 3 * mult of 7 * chain of 2 = 42
+        assert_eq!(game_board.score.score(), 65);
+        // The chain ends with the drop
+        assert_eq!(game_board.score.current_mult(), 7.0);
+    }
+
+    #[test]
+    pub fn verify_gravity_display() {
+        let mut game_board = gravity_board(4, 3);
+        game_board.rotate_right();
+
+        [0, 0, 1, 2].into_iter().for_each(|slot| {
+            game_board.place(slot).unwrap();
+        });
+
+        assert!(!game_board.last_collapse().is_empty());
+
+        // Moves follow the display board, which is never rotated
+        let display = game_board.display_board();
+        game_board.last_collapse().iter().for_each(|tile_move| {
+            let (col, row) = tile_move.to;
+            assert!(display[(row, col)].is_filled(), "{tile_move:?}");
+        });
+    }
+
+    #[test]
+    pub fn verify_legal_slots() {
+        let mut game_board = GameBoard::new(3, 2);
+        game_board.board = DMatrix::from_rows(&[
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::Empty, Tile::Empty]),
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::Empty, Tile::Empty]),
+        ]);
+
+        // The full left column blocks its top and bottom slots and both left slots
+        assert_eq!(game_board.legal_slots(0), vec![1, 2, 3, 4, 5, 6]);
+        assert!(!game_board.is_legal(0));
+        assert!(game_board.is_legal(3));
+
+        // Turned right, the column becomes the top row
+        assert_eq!(game_board.legal_slots(1), vec![3, 4, 5, 6, 7, 8]);
+        assert_eq!(game_board.legal_slots(4), game_board.legal_slots(0));
+
+        assert!(game_board.has_legal_move(&[0, 1], &[0, 3]));
+        assert!(!game_board.has_legal_move(&[0], &[0, 7, 8, 9]));
+        assert!(game_board.has_legal_move(&[0, 1], &[7]));
+    }
+
+    #[test]
+    pub fn verify_danger_slots() {
+        let mut game_board = GameBoard::new(3, 3);
+        game_board.board = DMatrix::from_rows(&[
+            RowDVector::from_vec(vec![Tile::Empty, Tile::Empty, Tile::Empty]),
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::FILLED, Tile::Empty]),
+            RowDVector::from_vec(vec![Tile::FILLED, Tile::Empty, Tile::Empty]),
+        ]);
+
+        // Lines with a single open cell before a tile, but not lines that are already blocked
+        assert_eq!(game_board.danger_slots(), vec![0, 1, 4, 7]);
+
+        game_board.place(0).unwrap();
+        assert!(!game_board.is_legal(0));
+        assert!(!game_board.danger_slots().contains(&0));
+    }
+
+    /// Reads a board written one row per string in tile characters
+    fn tiles(rows: &[&str]) -> DMatrix<Tile> {
+        DMatrix::from_row_iterator(
+            rows.len(),
+            rows[0].len(),
+            rows.iter()
+                .flat_map(|row| row.chars().map(|c| Tile::from_char(c).unwrap())),
+        )
+    }
+
+    #[test]
+    pub fn verify_garbage_clearing() {
+        let mut game_board = GameBoard::new(3, 3)
+            .with_rows_clearing()
+            .with_tiles(tiles(&["...", "...", "@#."]));
+
+        // Armor cracks on the first clear and stone stays put
+        game_board.place(2).unwrap();
+        assert_eq!(game_board.board(), &tiles(&["...", "...", "%#."]));
+        assert_eq!(game_board.lines_cleared(), 1);
+
+        game_board.place(2).unwrap();
+        assert_eq!(game_board.board(), &tiles(&["...", "...", ".#."]));
+
+        // A full line of nothing but garbage stays on the board
+        let mut game_board = GameBoard::new(3, 3)
+            .with_rows_clearing()
+            .with_tiles(tiles(&["...", "...", "%##"]));
+        game_board.place(0).unwrap();
+        assert_eq!(game_board.board(), &tiles(&["...", "R..", "%##"]));
+        assert_eq!(game_board.lines_cleared(), 0);
+    }
+
+    #[test]
+    pub fn verify_push_line() {
+        let mut game_board = GameBoard::new(3, 3).with_tiles(tiles(&["R..", "...", ".G."]));
+        let line = [Tile::Cracked, Tile::Cracked, Tile::Empty];
+
+        assert!(game_board.push_line(InsertionDirection::FromLeft, &line));
+        assert_eq!(game_board.board(), &tiles(&["%R.", "%..", "..G"]));
+        assert_eq!(game_board.display_board(), game_board.board());
+
+        // Every side now has a tile on its far side, or the wrong length of line
+        assert!(!game_board.push_line(InsertionDirection::FromLeft, &line));
+        assert!(!game_board.push_line(InsertionDirection::FromTop, &line));
+        assert!(!game_board.push_line(InsertionDirection::FromRight, &line));
+        assert!(!game_board.push_line(InsertionDirection::FromBottom, &line));
+        assert_eq!(game_board.board(), &tiles(&["%R.", "%..", "..G"]));
+
+        let mut game_board = GameBoard::new(4, 3);
+        assert!(!game_board.push_line(InsertionDirection::FromBottom, &line));
+        assert!(game_board.push_line(InsertionDirection::FromBottom, &[Tile::Stone; 4]));
+        assert!(game_board.push_line(InsertionDirection::FromBottom, &[Tile::Armored; 4]));
+        assert_eq!(game_board.board(), &tiles(&["....", "####", "@@@@"]));
+
+        // Pushing from the top would shove the bottom line off the board
+        assert!(!game_board.push_line(InsertionDirection::FromTop, &[Tile::Armored; 4]));
+    }
 }
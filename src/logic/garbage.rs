@@ -0,0 +1,190 @@
+use rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::{board::GameBoard, insertion::InsertionDirection, tile::Tile};
+
+/// How often the game pushes garbage onto the board, and what kind
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GarbageRules {
+    /// Turns between injections at the first rank
+    pub base_interval: u32,
+    /// Turns taken off the interval for every rank past the first
+    pub turns_per_rank: u32,
+    /// The fewest turns between injections, however high the rank
+    pub min_interval: u32,
+    /// Chance from 0 to 1 of pushing in a garbage line rather than dropping a single blocker
+    pub line_chance: f64,
+    /// Chance from 0 to 1 of a single blocker being armored rather than stone
+    pub armored_chance: f64,
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        Self {
+            base_interval: 10,
+            turns_per_rank: 1,
+            min_interval: 3,
+            line_chance: 0.3,
+            armored_chance: 0.7,
+        }
+    }
+}
+
+impl GarbageRules {
+    /// Turns between injections at a rank, shrinking as the rank climbs
+    pub fn interval(&self, rank: u32) -> u32 {
+        let cut = rank.saturating_sub(1).saturating_mul(self.turns_per_rank);
+
+        self.base_interval
+            .saturating_sub(cut)
+            .max(self.min_interval)
+            .max(1)
+    }
+
+    /// Pushes a garbage line in from a random side, or drops a single blocker into a random empty cell.
+    ///
+    /// Returns whether anything was injected, which is only not the case on a full board.
+    pub fn inject(&self, board: &mut GameBoard, rng: &mut StdRng) -> bool {
+        if rng.gen_bool(self.line_chance.clamp(0.0, 1.0)) {
+            self.push_line(board, rng)
+        } else {
+            self.drop_blocker(board, rng)
+        }
+    }
+
+    /// Pushes a line of cracked tiles in from a random side, with a single gap so it can be cleared by filling it.
+    /// Lines can only be pushed while the far side is clear, so a blocker is dropped instead when it isn't.
+    ///
+    /// Returns whether anything was injected, which is only not the case on a full board.
+    pub fn push_line(&self, board: &mut GameBoard, rng: &mut StdRng) -> bool {
+        let (side, len) = match rng.gen_range(0..4) {
+            0 => (InsertionDirection::FromTop, board.width()),
+            1 => (InsertionDirection::FromRight, board.height()),
+            2 => (InsertionDirection::FromBottom, board.width()),
+            _ => (InsertionDirection::FromLeft, board.height()),
+        };
+
+        let gap = rng.gen_range(0..len);
+        let line = (0..len)
+            .map(|i| if i == gap { Tile::Empty } else { Tile::Cracked })
+            .collect::<Vec<_>>();
+
+        board.push_line(side, &line) || self.drop_blocker(board, rng)
+    }
+
+    /// Drops a stone or armored blocker into a random empty cell, returning false if there isn't one
+    fn drop_blocker(&self, board: &mut GameBoard, rng: &mut StdRng) -> bool {
+        let cells = board.empty_cells();
+        if cells.is_empty() {
+            return false;
+        }
+
+        let cell = cells[rng.gen_range(0..cells.len())];
+        let tile = if rng.gen_bool(self.armored_chance.clamp(0.0, 1.0)) {
+            Tile::Armored
+        } else {
+            Tile::Stone
+        };
+        board.inject_tile(cell, tile);
+
+        true
+    }
+}
+
+/// Garbage lines sent to the opponent in versus for clearing lines in a single drop.
+/// A lone line sends nothing, every line cleared with it sends one.
+pub fn lines_sent(cleared: u32) -> u32 {
+    cleared.saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::logic::{board::GameBoard, tile::Tile};
+
+    use super::{lines_sent, GarbageRules};
+
+    fn garbage(board: &GameBoard) -> usize {
+        board.board().iter().filter(|x| x.is_garbage()).count()
+    }
+
+    #[test]
+    fn verify_interval() {
+        let rules = GarbageRules::default();
+
+        assert_eq!(rules.interval(1), 10);
+        assert_eq!(rules.interval(4), 7);
+        assert_eq!(rules.interval(50), 3);
+
+        // Intervals never shrink as the rank climbs
+        assert!((1..30).all(|rank| rules.interval(rank + 1) <= rules.interval(rank)));
+
+        let flat = GarbageRules {
+            turns_per_rank: 0,
+            ..Default::default()
+        };
+        assert_eq!(flat.interval(20), 10);
+    }
+
+    #[test]
+    fn verify_lines_sent() {
+        assert_eq!(lines_sent(0), 0);
+        assert_eq!(lines_sent(1), 0);
+        assert_eq!(lines_sent(2), 1);
+        assert_eq!(lines_sent(4), 3);
+    }
+
+    #[test]
+    fn verify_blockers() {
+        let rules = GarbageRules {
+            line_chance: 0.0,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut board = GameBoard::new(3, 3);
+
+        for injected in 1..=9 {
+            assert!(rules.inject(&mut board, &mut rng));
+            assert_eq!(garbage(&board), injected);
+            assert!(board
+                .board()
+                .iter()
+                .filter(|x| x.is_filled())
+                .all(|x| matches!(x, Tile::Stone | Tile::Armored)));
+        }
+
+        // Nowhere left to put one
+        assert!(!rules.inject(&mut board, &mut rng));
+    }
+
+    #[test]
+    fn verify_lines() {
+        let rules = GarbageRules {
+            line_chance: 1.0,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut board = GameBoard::new(4, 4);
+
+        assert!(rules.inject(&mut board, &mut rng));
+
+        // A line of cracked tiles along one side, with a single gap
+        assert_eq!(garbage(&board), 3);
+        assert!(board
+            .board()
+            .iter()
+            .all(|x| matches!(x, Tile::Empty | Tile::Cracked)));
+
+        // Lines keep coming until one can't be pushed, then it falls back to blockers
+        while !board
+            .board()
+            .iter()
+            .any(|x| matches!(x, Tile::Stone | Tile::Armored))
+        {
+            let before = garbage(&board);
+            assert!(rules.inject(&mut board, &mut rng));
+            assert!(garbage(&board) > before);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::{fmt::Debug, ops::Range};
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    seq::SliceRandom,
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// Sides of the board, clockwise from the top
+const SIDES: usize = 4;
+
+/// How much a side's weight is cut by each time it's rolled in a row
+const STREAK_FALLOFF: f64 = 0.5;
+
+/// Where a drop was rolled to fall from, before it's pinned to a slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropSpot {
+    /// A slot numbered the same way as [`GameBoard::slots`](super::board::GameBoard::slots)
+    Slot(usize),
+    /// A side of the board, clockwise from the top, and how far along it from 0 up to 1
+    Side { side: usize, along: f64 },
+}
+
+impl DropSpot {
+    /// The slot this spot falls from on a board `width` columns wide and `height` rows tall.
+    ///
+    /// Sides are resolved against the dimensions given, so a spot rolled before the board turned
+    /// still lands on the side it was rolled for.
+    pub fn slot(self, width: usize, height: usize) -> usize {
+        match self {
+            DropSpot::Slot(slot) => slot,
+            DropSpot::Side { side, along } => {
+                let slots = side_slots(width, height, side);
+                let offset = (along * slots.len() as f64) as usize;
+                slots.start + offset.min(slots.len().saturating_sub(1))
+            }
+        }
+    }
+}
+
+/// Picks the spot each drop falls from.
+///
+/// Generators draw from the session's seeded RNG, so a session rolls the same spots for the same seed.
+pub trait DropGenerator: Debug + Send + Sync {
+    /// Rolls the spot for the next drop
+    fn next_spot(&mut self, rng: &mut StdRng) -> DropSpot;
+
+    fn boxed_clone(&self) -> Box<dyn DropGenerator>;
+}
+
+impl Clone for Box<dyn DropGenerator> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+/// Which [`DropGenerator`] a session rolls its slots with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum GeneratorKind {
+    /// Every slot is equally likely on every drop
+    #[default]
+    Uniform,
+    /// Deals out the sides from a shuffled bag, so each side comes up once every four drops
+    Bag,
+    /// Makes a side less likely the more times in a row it's been rolled
+    AntiStreak,
+}
+
+impl GeneratorKind {
+    /// Creates the generator for a board `width` columns wide and `height` rows tall
+    pub fn build(self, width: usize, height: usize) -> Box<dyn DropGenerator> {
+        match self {
+            GeneratorKind::Uniform => Box::new(UniformGenerator::new(width, height)),
+            GeneratorKind::Bag => Box::<BagGenerator>::default(),
+            GeneratorKind::AntiStreak => Box::<AntiStreakGenerator>::default(),
+        }
+    }
+}
+
+/// The slots along one side of the board, in the same clockwise order as [`GameBoard::slots`](super::board::GameBoard::slots).
+/// Slots are relative to the board as it's currently turned, so pass the current dimensions.
+fn side_slots(width: usize, height: usize, side: usize) -> Range<usize> {
+    match side {
+        0 => 0..width,
+        1 => width..width + height,
+        2 => width + height..2 * width + height,
+        _ => 2 * width + height..2 * (width + height),
+    }
+}
+
+/// Rolls any spot along a side
+fn spot_on_side(rng: &mut StdRng, side: usize) -> DropSpot {
+    DropSpot::Side {
+        side,
+        along: rng.gen(),
+    }
+}
+
+/// Rolls every slot with the same chance, which can come up on the same side many times in a row
+#[derive(Debug, Clone)]
+pub struct UniformGenerator {
+    slots: usize,
+}
+
+impl UniformGenerator {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            slots: 2 * (width + height),
+        }
+    }
+}
+
+impl DropGenerator for UniformGenerator {
+    fn next_spot(&mut self, rng: &mut StdRng) -> DropSpot {
+        DropSpot::Slot(rng.gen_range(0..self.slots))
+    }
+
+    fn boxed_clone(&self) -> Box<dyn DropGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Deals each side once from a shuffled bag before refilling it, then rolls a spot along the dealt side
+#[derive(Debug, Clone, Default)]
+pub struct BagGenerator {
+    /// Sides left to deal before the bag is refilled
+    bag: Vec<usize>,
+}
+
+impl DropGenerator for BagGenerator {
+    fn next_spot(&mut self, rng: &mut StdRng) -> DropSpot {
+        if self.bag.is_empty() {
+            self.bag.extend(0..SIDES);
+            self.bag.shuffle(rng);
+        }
+
+        let side = self.bag.pop().unwrap();
+        spot_on_side(rng, side)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn DropGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Weighs the sides evenly, except the last rolled side which loses weight with every repeat
+#[derive(Debug, Clone, Default)]
+pub struct AntiStreakGenerator {
+    /// The last side rolled
+    last: Option<usize>,
+    /// Times in a row the last side has been rolled
+    streak: i32,
+}
+
+impl DropGenerator for AntiStreakGenerator {
+    fn next_spot(&mut self, rng: &mut StdRng) -> DropSpot {
+        let weights = (0..SIDES).map(|side| {
+            if Some(side) == self.last {
+                STREAK_FALLOFF.powi(self.streak)
+            } else {
+                1.0
+            }
+        });
+
+        let side = WeightedIndex::new(weights).unwrap().sample(rng);
+
+        if Some(side) == self.last {
+            self.streak += 1;
+        } else {
+            self.last = Some(side);
+            self.streak = 1;
+        }
+
+        spot_on_side(rng, side)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn DropGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use strum::IntoEnumIterator;
+
+    use super::{side_slots, GeneratorKind, SIDES};
+
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 3;
+    const ROLLS: usize = 40_000;
+
+    fn side_of(slot: usize) -> usize {
+        side_on(WIDTH, HEIGHT, slot)
+    }
+
+    fn side_on(width: usize, height: usize, slot: usize) -> usize {
+        (0..SIDES)
+            .find(|&side| side_slots(width, height, side).contains(&slot))
+            .unwrap()
+    }
+
+    fn roll(kind: GeneratorKind, seed: u64) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut generator = kind.build(WIDTH, HEIGHT);
+
+        (0..ROLLS)
+            .map(|_| generator.next_spot(&mut rng).slot(WIDTH, HEIGHT))
+            .collect()
+    }
+
+    /// Asserts each count is within 10% of its expected share of the rolls
+    fn assert_distribution(counts: &[usize], expected: &[f64]) {
+        let total = counts.iter().sum::<usize>() as f64;
+
+        for (count, share) in counts.iter().zip(expected) {
+            let expected = share * total;
+            assert!(
+                (*count as f64 - expected).abs() < expected * 0.1,
+                "{counts:?} is too far from {expected} of {total}"
+            );
+        }
+    }
+
+    fn slot_counts(slots: &[usize]) -> Vec<usize> {
+        slot_counts_on(WIDTH, HEIGHT, slots)
+    }
+
+    fn slot_counts_on(width: usize, height: usize, slots: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; 2 * (width + height)];
+        for &slot in slots {
+            counts[slot] += 1;
+        }
+        counts
+    }
+
+    fn side_counts(slots: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; SIDES];
+        for &slot in slots {
+            counts[side_of(slot)] += 1;
+        }
+        counts
+    }
+
+    /// Share of rolls on the same side as the roll before
+    fn repeat_rate(slots: &[usize]) -> f64 {
+        let repeats = slots
+            .windows(2)
+            .filter(|pair| side_of(pair[0]) == side_of(pair[1]))
+            .count();
+
+        repeats as f64 / (slots.len() - 1) as f64
+    }
+
+    /// Each slot's share when every slot is rolled evenly
+    fn slot_shares() -> Vec<f64> {
+        let slots = 2 * (WIDTH + HEIGHT);
+        vec![1.0 / slots as f64; slots]
+    }
+
+    /// Each slot's share when sides are rolled evenly and then a slot along it
+    fn side_slot_shares(width: usize, height: usize) -> Vec<f64> {
+        (0..SIDES)
+            .flat_map(|side| {
+                let len = side_slots(width, height, side).len();
+                vec![1.0 / (SIDES * len) as f64; len]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn verify_side_slots() {
+        let slots = (0..SIDES)
+            .flat_map(|side| side_slots(WIDTH, HEIGHT, side))
+            .collect::<Vec<_>>();
+
+        assert_eq!(slots, (0..2 * (WIDTH + HEIGHT)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn verify_uniform() {
+        let slots = roll(GeneratorKind::Uniform, 0);
+
+        assert_distribution(&slot_counts(&slots), &slot_shares());
+
+        // Same rolls sessions made before generators could be picked, so older replays still play back
+        let mut rng = StdRng::seed_from_u64(0);
+        let raw = (0..ROLLS)
+            .map(|_| rng.gen_range(0..2 * (WIDTH + HEIGHT)))
+            .collect::<Vec<_>>();
+        assert_eq!(slots, raw);
+    }
+
+    #[test]
+    fn verify_bag() {
+        let slots = roll(GeneratorKind::Bag, 0);
+
+        assert_distribution(&slot_counts(&slots), &side_slot_shares(WIDTH, HEIGHT));
+
+        // Every bag deals each side exactly once
+        for bag in slots.chunks(SIDES) {
+            assert_eq!(side_counts(bag), vec![1; SIDES]);
+        }
+
+        // Bags are shuffled, so sides still repeat across the end of one and the start of the next
+        let rate = repeat_rate(&slots);
+        assert!(rate > 0.0 && rate < 0.25, "repeat rate {rate}");
+    }
+
+    #[test]
+    fn verify_anti_streak() {
+        let slots = roll(GeneratorKind::AntiStreak, 0);
+
+        assert_distribution(&slot_counts(&slots), &side_slot_shares(WIDTH, HEIGHT));
+
+        // A side rolled once is half as likely as the others, 0.5 / 3.5 to come up again
+        let rate = repeat_rate(&slots);
+        assert!(rate > 0.1 && rate < 0.16, "repeat rate {rate}");
+
+        // Well below the quarter of rolls that repeat with uniform sides
+        let uniform = repeat_rate(&roll(GeneratorKind::Uniform, 0));
+        assert!(rate < uniform - 0.05, "{rate} against {uniform}");
+
+        let longest = slots
+            .chunk_by(|a, b| side_of(*a) == side_of(*b))
+            .map(|streak| streak.len())
+            .max()
+            .unwrap();
+        assert!(longest <= 4, "streak of {longest}");
+    }
+
+    #[test]
+    fn verify_sides_follow_rotation() {
+        // A 3x6 board given a quarter turn between every drop, so the sides swap lengths each roll
+        let orientations = [(3, 6), (6, 3)];
+
+        for kind in [GeneratorKind::Bag, GeneratorKind::AntiStreak] {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut generator = kind.build(3, 6);
+            let mut rolled = [Vec::new(), Vec::new()];
+            let mut sides = Vec::with_capacity(2 * ROLLS);
+
+            // Twice as many rolls, so each way the board is turned gets as many as the other tests
+            for roll in 0..2 * ROLLS {
+                let (width, height) = orientations[roll % 2];
+                let slot = generator.next_spot(&mut rng).slot(width, height);
+
+                rolled[roll % 2].push(slot);
+                sides.push(side_on(width, height, slot));
+            }
+
+            // Each side comes up evenly with the slots spread along it, whichever way the board is turned
+            for ((width, height), slots) in orientations.into_iter().zip(&rolled) {
+                assert_distribution(
+                    &slot_counts_on(width, height, slots),
+                    &side_slot_shares(width, height),
+                );
+            }
+
+            match kind {
+                GeneratorKind::Bag => {
+                    for bag in sides.chunks(SIDES) {
+                        let mut bag = bag.to_vec();
+                        bag.sort();
+                        assert_eq!(bag, (0..SIDES).collect::<Vec<_>>());
+                    }
+                }
+                _ => {
+                    let repeats = sides.windows(2).filter(|pair| pair[0] == pair[1]).count();
+                    let rate = repeats as f64 / (sides.len() - 1) as f64;
+                    assert!(rate > 0.1 && rate < 0.16, "repeat rate {rate}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_generators_are_seeded() {
+        for kind in GeneratorKind::iter() {
+            assert_eq!(roll(kind, 3), roll(kind, 3));
+            assert_ne!(roll(kind, 3), roll(kind, 4));
+        }
+    }
+}
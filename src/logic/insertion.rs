@@ -1,7 +1,8 @@
 use nalgebra::DMatrix;
 
-use super::error::GameError;
+use super::{error::GameError, tile::Tile};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq)]
 pub enum InsertionDirection {
     FromTop,
@@ -11,7 +12,7 @@ pub enum InsertionDirection {
 }
 
 impl InsertionDirection {
-    pub fn get_side_index(&self, board: &DMatrix<u8>, slot: usize) -> usize {
+    pub fn get_side_index(&self, board: &DMatrix<Tile>, slot: usize) -> usize {
         let passed_slots = match self {
             InsertionDirection::FromTop => 0,
             InsertionDirection::FromRight => board.ncols(),
@@ -22,15 +23,15 @@ impl InsertionDirection {
         let result = slot - passed_slots;
 
         match self {
-            InsertionDirection::FromBottom => board.nrows().saturating_sub(result + 1),
-            InsertionDirection::FromLeft => board.ncols().saturating_sub(result + 1),
+            InsertionDirection::FromBottom => board.ncols().saturating_sub(result + 1),
+            InsertionDirection::FromLeft => board.nrows().saturating_sub(result + 1),
             _ => result,
         }
     }
 
     /// Calculations what size the insertion is from given a board and a slot
     pub fn for_board_insertion(
-        board: &DMatrix<u8>,
+        board: &DMatrix<Tile>,
         slot: usize,
     ) -> Result<InsertionDirection, GameError> {
         let width = board.ncols();
@@ -58,45 +59,46 @@ impl InsertionDirection {
         Err(GameError::InvalidPlacementLocation(slot))
     }
 
-    /// For a slice, insertion begins at 0 and goes to the length of the slice.
-    pub fn place(&self, slice: &mut [u8]) -> Result<usize, GameError> {
-        let first_one_found = slice.iter().position(|&x| x == 1);
-
-        if let Some(one_found) = first_one_found {
-            // Place before
-            if one_found > 0 {
-                if slice.get(one_found - 1).map(|&x| x == 0).is_some_and(|x| x) {
-                    slice[one_found - 1] = 1;
-                    return Ok(one_found - 1);
-                }
-            }
-
-            // Otherwise, error as there's no space
-            return Err(GameError::NoSpace);
-        } else {
-            slice[slice.len() - 1] = 1;
+    /// Where in a line a tile dropped in from its start would land, without placing it
+    pub fn landing_index(&self, line: &[Tile]) -> Result<usize, GameError> {
+        match line.iter().position(|x| x.is_filled()) {
+            // Lands on top of the first filled tile, unless that's already at the edge
+            Some(0) => Err(GameError::NoSpace),
+            Some(one_found) => Ok(one_found - 1),
+            None => Ok(line.len() - 1),
         }
+    }
+
+    /// For a slice, insertion begins at 0 and goes to the length of the slice.
+    pub fn place(&self, slice: &mut [Tile], tile: Tile) -> Result<usize, GameError> {
+        let index = self.landing_index(slice)?;
+        slice[index] = tile;
 
-        Ok(slice.len() - 1)
+        Ok(index)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::InsertionDirection;
+    use crate::logic::tile::Tile;
     use nalgebra::DMatrix;
     use std::ops::Range;
 
-    fn matrix_iter(size_range: Range<usize>) -> impl Iterator<Item = Box<DMatrix<u8>>> {
-        size_range
-            .into_iter()
-            .map(|n| Box::new(DMatrix::zeros(n, n)))
+    fn matrix_iter(size_range: Range<usize>) -> impl Iterator<Item = Box<DMatrix<Tile>>> {
+        size_range.flat_map(|n| {
+            [
+                Box::new(DMatrix::from_element(n, n, Tile::Empty)),
+                Box::new(DMatrix::from_element(n, n + 1, Tile::Empty)),
+                Box::new(DMatrix::from_element(n + 2, n, Tile::Empty)),
+            ]
+        })
     }
 
     #[test]
     pub fn verify_top_insertion() {
         matrix_iter(2..10).for_each(|matrix| {
-            (0..matrix.ncols()).into_iter().for_each(|slot| {
+            (0..matrix.ncols()).for_each(|slot| {
                 let direction = InsertionDirection::for_board_insertion(&matrix, slot).unwrap();
                 assert_eq!(
                     direction,
@@ -110,16 +112,14 @@ mod tests {
     #[test]
     pub fn verify_right_insertion() {
         matrix_iter(2..10).for_each(|matrix| {
-            (matrix.ncols()..(matrix.ncols() + matrix.nrows()))
-                .into_iter()
-                .for_each(|slot| {
-                    let direction = InsertionDirection::for_board_insertion(&matrix, slot).unwrap();
-                    assert_eq!(
-                        direction,
-                        InsertionDirection::FromRight,
-                        "Failure on slot {slot}"
-                    )
-                });
+            (matrix.ncols()..(matrix.ncols() + matrix.nrows())).for_each(|slot| {
+                let direction = InsertionDirection::for_board_insertion(&matrix, slot).unwrap();
+                assert_eq!(
+                    direction,
+                    InsertionDirection::FromRight,
+                    "Failure on slot {slot}"
+                )
+            });
         });
     }
 
@@ -127,7 +127,6 @@ mod tests {
     pub fn verify_bottom_insertion() {
         matrix_iter(2..10).for_each(|matrix| {
             ((matrix.ncols() + matrix.nrows())..(matrix.ncols() + matrix.nrows() + matrix.ncols()))
-                .into_iter()
                 .for_each(|slot| {
                     let direction = InsertionDirection::for_board_insertion(&matrix, slot).unwrap();
                     assert_eq!(
@@ -145,7 +144,7 @@ mod tests {
             let lower = matrix.ncols() + matrix.nrows() + matrix.ncols();
             let upper = matrix.ncols() + matrix.nrows() + matrix.ncols() + matrix.nrows();
 
-            (lower..upper).into_iter().for_each(|slot| {
+            (lower..upper).for_each(|slot| {
                 let direction = InsertionDirection::for_board_insertion(&matrix, slot).unwrap();
                 assert_eq!(
                     direction,
@@ -161,7 +160,6 @@ mod tests {
         matrix_iter(2..10).for_each(|matrix| {
             ((matrix.ncols() + matrix.nrows() + matrix.ncols() + matrix.nrows())
                 ..(matrix.ncols() + matrix.nrows() + matrix.ncols() + matrix.nrows()) * 2)
-                .into_iter()
                 .for_each(|slot| {
                     let direction = InsertionDirection::for_board_insertion(&matrix, slot);
                     assert!(direction.is_err());
@@ -171,7 +169,7 @@ mod tests {
 
     #[test]
     pub fn verify_get_side_index() {
-        let board: DMatrix<u8> = DMatrix::zeros(3, 3);
+        let board = DMatrix::from_element(3, 3, Tile::Empty);
 
         assert_eq!(InsertionDirection::FromTop.get_side_index(&board, 0), 0);
         assert_eq!(InsertionDirection::FromTop.get_side_index(&board, 1), 1);
@@ -189,4 +187,21 @@ mod tests {
         assert_eq!(InsertionDirection::FromLeft.get_side_index(&board, 10), 1);
         assert_eq!(InsertionDirection::FromLeft.get_side_index(&board, 11), 0);
     }
+
+    #[test]
+    pub fn verify_get_side_index_rectangular() {
+        // 2 rows, 4 columns
+        let board = DMatrix::from_element(2, 4, Tile::Empty);
+
+        assert_eq!(InsertionDirection::FromTop.get_side_index(&board, 3), 3);
+
+        assert_eq!(InsertionDirection::FromRight.get_side_index(&board, 4), 0);
+        assert_eq!(InsertionDirection::FromRight.get_side_index(&board, 5), 1);
+
+        assert_eq!(InsertionDirection::FromBottom.get_side_index(&board, 6), 3);
+        assert_eq!(InsertionDirection::FromBottom.get_side_index(&board, 9), 0);
+
+        assert_eq!(InsertionDirection::FromLeft.get_side_index(&board, 10), 1);
+        assert_eq!(InsertionDirection::FromLeft.get_side_index(&board, 11), 0);
+    }
 }
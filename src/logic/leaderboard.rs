@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use super::mode::GameModeKind;
+
+/// How many entries are kept for each mode
+pub const ENTRIES_PER_MODE: usize = 10;
+
+/// A finished game on the leaderboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub score: u64,
+    /// Rank reached by the end of the game
+    pub rank: u32,
+    /// Columns on the board before any rotation
+    pub board_width: usize,
+    /// Rows on the board before any rotation
+    pub board_height: usize,
+    /// The category the game was played in. Scores are only compared within a mode
+    pub mode: String,
+    /// The rules the game was played under, which decide whether it's ranked by score or time
+    #[serde(default)]
+    pub kind: GameModeKind,
+    /// Seconds of game time the game lasted
+    #[serde(default)]
+    pub time: f32,
+    /// Unix timestamp in seconds of when the game ended
+    pub date: u64,
+    /// File name of the game's replay, if one was saved
+    pub replay: Option<String>,
+}
+
+impl LeaderboardEntry {
+    /// Orders entries best first: by score, or by fastest time in modes ranked by time.
+    /// Modes ranked by score are placed before those ranked by time.
+    fn cmp_rank(&self, other: &Self) -> Ordering {
+        let timed = self.kind.ranked_by_time();
+
+        timed.cmp(&other.kind.ranked_by_time()).then_with(|| {
+            if timed {
+                self.time.total_cmp(&other.time)
+            } else {
+                other.score.cmp(&self.score)
+            }
+        })
+    }
+}
+
+/// The best games played, kept separately for each mode
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    /// Every kept entry, best first
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Modes with at least one entry, ordered by their best entry
+    pub fn modes(&self) -> Vec<&str> {
+        let mut modes = Vec::new();
+
+        for entry in &self.entries {
+            if !modes.contains(&entry.mode.as_str()) {
+                modes.push(entry.mode.as_str());
+            }
+        }
+
+        modes
+    }
+
+    /// Entries for a mode, best first
+    pub fn entries<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a LeaderboardEntry> {
+        self.entries.iter().filter(move |entry| entry.mode == mode)
+    }
+
+    /// Adds an entry, keeping only the best [`ENTRIES_PER_MODE`] for its mode.
+    ///
+    /// Returns where the entry placed in its mode (0 is the best), or `None` if it didn't make the board.
+    /// Ties go to the entry that was submitted first.
+    pub fn submit(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let placement = self
+            .entries(&entry.mode)
+            .filter(|x| x.cmp_rank(&entry).is_le())
+            .count();
+
+        if placement >= ENTRIES_PER_MODE {
+            return None;
+        }
+
+        let mode = entry.mode.clone();
+        self.entries.push(entry);
+        // Stable, so equal scores keep their submission order
+        self.entries.sort_by(|a, b| a.cmp_rank(b));
+
+        let mut kept = 0;
+        self.entries.retain(|x| {
+            if x.mode != mode {
+                return true;
+            }
+
+            kept += 1;
+            kept <= ENTRIES_PER_MODE
+        });
+
+        Some(placement)
+    }
+}
+
+/// Formats seconds as `M:SS.CC`
+pub fn format_time(secs: f32) -> String {
+    let hundredths = (secs.max(0.0) * 100.0).round() as u64;
+
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// Formats a unix timestamp in seconds as a `YYYY-MM-DD` date in UTC
+pub fn format_date(unix_secs: u64) -> String {
+    // Converts days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (unix_secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logic::mode::GameModeKind;
+
+    use super::{format_date, format_time, Leaderboard, LeaderboardEntry, ENTRIES_PER_MODE};
+
+    fn entry(mode: &str, score: u64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            score,
+            rank: 1,
+            board_width: 4,
+            board_height: 4,
+            mode: mode.to_string(),
+            kind: GameModeKind::Endless,
+            time: 0.0,
+            date: 0,
+            replay: None,
+        }
+    }
+
+    fn sprint(time: f32) -> LeaderboardEntry {
+        LeaderboardEntry {
+            kind: GameModeKind::Sprint,
+            time,
+            // Slower games can still score more
+            score: time as u64,
+            ..entry("Sprint Standard", 0)
+        }
+    }
+
+    #[test]
+    fn verify_submit() {
+        let mut leaderboard = Leaderboard::default();
+
+        assert_eq!(leaderboard.submit(entry("Standard", 100)), Some(0));
+        assert_eq!(leaderboard.submit(entry("Standard", 300)), Some(0));
+        assert_eq!(leaderboard.submit(entry("Standard", 200)), Some(1));
+        // Ties place behind the existing score
+        assert_eq!(leaderboard.submit(entry("Standard", 200)), Some(2));
+        // Other modes are ranked separately
+        assert_eq!(leaderboard.submit(entry("Hardcore", 50)), Some(0));
+
+        let scores = leaderboard
+            .entries("Standard")
+            .map(|x| x.score)
+            .collect::<Vec<_>>();
+        assert_eq!(scores, vec![300, 200, 200, 100]);
+        assert_eq!(leaderboard.modes(), vec!["Standard", "Hardcore"]);
+    }
+
+    #[test]
+    fn verify_entries_per_mode() {
+        let mut leaderboard = Leaderboard::default();
+
+        for score in 0..ENTRIES_PER_MODE as u64 {
+            leaderboard.submit(entry("Standard", score + 10));
+        }
+        leaderboard.submit(entry("Casual", 1));
+
+        // Too low to make the board
+        assert_eq!(leaderboard.submit(entry("Standard", 5)), None);
+        // Pushes the lowest score off the board
+        assert_eq!(leaderboard.submit(entry("Standard", 15)), Some(5));
+
+        assert_eq!(leaderboard.entries("Standard").count(), ENTRIES_PER_MODE);
+        assert_eq!(leaderboard.entries("Standard").last().unwrap().score, 11);
+        assert_eq!(leaderboard.entries("Casual").count(), 1);
+    }
+
+    #[test]
+    fn verify_timed_submit() {
+        let mut leaderboard = Leaderboard::default();
+
+        assert_eq!(leaderboard.submit(entry("Standard", 100)), Some(0));
+        assert_eq!(leaderboard.submit(sprint(90.0)), Some(0));
+        assert_eq!(leaderboard.submit(sprint(60.5)), Some(0));
+        assert_eq!(leaderboard.submit(sprint(120.0)), Some(2));
+        // Ties place behind the existing time
+        assert_eq!(leaderboard.submit(sprint(60.5)), Some(1));
+
+        let times = leaderboard
+            .entries("Sprint Standard")
+            .map(|x| x.time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![60.5, 60.5, 90.0, 120.0]);
+        assert_eq!(leaderboard.modes(), vec!["Standard", "Sprint Standard"]);
+
+        for _ in 0..ENTRIES_PER_MODE {
+            leaderboard.submit(sprint(30.0));
+        }
+        // Too slow to make the board
+        assert_eq!(leaderboard.submit(sprint(31.0)), None);
+        assert_eq!(
+            leaderboard.entries("Sprint Standard").count(),
+            ENTRIES_PER_MODE
+        );
+    }
+
+    #[test]
+    fn verify_format_time() {
+        assert_eq!(format_time(0.0), "0:00.00");
+        assert_eq!(format_time(9.876), "0:09.88");
+        assert_eq!(format_time(125.5), "2:05.50");
+        assert_eq!(format_time(-1.0), "0:00.00");
+    }
+
+    #[test]
+    fn verify_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_704_067_199), "2023-12-31");
+    }
+}
@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    board::GameBoard,
+    error::GameError,
+    garbage,
+    session::{GameAction, GameSession, SessionConfig},
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LockstepError {
+    #[error("Boards went out of sync on turn {0}")]
+    Desync(u32),
+    #[error("Turn {0} wasn't played through to its last drop, or went on past it")]
+    InvalidTurn(u32),
+}
+
+/// Bumped whenever peers on different versions could no longer play together
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A turn played on one peer, sent to the other so it can be played there too
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnInputs {
+    /// Turns played before this one
+    pub turn: u32,
+    /// Every action taken during the turn, drops included
+    pub actions: Vec<GameAction>,
+    /// [`checksum`] of the sender's board once the turn was played
+    pub checksum: u64,
+}
+
+/// Everything sent between peers. Only inputs are sent, each peer simulates both boards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NetMessage {
+    /// Sent by the host once connected, with the match both boards are played with
+    Start {
+        version: u32,
+        config: Box<SessionConfig>,
+        seed: u64,
+    },
+    Turn(TurnInputs),
+}
+
+/// Hashes a board's tiles, so peers can check they've both ended a turn with the same board.
+/// FNV-1a is used as it's the same on every platform and build, unlike the std hasher.
+pub fn checksum(board: &GameBoard) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let dims = [board.width() as u64, board.height() as u64];
+    let tiles = board.board().iter().map(|tile| tile.to_string());
+
+    dims.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .chain(tiles.flat_map(String::into_bytes))
+        .fold(OFFSET, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+}
+
+/// Garbage sent to a board, waiting for the turn it lands on.
+///
+/// Lines cleared on a turn land on the opponent right after their own turn of the same number,
+/// so both peers push them in at the same point whichever board finished its turn first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GarbageQueue(Vec<(u32, u32)>);
+
+impl GarbageQueue {
+    /// Queues the garbage sent by clearing lines on the opponent's turn
+    pub fn send(&mut self, turn: u32, cleared: u32) {
+        let lines = garbage::lines_sent(cleared);
+        if lines > 0 {
+            self.0.push((turn, lines));
+        }
+    }
+
+    /// Takes the lines due once a board has played `turns_played` turns
+    pub fn take_due(&mut self, turns_played: u32) -> u32 {
+        let mut lines = 0;
+        self.0.retain(|(turn, sent)| {
+            let due = *turn < turns_played;
+            if due {
+                lines += sent;
+            }
+            !due
+        });
+
+        lines
+    }
+}
+
+/// Plays a turn's actions on a session, stopping early if the game is lost
+fn play_actions(session: &mut GameSession, actions: &[GameAction]) -> Result<(), GameError> {
+    for action in actions {
+        session.step(*action)?;
+    }
+
+    Ok(())
+}
+
+/// Plays one of a board's turns from its actions, checking they make up exactly that turn.
+/// Returns whether the board was lost, either during the turn or for lack of space for the next one.
+pub fn play_turn(
+    session: &mut GameSession,
+    turn: u32,
+    actions: &[GameAction],
+) -> Result<bool, LockstepError> {
+    let lost = play_actions(session, actions).is_err() || !session.has_legal_move();
+    if !lost && session.turns_played() as u32 != turn + 1 {
+        return Err(LockstepError::InvalidTurn(turn));
+    }
+
+    Ok(lost)
+}
+
+/// The turns each seat of a lockstep match has played, and the garbage waiting on them.
+///
+/// A seat can't start a turn until the other has played the one before it, so garbage always
+/// lands at the same point on both peers.
+#[derive(Debug, Clone, Default)]
+pub struct Lockstep {
+    garbage: [GarbageQueue; 2],
+    /// Turns each seat has played, the one it was lost on included
+    turns: [u32; 2],
+}
+
+impl Lockstep {
+    /// Turns a seat has played, the one it was lost on included
+    pub fn turns(&self, seat: usize) -> u32 {
+        self.turns[seat]
+    }
+
+    /// Whether a seat can play its next turn, which waits on the other seat catching up
+    pub fn can_play(&self, seat: usize) -> bool {
+        self.turns(seat) <= self.turns(1 - seat)
+    }
+
+    /// Ends a seat's turn, sending garbage for the lines it cleared to the other seat. Returns the turn's number.
+    pub fn end_turn(&mut self, seat: usize, cleared: u32) -> u32 {
+        let turn = self.turns[seat];
+        self.turns[seat] += 1;
+        self.garbage[1 - seat].send(turn, cleared);
+
+        turn
+    }
+
+    /// Takes the garbage lines that are due on a seat's board
+    pub fn take_garbage(&mut self, seat: usize) -> u32 {
+        self.garbage[seat].take_due(self.turns[seat])
+    }
+}
+
+/// A versus match kept in lockstep between two peers without anything rendered.
+///
+/// Both boards are simulated on each peer from the same seed.
+pub struct NetMatch {
+    sessions: [GameSession; 2],
+    lockstep: Lockstep,
+    /// Whether each board has been lost
+    lost: [bool; 2],
+}
+
+impl NetMatch {
+    pub fn new(config: SessionConfig, seed: u64) -> Self {
+        let session = GameSession::new(config, seed);
+
+        Self {
+            sessions: [session.clone(), session],
+            lockstep: Lockstep::default(),
+            lost: [false; 2],
+        }
+    }
+
+    /// The game on a seat's board, 0 for the host and 1 for the peer that joined
+    pub fn session(&self, seat: usize) -> &GameSession {
+        &self.sessions[seat]
+    }
+
+    pub fn lost(&self, seat: usize) -> bool {
+        self.lost[seat]
+    }
+
+    /// Whether the match is over, with either board lost
+    pub fn over(&self) -> bool {
+        self.lost.iter().any(|x| *x)
+    }
+
+    /// Turns a seat has played, the one it was lost on included
+    pub fn turns(&self, seat: usize) -> u32 {
+        self.lockstep.turns(seat)
+    }
+
+    /// Whether a seat can play its next turn, which waits on the other seat catching up
+    pub fn can_play(&self, seat: usize) -> bool {
+        !self.over() && self.lockstep.can_play(seat)
+    }
+
+    /// Plays a seat's next turn, sending any garbage it cleared to the other seat.
+    /// Returns the turn as it's sent to the other peer.
+    ///
+    /// The turn is checked against `expected` when it came from the other peer, failing with
+    /// [`LockstepError::Desync`] if their board ended up different.
+    pub fn play_turn(
+        &mut self,
+        seat: usize,
+        actions: &[GameAction],
+        expected: Option<u64>,
+    ) -> Result<TurnInputs, LockstepError> {
+        let turn = self.turns(seat);
+        let session = &mut self.sessions[seat];
+        let cleared = session.board().lines_cleared();
+
+        self.lost[seat] = play_turn(session, turn, actions)?;
+
+        let session = &self.sessions[seat];
+        let inputs = TurnInputs {
+            turn,
+            actions: actions.to_vec(),
+            checksum: checksum(session.board()),
+        };
+
+        if expected.is_some_and(|x| x != inputs.checksum) {
+            return Err(LockstepError::Desync(turn));
+        }
+
+        self.lockstep
+            .end_turn(seat, session.board().lines_cleared() - cleared);
+        self.receive_garbage();
+
+        Ok(inputs)
+    }
+
+    /// Pushes garbage onto both boards once it's due
+    fn receive_garbage(&mut self) {
+        for seat in 0..2 {
+            let lines = self.lockstep.take_garbage(seat);
+            if lines == 0 || self.lost[seat] {
+                continue;
+            }
+
+            self.sessions[seat].receive_garbage(lines);
+            if !self.sessions[seat].has_legal_move() {
+                self.lost[seat] = true;
+            }
+        }
+    }
+}
+
+/// The actions the session's next turn is played with: a rotation, then each of the turn's drops
+pub fn turn_actions(session: &GameSession, rotation: GameAction) -> Vec<GameAction> {
+    let drops = session.turn_drops().len();
+
+    std::iter::once(rotation)
+        .chain(std::iter::repeat(GameAction::Drop).take(drops))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use crate::logic::{
+        ai::{Rotation, Solver},
+        board::GameBoard,
+        session::{GameAction, SessionConfig},
+        tile::Tile,
+    };
+
+    use super::{checksum, turn_actions, GarbageQueue, LockstepError, NetMatch};
+
+    #[test]
+    fn verify_checksum() {
+        let board = GameBoard::new(4, 4);
+        assert_eq!(checksum(&board), checksum(&GameBoard::new(4, 4)));
+
+        // Same tiles in a different shape
+        assert_ne!(checksum(&board), checksum(&GameBoard::new(2, 8)));
+
+        let mut placed = board.clone();
+        placed.place_tile(0, Tile::FILLED).unwrap();
+        assert_ne!(checksum(&board), checksum(&placed));
+    }
+
+    #[test]
+    fn verify_garbage_queue() {
+        let mut queue = GarbageQueue::default();
+
+        // A lone line sends nothing
+        queue.send(0, 1);
+        assert_eq!(queue, GarbageQueue::default());
+
+        queue.send(2, 3);
+        queue.send(3, 2);
+
+        // Lands after the receiver's own turn of the same number
+        assert_eq!(queue.take_due(2), 0);
+        assert_eq!(queue.take_due(3), 2);
+        assert_eq!(queue.take_due(5), 1);
+        assert_eq!(queue.take_due(5), 0);
+    }
+
+    /// Plays both seats with the solver, each peer checking the other's turns
+    #[test]
+    fn verify_lockstep() {
+        let solver = Solver::default();
+        let options = Rotation::iter().collect::<Vec<_>>();
+        let config = SessionConfig::new(6, 6);
+
+        // Each peer simulates both boards
+        let mut host = NetMatch::new(config.clone(), 7);
+        let mut peer = NetMatch::new(config, 7);
+
+        for _ in 0..60 {
+            if host.over() {
+                break;
+            }
+
+            for seat in 0..2 {
+                assert!(host.can_play(seat) || host.over());
+                if host.over() {
+                    break;
+                }
+
+                let (local, remote) = if seat == 0 {
+                    (&mut host, &mut peer)
+                } else {
+                    (&mut peer, &mut host)
+                };
+
+                let rotation = solver
+                    .best_rotation_for(local.session(seat), &options)
+                    .unwrap_or(Rotation::None);
+                let actions = turn_actions(local.session(seat), rotation.action());
+
+                let sent = local.play_turn(seat, &actions, None).unwrap();
+                let played = remote
+                    .play_turn(seat, &sent.actions, Some(sent.checksum))
+                    .unwrap();
+                assert_eq!(played, sent);
+            }
+
+            // Neither seat can get more than a turn ahead
+            assert!(!host.can_play(0) || host.turns(0) == host.turns(1));
+        }
+
+        for seat in 0..2 {
+            assert_eq!(
+                host.session(seat).board().board(),
+                peer.session(seat).board().board()
+            );
+            assert_eq!(host.lost(seat), peer.lost(seat));
+        }
+    }
+
+    #[test]
+    fn verify_desync() {
+        let config = SessionConfig::new(5, 5);
+        let mut host = NetMatch::new(config.clone(), 1);
+        let mut peer = NetMatch::new(config, 2);
+
+        let actions = turn_actions(host.session(0), GameAction::RotateLeft);
+        let sent = host.play_turn(0, &actions, None).unwrap();
+
+        // Different seeds roll different drops
+        assert_eq!(
+            peer.play_turn(0, &sent.actions, Some(sent.checksum)),
+            Err(LockstepError::Desync(0))
+        );
+
+        // A turn has to be played through to its last drop, and no further
+        let mut host = NetMatch::new(SessionConfig::new(5, 5), 1);
+        assert_eq!(
+            host.play_turn(0, &[GameAction::RotateLeft], None),
+            Err(LockstepError::InvalidTurn(0))
+        );
+
+        let mut two_turns = turn_actions(host.session(0), GameAction::RotateLeft);
+        two_turns.extend(turn_actions(host.session(0), GameAction::Flip));
+        assert_eq!(
+            host.play_turn(0, &two_turns, None),
+            Err(LockstepError::InvalidTurn(0))
+        );
+    }
+}
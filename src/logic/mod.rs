@@ -1,4 +1,14 @@
+pub mod ai;
 pub mod board;
 pub mod error;
+pub mod garbage;
+pub mod generator;
 pub mod insertion;
+pub mod leaderboard;
+pub mod lockstep;
+pub mod mode;
+pub mod puzzle;
+pub mod replay;
 pub mod score;
+pub mod session;
+pub mod tile;
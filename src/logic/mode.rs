@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// Lines a sprint is raced to
+pub const SPRINT_LINES: u32 = 40;
+/// Seconds of game time an ultra game lasts
+pub const ULTRA_SECS: f32 = 120.0;
+
+/// The rules a game is played to the end under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum GameModeKind {
+    /// Plays on until there's no space left
+    #[default]
+    Endless,
+    /// Clear [`SPRINT_LINES`] lines as fast as possible
+    Sprint,
+    /// Score as much as possible in [`ULTRA_SECS`] seconds
+    Ultra,
+}
+
+impl GameModeKind {
+    /// Whether finished games are ranked by how fast they were, rather than by score
+    pub fn ranked_by_time(&self) -> bool {
+        *self == GameModeKind::Sprint
+    }
+}
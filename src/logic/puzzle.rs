@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    board::GameBoard,
+    session::SessionConfig,
+    tile::{ClearRules, Tile},
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PuzzleError {
+    #[error("The level has no board")]
+    NoBoard,
+    #[error("Row {row} is {found} tiles wide, expected {expected}")]
+    RaggedBoard {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Unknown tile '{0}', expected one of . R G B Y W * # @ %")]
+    UnknownTile(char),
+    #[error("The level has no drops")]
+    NoDrops,
+    #[error("Drop {0} is off the board or has no tile")]
+    InvalidDrop(usize),
+    #[error("The level allows no moves")]
+    NoMoves,
+    #[error("The level's goal is met before any moves")]
+    AlreadySolved,
+}
+
+/// What has to be done to solve a puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PuzzleGoal {
+    /// Clear at least this many rows and columns
+    ClearLines(u32),
+    /// Leave no tiles on the board
+    EmptyBoard,
+}
+
+impl PuzzleGoal {
+    /// Whether the board has reached the goal
+    pub fn is_met(&self, board: &GameBoard) -> bool {
+        match self {
+            PuzzleGoal::ClearLines(lines) => board.lines_cleared() >= *lines,
+            PuzzleGoal::EmptyBoard => board.is_empty(),
+        }
+    }
+
+    /// The goal as shown to the player
+    pub fn describe(&self) -> String {
+        match self {
+            PuzzleGoal::ClearLines(1) => String::from("Clear a line"),
+            PuzzleGoal::ClearLines(lines) => format!("Clear {lines} lines"),
+            PuzzleGoal::EmptyBoard => String::from("Empty the board"),
+        }
+    }
+}
+
+/// How a puzzle is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    InProgress,
+    Solved,
+    /// Every move was used without reaching the goal
+    Failed,
+}
+
+/// A level as written in its file, with the board drawn as rows of tile characters
+#[derive(Serialize, Deserialize)]
+struct LevelFile {
+    name: String,
+    board: Vec<String>,
+    drops: Vec<(usize, Tile)>,
+    move_limit: usize,
+    goal: PuzzleGoal,
+    #[serde(default)]
+    clear_rules: ClearRules,
+}
+
+/// An authored puzzle: a starting board, a fixed sequence of drops and a goal to reach within a number of moves.
+///
+/// Levels are checked as they're read, so a level that loaded can always be played.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LevelFile", into = "LevelFile")]
+pub struct PuzzleLevel {
+    /// Shown on the level select, and what completion is tracked by
+    name: String,
+    /// The starting board
+    tiles: DMatrix<Tile>,
+    /// Slot and tile of each turn's drop, in order
+    drops: Vec<(usize, Tile)>,
+    /// Turns the goal has to be reached within
+    move_limit: usize,
+    goal: PuzzleGoal,
+    /// How full rows/cols are cleared and scored
+    clear_rules: ClearRules,
+}
+
+impl TryFrom<LevelFile> for PuzzleLevel {
+    type Error = PuzzleError;
+
+    fn try_from(file: LevelFile) -> Result<Self, Self::Error> {
+        let height = file.board.len();
+        let width = file.board.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(PuzzleError::NoBoard);
+        }
+
+        let mut tiles = Vec::with_capacity(width * height);
+        for (row, line) in file.board.iter().enumerate() {
+            let found = line.chars().count();
+            if found != width {
+                return Err(PuzzleError::RaggedBoard {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+
+            for c in line.chars() {
+                tiles.push(Tile::from_char(c).ok_or(PuzzleError::UnknownTile(c))?);
+            }
+        }
+
+        if file.drops.is_empty() {
+            return Err(PuzzleError::NoDrops);
+        }
+
+        let slots = 2 * (width + height);
+        if let Some(index) = file
+            .drops
+            .iter()
+            .position(|(slot, tile)| *slot >= slots || !tile.is_filled())
+        {
+            return Err(PuzzleError::InvalidDrop(index));
+        }
+
+        if file.move_limit == 0 {
+            return Err(PuzzleError::NoMoves);
+        }
+
+        let level = Self {
+            name: file.name,
+            tiles: DMatrix::from_row_slice(height, width, &tiles),
+            drops: file.drops,
+            move_limit: file.move_limit,
+            goal: file.goal,
+            clear_rules: file.clear_rules,
+        };
+
+        if level.goal.is_met(&level.board()) {
+            return Err(PuzzleError::AlreadySolved);
+        }
+
+        Ok(level)
+    }
+}
+
+impl From<PuzzleLevel> for LevelFile {
+    fn from(level: PuzzleLevel) -> Self {
+        let board = level
+            .tiles
+            .row_iter()
+            .map(|row| row.iter().map(|tile| tile.to_string()).collect())
+            .collect();
+
+        Self {
+            name: level.name,
+            board,
+            drops: level.drops,
+            move_limit: level.move_limit,
+            goal: level.goal,
+            clear_rules: level.clear_rules,
+        }
+    }
+}
+
+impl PuzzleLevel {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn goal(&self) -> PuzzleGoal {
+        self.goal
+    }
+
+    /// Turns the goal has to be reached within
+    pub fn move_limit(&self) -> usize {
+        self.move_limit
+    }
+
+    /// The slot and tile dropped on a turn, counting from 0.
+    /// The sequence starts over if the move limit outlasts it.
+    pub fn drop(&self, turn: usize) -> (usize, Tile) {
+        self.drops[turn % self.drops.len()]
+    }
+
+    /// A board set up with the level's starting tiles
+    pub fn board(&self) -> GameBoard {
+        GameBoard::new(self.tiles.ncols(), self.tiles.nrows()).with_tiles(self.tiles.clone())
+    }
+
+    /// The config to play the level with, taking anything the level doesn't set from `base`
+    pub fn session_config(&self, base: SessionConfig) -> SessionConfig {
+        SessionConfig {
+            width: self.tiles.ncols(),
+            height: self.tiles.nrows(),
+            clear_rules: self.clear_rules,
+            drops_per_turn: 1,
+            // Nothing past the last move is previewed
+            preview_turns: base.preview_turns.min(self.move_limit - 1),
+            puzzle: Some(self.clone()),
+            ..base
+        }
+    }
+}
+
+/// The puzzles solved so far, saved in the data dir
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PuzzleProgress {
+    /// Fewest moves each solved level took, by level name
+    solved: BTreeMap<String, usize>,
+}
+
+impl PuzzleProgress {
+    /// Records a level as solved. Returns whether it was solved in fewer moves than before.
+    pub fn complete(&mut self, level: &str, moves: usize) -> bool {
+        match self.solved.get(level) {
+            Some(&best) if best <= moves => false,
+            _ => {
+                self.solved.insert(level.to_string(), moves);
+                true
+            }
+        }
+    }
+
+    /// The fewest moves a level has been solved in, or `None` if it hasn't been
+    pub fn best(&self, level: &str) -> Option<usize> {
+        self.solved.get(level).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use strum::IntoEnumIterator;
+
+    use crate::logic::{
+        ai::Rotation,
+        session::{GameAction, GameSession, SessionConfig},
+        tile::{Tile, TileColor},
+    };
+
+    use super::{PuzzleError, PuzzleGoal, PuzzleLevel, PuzzleProgress, PuzzleStatus};
+
+    const LEVEL: &str = r#"(
+        name: "Finish the Row",
+        board: [
+            "RRR.",
+            "....",
+            "B...",
+        ],
+        drops: [(4, Color(Red)), (6, Color(Green))],
+        move_limit: 1,
+        goal: ClearLines(1),
+    )"#;
+
+    fn parse(text: &str) -> Result<PuzzleLevel, String> {
+        ron::from_str(text).map_err(|err| err.code.to_string())
+    }
+
+    #[test]
+    fn verify_load() {
+        let level = parse(LEVEL).unwrap();
+        let board = level.board();
+
+        assert_eq!(level.name(), "Finish the Row");
+        assert_eq!((board.width(), board.height()), (4, 3));
+        assert_eq!(board.board()[(0, 0)], Tile::Color(TileColor::Red));
+        assert_eq!(board.board()[(2, 0)], Tile::Color(TileColor::Blue));
+        assert_eq!(board.board()[(0, 3)], Tile::Empty);
+        assert_eq!(level.drop(0), (4, Tile::Color(TileColor::Red)));
+        // The sequence wraps around
+        assert_eq!(level.drop(3), (6, Tile::Color(TileColor::Green)));
+
+        // Saved levels read back the same
+        let text = ron::to_string(&level).unwrap();
+        assert_eq!(parse(&text).unwrap(), level);
+    }
+
+    #[test]
+    fn verify_load_errors() {
+        let with = |from: &str, to: &str| parse(&LEVEL.replace(from, to)).unwrap_err();
+
+        assert_eq!(
+            with(r#""....","#, r#""...","#),
+            PuzzleError::RaggedBoard {
+                row: 1,
+                expected: 4,
+                found: 3
+            }
+            .to_string()
+        );
+        assert_eq!(
+            with("B...", "Bx.."),
+            PuzzleError::UnknownTile('x').to_string()
+        );
+        assert_eq!(
+            with("(6, Color(Green))", "(14, Color(Green))"),
+            PuzzleError::InvalidDrop(1).to_string()
+        );
+        assert_eq!(
+            with("(4, Color(Red))", "(4, Empty)"),
+            PuzzleError::InvalidDrop(0).to_string()
+        );
+        assert_eq!(
+            with("move_limit: 1", "move_limit: 0"),
+            PuzzleError::NoMoves.to_string()
+        );
+        assert_eq!(
+            with("ClearLines(1)", "ClearLines(0)"),
+            PuzzleError::AlreadySolved.to_string()
+        );
+    }
+
+    #[test]
+    fn verify_goals() {
+        let level = parse(LEVEL).unwrap();
+        let mut board = level.board().with_rows_clearing();
+
+        assert!(!PuzzleGoal::ClearLines(1).is_met(&board));
+        assert!(!PuzzleGoal::EmptyBoard.is_met(&board));
+
+        board.place_tile(4, Tile::FILLED).unwrap();
+        assert!(PuzzleGoal::ClearLines(1).is_met(&board));
+        assert!(!PuzzleGoal::ClearLines(2).is_met(&board));
+        // The blue tile is left behind
+        assert!(!PuzzleGoal::EmptyBoard.is_met(&board));
+    }
+
+    #[test]
+    fn verify_puzzle_session() {
+        let level = parse(LEVEL).unwrap();
+        let config = level.session_config(SessionConfig::new(8, 8));
+        assert_eq!((config.width, config.height), (4, 3));
+
+        // Solved by sliding the first tile into the gap on the top row
+        let mut session = GameSession::new(config.clone(), 0);
+        assert_eq!(session.puzzle_status(), Some(PuzzleStatus::InProgress));
+        assert_eq!(session.drop(), 4);
+        session.step(GameAction::SkipRotation).unwrap();
+        session.step(GameAction::Drop).unwrap();
+        assert_eq!(session.puzzle_status(), Some(PuzzleStatus::Solved));
+        assert_eq!(session.turns_played(), 1);
+
+        // Shifting off the gap uses up the only move
+        let mut session = GameSession::new(config, 0);
+        session.step(GameAction::ShiftUp).unwrap();
+        session.step(GameAction::SkipRotation).unwrap();
+        session.step(GameAction::Drop).unwrap();
+        assert_eq!(session.puzzle_status(), Some(PuzzleStatus::Failed));
+        // The next turn comes from the sequence all the same
+        assert_eq!(session.next_drop(), 6);
+        assert_eq!(session.next_tile(), Tile::Color(TileColor::Green));
+
+        // Endless games aren't puzzles
+        let session = GameSession::new(SessionConfig::new(4, 4), 0);
+        assert_eq!(session.puzzle_status(), None);
+    }
+
+    #[test]
+    fn verify_progress() {
+        let mut progress = PuzzleProgress::default();
+        assert_eq!(progress.best("First"), None);
+
+        assert!(progress.complete("First", 3));
+        assert!(!progress.complete("First", 4));
+        assert!(progress.complete("First", 2));
+        assert_eq!(progress.best("First"), Some(2));
+        assert_eq!(progress.best("Second"), None);
+    }
+
+    /// Tries every shift and rotation on each turn, returning whether any line of play solves the puzzle
+    fn solvable(session: &GameSession) -> bool {
+        match session.puzzle_status() {
+            Some(PuzzleStatus::Solved) => return true,
+            Some(PuzzleStatus::Failed) | None => return false,
+            Some(PuzzleStatus::InProgress) => {}
+        }
+
+        [None, Some(GameAction::ShiftUp), Some(GameAction::ShiftDown)]
+            .into_iter()
+            .flat_map(|shift| Rotation::iter().map(move |rotation| (shift, rotation)))
+            .any(|(shift, rotation)| {
+                let mut next = session.clone();
+                if let Some(shift) = shift {
+                    next.step(shift).unwrap();
+                }
+                next.step(rotation.action()).unwrap();
+
+                next.step(GameAction::Drop).is_ok() && solvable(&next)
+            })
+    }
+
+    #[test]
+    fn verify_shipped_levels() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/levels");
+        let mut levels = 0;
+
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let level = parse(&fs::read_to_string(&path).unwrap())
+                .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+
+            let session = GameSession::new(level.session_config(SessionConfig::new(4, 4)), 0);
+            assert!(solvable(&session), "{} can't be solved", level.name());
+            levels += 1;
+        }
+
+        assert!(levels > 0);
+    }
+}
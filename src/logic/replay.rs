@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use super::session::{GameSession, SessionConfig, TimedAction};
+
+/// A recorded game that can be played back deterministically.
+///
+/// Since drops are rolled from the seed, the seed, config and timed inputs are
+/// all that's needed to reproduce a [`GameSession`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// Seed of the drop generator
+    pub seed: u64,
+    /// How the session was set up, including the board size before any rotation
+    pub config: SessionConfig,
+    /// Every input applied during the game, in order
+    pub inputs: Vec<TimedAction>,
+    /// Score the game finished with
+    pub final_score: u64,
+}
+
+impl Replay {
+    /// Records a replay of everything that has happened in a session so far
+    pub fn from_session(session: &GameSession) -> Self {
+        Self {
+            seed: session.seed(),
+            config: session.config().clone(),
+            inputs: session.history().to_vec(),
+            final_score: session.board().score().score(),
+        }
+    }
+
+    /// Plays every input back on a fresh session without any rendering.
+    /// The game plays replays back the same way through its controls, this checks they reproduce.
+    #[cfg(test)]
+    pub fn simulate(&self) -> GameSession {
+        let mut session = GameSession::new(self.config.clone(), self.seed);
+
+        for input in &self.inputs {
+            session.tick((input.time - session.elapsed()).max(0.0));
+            // Recorded inputs were all successful, so this only fails on a corrupted replay
+            let _ = session.step(input.action);
+        }
+
+        session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logic::session::{GameAction, GameSession, SessionConfig};
+
+    use super::Replay;
+
+    #[test]
+    fn verify_simulate() {
+        let mut session = GameSession::new(SessionConfig::new(5, 3), 99);
+
+        for turn in 0..40 {
+            if turn % 4 == 0 {
+                session.step(GameAction::ShiftUp).unwrap();
+            }
+            session.tick(0.25);
+            session.step(GameAction::RankBoost).unwrap();
+            session.step(GameAction::RotateRight).unwrap();
+            session.tick(0.5);
+            if session.step(GameAction::Drop).is_err() {
+                break;
+            }
+        }
+
+        let replay = Replay::from_session(&session);
+        let simulated = replay.simulate();
+
+        assert_eq!(simulated.board().board(), session.board().board());
+        assert_eq!(simulated.history(), session.history());
+        assert_eq!(simulated.board().score().score(), replay.final_score);
+    }
+
+    /// Games pass time a frame at a time, which the replay catches up on in one tick per input
+    #[test]
+    fn verify_simulate_frames() {
+        let mut session = GameSession::new(SessionConfig::new(4, 6), 12);
+        let frames = [1.0 / 60.0, 1.0 / 144.0, 0.0213, 1.0 / 30.0];
+
+        for turn in 0..60 {
+            for frame in frames.iter().cycle().skip(turn % 4).take(turn % 7 + 3) {
+                session.tick(*frame);
+            }
+            if turn % 5 == 0 {
+                session.step(GameAction::RankBoost).unwrap();
+            }
+            session.step(GameAction::RotateLeft).unwrap();
+            session.tick(frames[turn % 4] * 31.0);
+            if session.step(GameAction::Drop).is_err() {
+                break;
+            }
+        }
+
+        let replay = Replay::from_session(&session);
+        let mut simulated = replay.simulate();
+
+        assert_eq!(simulated.board().board(), session.board().board());
+        assert_eq!(simulated.history(), session.history());
+        assert_eq!(simulated.board().score().score(), replay.final_score);
+
+        // Time passed after the last input isn't recorded, but lands the same once caught up on
+        simulated.tick(session.elapsed() - simulated.elapsed());
+        assert_eq!(
+            simulated.board().score().current_mult(),
+            session.board().score().current_mult()
+        );
+    }
+
+    #[test]
+    fn verify_serialization() {
+        let mut session = GameSession::new(SessionConfig::new(3, 3), 5);
+        session.step(GameAction::RotateLeft).unwrap();
+        session.tick(0.5);
+        session.step(GameAction::Drop).unwrap();
+
+        let replay = Replay::from_session(&session);
+        let text = ron::to_string(&replay).unwrap();
+
+        assert_eq!(ron::from_str::<Replay>(&text).unwrap(), replay);
+    }
+}
@@ -1,4 +1,88 @@
 use bevy::math::FloatExt;
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// The tunable constants behind [`GameScore`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoringRules {
+    /// How much of the clear multiplier decays away every second
+    pub mult_decay_rate: f32,
+    /// The drop timer multiplier for dropping a block right away
+    pub drop_timer_max_mult: f32,
+    /// Seconds until the drop timer multiplier runs out
+    pub drop_timer_secs: f32,
+    /// How much of the drop timer multiplier is kept by a turn played without rotating, from 0 to 1
+    #[serde(default)]
+    pub unrotated_drop_timer_mult: f32,
+    /// The score needed for the next rank is `rank_score_scale * rank ^ rank_score_exponent`
+    pub rank_score_scale: u64,
+    pub rank_score_exponent: u32,
+    /// Clearing `n` rows or columns at once adds `n ^ clear_mult_exponent` to the multiplier
+    pub clear_mult_exponent: f32,
+    /// Seconds of rank boost granted for every rank left after boosting
+    pub rank_boost_secs_per_rank: f32,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringPreset::Standard.rules()
+    }
+}
+
+impl ScoringRules {
+    /// Calculates the score required to rank up from a rank
+    fn next_rank_score(&self, current_rank: u64) -> u64 {
+        self.rank_score_scale * current_rank.pow(self.rank_score_exponent)
+    }
+}
+
+/// Named sets of [`ScoringRules`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum ScoringPreset {
+    /// Slower decay, longer drop timer and quicker ranks
+    Casual,
+    #[default]
+    Standard,
+    /// Faster decay and a short drop timer, with bigger rewards for multi-clears
+    Hardcore,
+}
+
+impl ScoringPreset {
+    pub fn rules(&self) -> ScoringRules {
+        match self {
+            ScoringPreset::Casual => ScoringRules {
+                mult_decay_rate: 0.05,
+                drop_timer_max_mult: 4.0,
+                drop_timer_secs: 15.0,
+                unrotated_drop_timer_mult: 0.5,
+                rank_score_scale: 8,
+                rank_score_exponent: 2,
+                clear_mult_exponent: 2.0,
+                rank_boost_secs_per_rank: 8.0,
+            },
+            ScoringPreset::Standard => ScoringRules {
+                mult_decay_rate: 0.1,
+                drop_timer_max_mult: 4.0,
+                drop_timer_secs: 10.0,
+                unrotated_drop_timer_mult: 0.0,
+                rank_score_scale: 10,
+                rank_score_exponent: 2,
+                clear_mult_exponent: 2.0,
+                rank_boost_secs_per_rank: 5.0,
+            },
+            ScoringPreset::Hardcore => ScoringRules {
+                mult_decay_rate: 0.25,
+                drop_timer_max_mult: 6.0,
+                drop_timer_secs: 5.0,
+                unrotated_drop_timer_mult: 0.0,
+                rank_score_scale: 15,
+                rank_score_exponent: 2,
+                clear_mult_exponent: 2.5,
+                rank_boost_secs_per_rank: 3.0,
+            },
+        }
+    }
+}
 
 /// Represents an instance of game scoring
 ///
@@ -10,7 +94,7 @@ pub struct GameScore {
     ///
     /// Is increased based on the following formula of multipliers:
     ///
-    /// `score_delta = 1 * drop_timer_mult * mult * rank_mult (if Some)`
+    /// `score_delta = 1 * (drop_timer_mult + mult + rank_mult (if Some)) * chain`
     score: u64,
     /// The rank the player has achieved.
     /// Starts at 1 and increments on a logarithmic scale.
@@ -32,15 +116,19 @@ pub struct GameScore {
     next_rank: u64,
     /// An active multiplier on score. Decays with time.
     mult: f32,
-    /// A rate modifier for the multiplier to declay.
-    mult_decay_rate: f32,
     /// The drop timer
     drop_timer: DropTimer,
+    /// The current turn is played without rotating, which cuts the drop timer multiplier
+    unrotated: bool,
+    /// The constants scoring is tuned with
+    rules: ScoringRules,
+    /// The chain multiplier. Escalates with every chain clear set off by gravity, and resets once the chain ends.
+    chain: u32,
 }
 
 impl GameScore {
     /// Creates a new game score
-    pub fn new() -> Self {
+    pub fn new(rules: ScoringRules) -> Self {
         Self {
             score: 0,
             rank: 1,
@@ -48,10 +136,12 @@ impl GameScore {
             rank_boost_timer: 0.0,
             rank_boost_timer_max: 0.0,
             rank_buffer: 0,
-            next_rank: Self::next_rank_score(1),
+            next_rank: rules.next_rank_score(1),
             mult: 1.0,
-            mult_decay_rate: 0.1,
-            drop_timer: DropTimer::new(4.0, 10.0),
+            drop_timer: DropTimer::new(rules.drop_timer_max_mult, rules.drop_timer_secs),
+            unrotated: false,
+            chain: 1,
+            rules,
         }
     }
 
@@ -68,15 +158,21 @@ impl GameScore {
         }
 
         // Decay standard multiplier
-        self.mult = (self.mult - (dt_secs * self.mult_decay_rate)).max(1.0);
+        self.mult = (self.mult - (dt_secs * self.rules.mult_decay_rate)).max(1.0);
 
         // Pass time on drop timer
         self.drop_timer.pass_time(dt_secs);
     }
 
-    /// Resets the drop timer
+    /// Resets the drop timer for the next turn
     pub fn reset_drop_timer(&mut self) {
         self.drop_timer.reset();
+        self.unrotated = false;
+    }
+
+    /// Marks the current turn as played without rotating
+    pub fn skip_rotation(&mut self) {
+        self.unrotated = true;
     }
 
     /// Gets the current score
@@ -94,21 +190,32 @@ impl GameScore {
         (self.rank_buffer as f32 / self.next_rank as f32).min(1.0)
     }
 
-    /// Calculates the next rank up score
-    fn next_rank_score(current_rank: u64) -> u64 {
-        10 * current_rank.pow(2)
-    }
-
     /// Increases the current standard multiplier before scoring, which decays over time.
     ///
     /// Multiplier increases happen from clearing rows or columns
     pub fn add_mult(&mut self, total_cleared: u32) {
-        self.mult += (total_cleared as f32).powf(2.0);
+        self.mult += (total_cleared as f32).powf(self.rules.clear_mult_exponent);
+    }
+
+    /// Escalates the chain multiplier for a chain clear
+    pub fn advance_chain(&mut self) {
+        self.chain += 1;
+    }
+
+    /// Ends the current chain
+    pub fn reset_chain(&mut self) {
+        self.chain = 1;
     }
 
     /// Returns the current combined multiplier
     pub fn current_mult(&self) -> f32 {
-        self.drop_timer.mult() + self.mult + self.rank_mult.unwrap_or_default()
+        let drop_timer_mult = if self.unrotated {
+            self.drop_timer.mult() * self.rules.unrotated_drop_timer_mult
+        } else {
+            self.drop_timer.mult()
+        };
+
+        (drop_timer_mult + self.mult + self.rank_mult.unwrap_or_default()) * self.chain as f32
     }
 
     /// Adds score with all the extra multipliers. Points can be gained from dropping blocks or from clearing rows
@@ -120,9 +227,9 @@ impl GameScore {
         self.rank_buffer += score_delta;
 
         if self.rank_buffer >= self.next_rank {
-            self.rank_buffer = self.rank_buffer.saturating_sub(self.next_rank as u64);
+            self.rank_buffer = self.rank_buffer.saturating_sub(self.next_rank);
             self.rank += 1;
-            self.next_rank = Self::next_rank_score(self.rank as u64);
+            self.next_rank = self.rules.next_rank_score(self.rank as u64);
         }
 
         self.score += score_delta;
@@ -142,7 +249,7 @@ impl GameScore {
         if self.rank > 1 && self.rank_mult.is_none() {
             self.rank_mult = Some(self.rank as f32);
             self.rank -= 1;
-            self.rank_boost_timer = self.rank as f32 * 5.0;
+            self.rank_boost_timer = self.rank as f32 * self.rules.rank_boost_secs_per_rank;
             self.rank_boost_timer_max = self.rank_boost_timer;
             true
         } else {
@@ -195,3 +302,71 @@ impl DropTimer {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GameScore, ScoringPreset, ScoringRules};
+
+    #[test]
+    fn verify_rank_curve() {
+        let rules = ScoringRules {
+            rank_score_scale: 3,
+            rank_score_exponent: 3,
+            ..ScoringRules::default()
+        };
+
+        assert_eq!(rules.next_rank_score(1), 3);
+        assert_eq!(rules.next_rank_score(2), 24);
+        assert_eq!(ScoringRules::default().next_rank_score(3), 90);
+    }
+
+    #[test]
+    fn verify_rank_boost_duration() {
+        let mut score = GameScore::new(ScoringPreset::Hardcore.rules());
+        score.update(1000.0);
+
+        // Drop mult is gone, so each point is worth the base mult of 1
+        score.add_score(15);
+        score.add_score(60);
+        assert_eq!(score.rank(), 3);
+
+        assert!(score.rank_boost());
+        // Two ranks left at 3 seconds each
+        assert_eq!(score.current_rank_boost_percentage(), Some(1.0));
+        score.update(3.0);
+        assert_eq!(score.current_rank_boost_percentage(), Some(0.5));
+    }
+
+    #[test]
+    fn verify_skip_rotation() {
+        let rules = ScoringPreset::Casual.rules();
+        let mut score = GameScore::new(rules);
+
+        // Base mult of 1 plus the full drop timer mult
+        assert_eq!(score.current_mult(), 1.0 + rules.drop_timer_max_mult);
+
+        score.skip_rotation();
+        assert_eq!(
+            score.current_mult(),
+            1.0 + rules.drop_timer_max_mult * rules.unrotated_drop_timer_mult
+        );
+
+        // Only lasts for the turn
+        score.reset_drop_timer();
+        assert_eq!(score.current_mult(), 1.0 + rules.drop_timer_max_mult);
+
+        // No drop timer bonus at all on Standard
+        let mut score = GameScore::new(ScoringPreset::Standard.rules());
+        score.skip_rotation();
+        assert_eq!(score.current_mult(), 1.0);
+    }
+
+    #[test]
+    fn verify_presets() {
+        let standard = ScoringPreset::Standard.rules();
+
+        assert_eq!(ScoringRules::default(), standard);
+        assert!(ScoringPreset::Casual.rules().mult_decay_rate < standard.mult_decay_rate);
+        assert!(ScoringPreset::Hardcore.rules().drop_timer_secs < standard.drop_timer_secs);
+    }
+}
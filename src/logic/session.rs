@@ -0,0 +1,1076 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use strum::{EnumCount, IntoEnumIterator};
+
+use super::{
+    board::GameBoard,
+    error::GameError,
+    garbage::GarbageRules,
+    generator::{DropGenerator, DropSpot, GeneratorKind},
+    insertion::InsertionDirection,
+    mode::{GameModeKind, SPRINT_LINES, ULTRA_SECS},
+    puzzle::{PuzzleLevel, PuzzleStatus},
+    score::ScoringRules,
+    tile::{ClearRules, Tile, TileColor, TileSet},
+};
+
+/// Seconds the clock moves in each of its steps. Time only passes in whole steps, so the score lands on
+/// exactly the same values however a game's frames split its time up, and a replay plays out the same as
+/// the game it was recorded from. A power of two, so every step adds up without rounding.
+pub const CLOCK_STEP_SECS: f32 = 1.0 / 128.0;
+
+/// An input a player can apply to a [`GameSession`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameAction {
+    /// Rotates the board counter-clockwise
+    RotateLeft,
+    /// Rotates the board clockwise
+    RotateRight,
+    /// Turns the board upside down
+    Flip,
+    /// Drops the turn into the board as it is, at the cost of some of the drop timer multiplier
+    SkipRotation,
+    /// Shifts the pending drop one slot along its side
+    ShiftUp,
+    /// Shifts the pending drop one slot back along its side
+    ShiftDown,
+    /// Consumes a rank for a scoring boost
+    RankBoost,
+    /// Stashes the pending drop's slot, swapping in the one stashed before. Once per turn
+    Hold,
+    /// Drops the pending tile into the board
+    Drop,
+}
+
+/// An input applied to a [`GameSession`] and the game time it was applied at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimedAction {
+    /// Seconds since the session started
+    pub time: f32,
+    pub action: GameAction,
+}
+
+/// The result of an applied [`GameAction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Rotated,
+    /// The turn will be dropped without rotating
+    Skipped,
+    Shifted,
+    /// Whether a rank was consumed for the boost
+    Boosted(bool),
+    /// Whether the drop's slot was swapped with the held one
+    Held(bool),
+    /// The coordinate the tile was placed at (column, row)
+    Placed(usize, usize),
+    /// The tile had no space, but the turn goes on as another of its drops was or still may be placed
+    Blocked,
+}
+
+/// Everything needed to set up a [`GameSession`] besides its seed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Columns on the board
+    pub width: usize,
+    /// Rows on the board
+    pub height: usize,
+    /// Which tiles are rolled for drops
+    pub tile_set: TileSet,
+    /// How full rows/cols are cleared and scored
+    pub clear_rules: ClearRules,
+    /// The constants scoring is tuned with
+    pub scoring: ScoringRules,
+    /// Tiles dropped after each rotation, each into its own slot
+    pub drops_per_turn: usize,
+    /// Turns rolled ahead of the current one so they can be previewed
+    #[serde(default)]
+    pub preview_turns: usize,
+    /// How drop slots are rolled
+    #[serde(default)]
+    pub generator: GeneratorKind,
+    /// How the game ends
+    #[serde(default)]
+    pub mode: GameModeKind,
+    /// The level being played, which sets the starting board and every drop
+    #[serde(default)]
+    pub puzzle: Option<PuzzleLevel>,
+    /// How often the game pushes garbage onto the board, if at all
+    #[serde(default)]
+    pub garbage: Option<GarbageRules>,
+}
+
+impl SessionConfig {
+    /// Classic single color rules on a board `width` columns wide and `height` rows tall
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            tile_set: TileSet::default(),
+            clear_rules: ClearRules::default(),
+            scoring: ScoringRules::default(),
+            drops_per_turn: 1,
+            preview_turns: 0,
+            generator: GeneratorKind::default(),
+            mode: GameModeKind::default(),
+            puzzle: None,
+            garbage: None,
+        }
+    }
+}
+
+/// A complete game of Quatrix without any rendering attached.
+///
+/// Owns the board, the score clock and the drop generator. Drops are rolled from a
+/// seeded RNG, so two sessions with the same seed and the same inputs play out identically.
+#[derive(Debug, Clone)]
+pub struct GameSession {
+    /// How the session was set up
+    config: SessionConfig,
+    /// The board being played
+    board: GameBoard,
+    /// Seed the drop generator was created with
+    seed: u64,
+    /// Source of randomness for drops
+    rng: StdRng,
+    /// Picks the slot each drop falls from
+    generator: Box<dyn DropGenerator>,
+    /// Next drop placement
+    next_drop: usize,
+    /// Tile that will be dropped next
+    next_tile: Tile,
+    /// Placements and tiles for the rest of the turn's drops after the next one
+    queued_drops: VecDeque<(usize, Tile)>,
+    /// Turns rolled ahead of the current one, the soonest first. They're only pinned to slots once
+    /// they start, as the board may have turned in the meantime.
+    upcoming: VecDeque<Vec<(DropSpot, Tile)>>,
+    /// Drops already attempted this turn
+    turn_drops: usize,
+    /// Whether any drop this turn has been placed
+    turn_placed: bool,
+    /// Offset from next drop placement, clamped to a single slot either way
+    offset: i8,
+    /// Slot stashed by [`GameAction::Hold`]
+    held: Option<usize>,
+    /// Whether this turn has already used its hold
+    hold_used: bool,
+    /// Turns that have finished dropping
+    turns_played: usize,
+    /// Turns rolled so far, including the current one and those rolled ahead
+    turns_rolled: usize,
+    /// Turns played since garbage was last injected
+    turns_since_garbage: u32,
+    /// Seconds of game time passed through [`GameSession::tick`], always a whole number of clock steps
+    elapsed: f32,
+    /// Time passed through [`GameSession::tick`] that doesn't make up a whole clock step yet
+    pending: f32,
+    /// Every input successfully applied to the session, in order
+    history: Vec<TimedAction>,
+}
+
+impl GameSession {
+    /// Creates a session from a config with row clearing enabled
+    pub fn new(config: SessionConfig, seed: u64) -> Self {
+        let board = match &config.puzzle {
+            Some(level) => level.board(),
+            None => GameBoard::new(config.width, config.height),
+        };
+
+        let mut session = Self {
+            board: board
+                .with_rows_clearing()
+                .with_clear_rules(config.clear_rules)
+                .with_scoring_rules(config.scoring),
+            generator: config.generator.build(config.width, config.height),
+            config,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            next_drop: 0,
+            next_tile: Tile::FILLED,
+            queued_drops: VecDeque::new(),
+            upcoming: VecDeque::new(),
+            turn_drops: 0,
+            turn_placed: false,
+            offset: 0,
+            held: None,
+            hold_used: false,
+            turns_played: 0,
+            turns_rolled: 0,
+            turns_since_garbage: 0,
+            elapsed: 0.0,
+            pending: 0.0,
+            history: Vec::new(),
+        };
+
+        session.roll_turn();
+        session
+    }
+
+    /// The board being played
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    /// How the session was set up
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// The seed this session was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The rolled drop placement, before any offset is applied
+    pub fn next_drop(&self) -> usize {
+        self.next_drop
+    }
+
+    /// The tile that will be dropped next
+    pub fn next_tile(&self) -> Tile {
+        self.next_tile
+    }
+
+    /// Every drop left this turn as its placement with the offset applied and its tile, the next one first
+    pub fn turn_drops(&self) -> Vec<(usize, Tile)> {
+        std::iter::once((self.next_drop, self.next_tile))
+            .chain(self.queued_drops.iter().copied())
+            .map(|(slot, tile)| (self.offset_slot(slot, self.offset), tile))
+            .collect()
+    }
+
+    /// The placements and tiles of the turns after this one, the soonest first.
+    /// Holds [`SessionConfig::preview_turns`] turns, placed as the board is turned now.
+    pub fn upcoming(&self) -> Vec<Vec<(usize, Tile)>> {
+        self.upcoming
+            .iter()
+            .map(|drops| self.place_spots(drops))
+            .collect()
+    }
+
+    /// The slot stashed by [`GameAction::Hold`], if any
+    pub fn held(&self) -> Option<usize> {
+        self.held
+    }
+
+    /// Whether the pending drop can be held, which is once per turn before any of it has dropped
+    pub fn can_hold(&self) -> bool {
+        !self.hold_used && !self.mid_turn()
+    }
+
+    /// Whether some of this turn's drops have happened and the rest are still to come
+    pub fn mid_turn(&self) -> bool {
+        self.turn_drops > 0
+    }
+
+    /// Turns that have finished dropping
+    pub fn turns_played(&self) -> usize {
+        self.turns_played
+    }
+
+    /// How the puzzle being played is going, or `None` outside of puzzles
+    pub fn puzzle_status(&self) -> Option<PuzzleStatus> {
+        let level = self.config.puzzle.as_ref()?;
+
+        Some(if level.goal().is_met(&self.board) {
+            PuzzleStatus::Solved
+        } else if self.turns_played >= level.move_limit() {
+            PuzzleStatus::Failed
+        } else {
+            PuzzleStatus::InProgress
+        })
+    }
+
+    /// Whether the game has reached its end condition: a puzzle that's solved or out of moves,
+    /// a finished sprint or an ultra game out of time. Running out of space is checked separately.
+    pub fn finished(&self) -> bool {
+        if let Some(status) = self.puzzle_status() {
+            return status != PuzzleStatus::InProgress;
+        }
+
+        match self.config.mode {
+            GameModeKind::Endless => false,
+            GameModeKind::Sprint => self.board.lines_cleared() >= SPRINT_LINES,
+            GameModeKind::Ultra => self.elapsed >= ULTRA_SECS,
+        }
+    }
+
+    /// Seconds left on the clock in modes played against one
+    pub fn time_left(&self) -> Option<f32> {
+        (self.config.mode == GameModeKind::Ultra).then(|| (ULTRA_SECS - self.elapsed).max(0.0))
+    }
+
+    /// Seconds of game time that have passed
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Every input successfully applied to the session, in order
+    pub fn history(&self) -> &[TimedAction] {
+        &self.history
+    }
+
+    /// Calculated the drop location given the next drop and the offset
+    pub fn drop(&self) -> usize {
+        self.offset_slot(self.next_drop, self.offset)
+    }
+
+    fn offset_slot(&self, slot: usize, offset: i8) -> usize {
+        let max_index = self.board.slots().saturating_sub(1);
+        if slot == 0 && offset == -1 {
+            max_index
+        } else if slot == max_index && offset == 1 {
+            0
+        } else {
+            slot.saturating_add_signed(offset.into())
+        }
+    }
+
+    /// Whether any of the turn's pending drops can still be placed with any shift and rotation.
+    /// Once this is false the game is lost, whatever the player does.
+    pub fn has_legal_move(&self) -> bool {
+        let held = self.held.filter(|_| self.can_hold());
+
+        let slots = std::iter::once(self.next_drop)
+            .chain(self.queued_drops.iter().map(|(slot, _)| *slot))
+            .chain(held)
+            .flat_map(|slot| (-1..=1).map(move |offset| (slot, offset)))
+            .map(|(slot, offset)| self.offset_slot(slot, offset))
+            .collect::<Vec<_>>();
+
+        // Drops fall without rotating (no quarter turns), after a rotation to the right (one), a flip (two) or left (three)
+        self.board.has_legal_move(&[0, 1, 2, 3], &slots)
+    }
+
+    /// Passes game time on the score clock, a whole [`CLOCK_STEP_SECS`] at a time
+    pub fn tick(&mut self, dt_secs: f32) {
+        self.pending += dt_secs;
+
+        while self.pending >= CLOCK_STEP_SECS {
+            self.pending -= CLOCK_STEP_SECS;
+            self.elapsed += CLOCK_STEP_SECS;
+            self.board.score_mut().update(CLOCK_STEP_SECS);
+        }
+    }
+
+    /// Applies a single player input to the game
+    pub fn step(&mut self, action: GameAction) -> Result<StepOutcome, GameError> {
+        let outcome = self.apply(action)?;
+
+        self.history.push(TimedAction {
+            time: self.elapsed,
+            action,
+        });
+
+        Ok(outcome)
+    }
+
+    /// Pushes in garbage lines sent by an opponent, falling back to blockers for any that don't fit.
+    /// Received garbage isn't an input, so a session that's been sent any can't be replayed from its history.
+    pub fn receive_garbage(&mut self, lines: u32) {
+        let rules = self.config.garbage.unwrap_or_default();
+
+        for _ in 0..lines {
+            rules.push_line(&mut self.board, &mut self.rng);
+        }
+    }
+
+    fn apply(&mut self, action: GameAction) -> Result<StepOutcome, GameError> {
+        match action {
+            GameAction::RotateLeft => {
+                self.board.rotate_left();
+                Ok(StepOutcome::Rotated)
+            }
+            GameAction::RotateRight => {
+                self.board.rotate_right();
+                Ok(StepOutcome::Rotated)
+            }
+            GameAction::Flip => {
+                self.board.flip();
+                Ok(StepOutcome::Rotated)
+            }
+            GameAction::SkipRotation => {
+                self.board.score_mut().skip_rotation();
+                Ok(StepOutcome::Skipped)
+            }
+            GameAction::ShiftUp => {
+                self.shift(1);
+                Ok(StepOutcome::Shifted)
+            }
+            GameAction::ShiftDown => {
+                self.shift(-1);
+                Ok(StepOutcome::Shifted)
+            }
+            GameAction::RankBoost => Ok(StepOutcome::Boosted(self.board.score_mut().rank_boost())),
+            GameAction::Hold => Ok(StepOutcome::Held(self.hold())),
+            GameAction::Drop => {
+                // A turn only fails once every one of its drops has
+                let outcome = match self.board.place_tile(self.drop(), self.next_tile) {
+                    Ok((x, y)) => {
+                        self.turn_placed = true;
+                        StepOutcome::Placed(x, y)
+                    }
+                    Err(err) if self.queued_drops.is_empty() && !self.turn_placed => {
+                        return Err(err)
+                    }
+                    Err(_) => StepOutcome::Blocked,
+                };
+
+                self.next_turn_drop();
+                Ok(outcome)
+            }
+        }
+    }
+
+    /// Moves the offset along the side the next drop is on, so that positive is
+    /// always up or right from the player's point of view.
+    fn shift(&mut self, offset: i8) {
+        let direction =
+            InsertionDirection::for_board_insertion(self.board.board(), self.next_drop).ok();
+
+        let oriented_offset = match direction {
+            Some(InsertionDirection::FromLeft | InsertionDirection::FromTop) | None => offset,
+            Some(InsertionDirection::FromRight | InsertionDirection::FromBottom) => -offset,
+        };
+
+        self.offset = (self.offset + oriented_offset).clamp(-1, 1);
+    }
+
+    /// Stashes the pending drop's slot with its offset applied. The held slot is swapped in for it,
+    /// or a new one is rolled if nothing was held yet. Returns whether the slots were swapped.
+    fn hold(&mut self) -> bool {
+        if !self.can_hold() {
+            return false;
+        }
+
+        let current = self.drop();
+        let taken =
+            |slot: usize| slot == current || self.queued_drops.iter().any(|(x, _)| *x == slot);
+
+        let next = match self.held {
+            // Each of the turn's drops needs its own slot
+            Some(held) if taken(held) => return false,
+            Some(held) => held,
+            None => {
+                let free = (0..self.board.slots())
+                    .filter(|&slot| !taken(slot))
+                    .collect::<Vec<_>>();
+                free[self.rng.gen_range(0..free.len())]
+            }
+        };
+
+        self.held = Some(current);
+        self.next_drop = next;
+        self.offset = 0;
+        self.hold_used = true;
+
+        true
+    }
+
+    /// Moves on to the turn's next drop, or starts a new turn once they've all dropped
+    fn next_turn_drop(&mut self) {
+        if let Some((slot, tile)) = self.queued_drops.pop_front() {
+            self.next_drop = slot;
+            self.next_tile = tile;
+            self.turn_drops += 1;
+        } else {
+            // The drop timer covers the whole turn, so every tile in it scores the same
+            self.board.score_mut().reset_drop_timer();
+            self.turns_played += 1;
+            self.inject_garbage();
+            self.roll_turn();
+        }
+    }
+
+    /// Counts down to the next garbage injection, which comes sooner the higher the rank
+    fn inject_garbage(&mut self) {
+        let Some(rules) = self.config.garbage else {
+            return;
+        };
+
+        self.turns_since_garbage += 1;
+        if self.turns_since_garbage < rules.interval(self.board.score().rank()) {
+            return;
+        }
+
+        self.turns_since_garbage = 0;
+        rules.inject(&mut self.board, &mut self.rng);
+    }
+
+    /// Starts the next rolled turn, topping up the turns rolled ahead, and clears the offset
+    fn roll_turn(&mut self) {
+        while self.upcoming.len() <= self.config.preview_turns {
+            let drops = self.roll_drops();
+            self.upcoming.push_back(drops);
+        }
+
+        let drops = self.upcoming.pop_front().unwrap();
+        let mut drops = self.place_spots(&drops).into_iter();
+
+        (self.next_drop, self.next_tile) = drops.next().unwrap();
+        self.queued_drops = drops.collect();
+        self.turn_drops = 0;
+        self.turn_placed = false;
+        self.offset = 0;
+        self.hold_used = false;
+    }
+
+    /// Rolls spots and tiles for a turn of drops, each in a different slot as the board is turned now
+    fn roll_drops(&mut self) -> Vec<(DropSpot, Tile)> {
+        self.turns_rolled += 1;
+
+        // Puzzles drop a fixed sequence instead
+        if let Some(level) = &self.config.puzzle {
+            let (slot, tile) = level.drop(self.turns_rolled - 1);
+            return vec![(DropSpot::Slot(slot), tile)];
+        }
+
+        let (width, height) = (self.board.width(), self.board.height());
+        let drops = self.config.drops_per_turn.clamp(1, self.board.slots());
+        let mut rolled: Vec<(DropSpot, Tile)> = Vec::with_capacity(drops);
+
+        while rolled.len() < drops {
+            let spot = self.generator.next_spot(&mut self.rng);
+            let slot = spot.slot(width, height);
+            if rolled.iter().any(|(x, _)| x.slot(width, height) == slot) {
+                continue;
+            }
+
+            let tile = self.roll_tile();
+            rolled.push((spot, tile));
+        }
+
+        rolled
+    }
+
+    /// Pins a turn's spots to slots on the board as it's turned now. Spots rolled apart can share a
+    /// slot once the board has turned, in which case the later drop moves clockwise to the next free one.
+    fn place_spots(&self, drops: &[(DropSpot, Tile)]) -> Vec<(usize, Tile)> {
+        let (width, height) = (self.board.width(), self.board.height());
+        let slots = self.board.slots();
+        let mut placed: Vec<(usize, Tile)> = Vec::with_capacity(drops.len());
+
+        for (spot, tile) in drops {
+            let start = spot.slot(width, height);
+            let slot = (0..slots)
+                .map(|step| (start + step) % slots)
+                .find(|slot| placed.iter().all(|(x, _)| x != slot))
+                .unwrap_or(start);
+
+            placed.push((slot, *tile));
+        }
+
+        placed
+    }
+
+    fn roll_tile(&mut self) -> Tile {
+        let tile_set = self.config.tile_set;
+
+        if tile_set.special_chance > 0.0 && self.rng.gen_bool(tile_set.special_chance.min(1.0)) {
+            return if self.rng.gen_bool(0.5) {
+                Tile::Wild
+            } else {
+                Tile::Bomb
+            };
+        }
+
+        let colors = tile_set.colors.clamp(1, TileColor::COUNT);
+        TileColor::iter()
+            .nth(self.rng.gen_range(0..colors))
+            .map(Tile::Color)
+            .unwrap_or(Tile::FILLED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use strum::IntoEnumIterator;
+
+    use crate::logic::{
+        ai::{autoplay, Rotation, Solver},
+        error::GameError,
+        garbage::GarbageRules,
+        generator::GeneratorKind,
+        mode::{GameModeKind, SPRINT_LINES, ULTRA_SECS},
+        tile::{Tile, TileSet},
+    };
+
+    use super::{GameAction, GameSession, SessionConfig, StepOutcome, TimedAction};
+
+    /// Plays rotations and drops until the game ends or the move limit is reached
+    fn play(session: &mut GameSession, moves: usize) -> Vec<usize> {
+        let mut drops = Vec::new();
+
+        for turn in 0..moves {
+            let rotation = if turn % 3 == 0 {
+                GameAction::RotateLeft
+            } else {
+                GameAction::RotateRight
+            };
+
+            session.step(rotation).unwrap();
+            session.tick(0.5);
+
+            let drop = session.drop();
+            if session.step(GameAction::Drop).is_err() {
+                break;
+            }
+            drops.push(drop);
+        }
+
+        drops
+    }
+
+    #[test]
+    fn verify_drop() {
+        let mut state = GameSession::new(SessionConfig::new(4, 4), 0);
+        state.next_drop = 0;
+
+        // Min Wrapping
+        state.offset = -1;
+        assert_eq!(state.drop(), 15);
+
+        // Max Wrapping
+        state.next_drop = 15;
+        state.offset = 1;
+        assert_eq!(state.drop(), 0);
+
+        [-1, 0, 1].into_iter().for_each(|offset| {
+            state.offset = offset;
+
+            (0..15).for_each(|index| {
+                if (index == 0 && offset == -1) || (index == 15 && offset == 1) {
+                    // Skip Edge
+                } else {
+                    state.next_drop = index;
+                    assert_eq!(state.drop(), (index as i32 + offset as i32) as usize);
+                }
+            });
+        });
+    }
+
+    #[test]
+    fn verify_seeded_determinism() {
+        let mut first = GameSession::new(SessionConfig::new(4, 4), 1234);
+        let mut second = GameSession::new(SessionConfig::new(4, 4), 1234);
+
+        assert_eq!(play(&mut first, 50), play(&mut second, 50));
+        assert_eq!(first.board().board(), second.board().board());
+        assert_eq!(
+            first.board().score().score(),
+            second.board().score().score()
+        );
+    }
+
+    #[test]
+    fn verify_history() {
+        let mut session = GameSession::new(SessionConfig::new(3, 3), 7);
+
+        session.step(GameAction::RotateLeft).unwrap();
+        session.tick(1.5);
+        session.step(GameAction::Drop).unwrap();
+
+        assert_eq!(
+            session.history(),
+            &[
+                TimedAction {
+                    time: 0.0,
+                    action: GameAction::RotateLeft
+                },
+                TimedAction {
+                    time: 1.5,
+                    action: GameAction::Drop
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_rectangular_drops() {
+        let mut session = GameSession::new(SessionConfig::new(5, 2), 3);
+
+        for _ in 0..50 {
+            assert!(session.next_drop() < 14);
+            session.step(GameAction::RotateRight).unwrap();
+            if session.step(GameAction::Drop).is_err() {
+                break;
+            }
+        }
+
+        // Wrapping uses the full ring of slots
+        session.next_drop = 0;
+        session.offset = -1;
+        assert_eq!(session.drop(), 13);
+    }
+
+    #[test]
+    fn verify_seeds_differ() {
+        let drops = (0..8)
+            .map(|seed| play(&mut GameSession::new(SessionConfig::new(4, 4), seed), 10))
+            .collect::<Vec<_>>();
+
+        assert!(drops.iter().any(|x| x != &drops[0]));
+    }
+
+    #[test]
+    fn verify_shift_is_oriented() {
+        let mut session = GameSession::new(SessionConfig::new(4, 4), 0);
+
+        // From the top, shifting up moves right along the slots
+        session.next_drop = 1;
+        session.step(GameAction::ShiftUp).unwrap();
+        assert_eq!(session.drop(), 2);
+
+        // Clamped to a single slot
+        session.step(GameAction::ShiftUp).unwrap();
+        assert_eq!(session.drop(), 2);
+
+        // From the bottom, shifting up moves back along the slots
+        session.next_drop = 9;
+        session.offset = 0;
+        session.step(GameAction::ShiftUp).unwrap();
+        assert_eq!(session.drop(), 8);
+    }
+
+    #[test]
+    fn verify_drop_rolls_next() {
+        let mut session = GameSession::new(SessionConfig::new(3, 3), 42);
+        session.step(GameAction::ShiftDown).unwrap();
+
+        let drop = session.drop();
+        let outcome = session.step(GameAction::Drop).unwrap();
+
+        assert!(matches!(outcome, StepOutcome::Placed(_, _)));
+        assert_eq!(session.offset, 0);
+        assert_eq!(
+            session
+                .board()
+                .board()
+                .iter()
+                .filter(|x| x.is_filled())
+                .count(),
+            1
+        );
+        assert!(drop < 12);
+    }
+
+    #[test]
+    fn verify_no_space() {
+        let mut session = GameSession::new(SessionConfig::new(3, 3), 7);
+
+        for slot in [10, 6] {
+            session.next_drop = slot;
+            session.step(GameAction::Drop).unwrap();
+        }
+        session.next_drop = 6;
+
+        assert_eq!(session.step(GameAction::Drop), Err(GameError::NoSpace));
+        // Failed inputs are not recorded
+        assert_eq!(session.history().len(), 2);
+    }
+
+    #[test]
+    fn verify_tile_set() {
+        let single = GameSession::new(SessionConfig::new(4, 4), 11);
+        assert_eq!(single.next_tile(), Tile::FILLED);
+
+        let mut config = SessionConfig::new(4, 4);
+        config.tile_set = TileSet {
+            colors: 4,
+            special_chance: 0.2,
+        };
+        let mut session = GameSession::new(config, 11);
+
+        let mut rolled = Vec::new();
+        for _ in 0..60 {
+            rolled.push(session.next_tile());
+            session.roll_turn();
+        }
+
+        assert!(rolled.iter().all(|tile| tile.is_filled()));
+        assert!(rolled.contains(&Tile::Wild));
+        assert!(rolled.contains(&Tile::Bomb));
+        assert!(rolled
+            .iter()
+            .filter_map(|tile| tile.color())
+            .any(|color| color != Tile::FILLED.color().unwrap()));
+
+        // Placed tiles keep their type
+        let tile = session.next_tile();
+        let Ok(StepOutcome::Placed(_, _)) = session.step(GameAction::Drop) else {
+            panic!("Drop on an empty board failed");
+        };
+        assert!(session.board().board().iter().any(|x| *x == tile));
+    }
+
+    #[test]
+    fn verify_has_legal_move() {
+        for seed in 0..10 {
+            let mut session = GameSession::new(SessionConfig::new(5, 4), seed);
+
+            loop {
+                // Try every shift and rotation the player could make
+                let legal = [
+                    GameAction::RotateLeft,
+                    GameAction::RotateRight,
+                    GameAction::Flip,
+                    GameAction::SkipRotation,
+                ]
+                .into_iter()
+                .flat_map(|rotation| [-1, 0, 1].map(|offset| (rotation, offset)))
+                .find(|&(rotation, offset)| {
+                    let mut attempt = session.clone();
+                    attempt.offset = offset;
+                    attempt.step(rotation).unwrap();
+                    attempt.step(GameAction::Drop).is_ok()
+                });
+
+                assert_eq!(session.has_legal_move(), legal.is_some(), "Seed {seed}");
+
+                // Plays on until there's nothing left to try, so both answers are checked in every game
+                let Some((rotation, offset)) = legal else {
+                    break;
+                };
+
+                session.offset = offset;
+                session.step(rotation).unwrap();
+                session.step(GameAction::Drop).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn verify_upcoming() {
+        let mut config = SessionConfig::new(4, 4);
+        config.drops_per_turn = 2;
+        let mut unpreviewed = GameSession::new(config.clone(), 9);
+
+        config.preview_turns = 3;
+        let mut session = GameSession::new(config, 9);
+        assert_eq!(session.upcoming().len(), 3);
+        assert!(unpreviewed.upcoming().is_empty());
+
+        for _ in 0..10 {
+            let next = session.upcoming()[0].clone();
+
+            // Rolling ahead doesn't change what's dropped
+            assert_eq!(session.turn_drops(), unpreviewed.turn_drops());
+
+            for session in [&mut session, &mut unpreviewed] {
+                session.step(GameAction::RotateRight).unwrap();
+                session.step(GameAction::Drop).unwrap();
+                session.step(GameAction::Drop).unwrap();
+            }
+
+            assert_eq!(session.turn_drops(), next);
+            assert_eq!(session.upcoming().len(), 3);
+        }
+    }
+
+    #[test]
+    fn verify_upcoming_sides_follow_rotation() {
+        let mut config = SessionConfig::new(3, 6);
+        config.generator = GeneratorKind::Bag;
+        config.preview_turns = 4;
+        let mut session = GameSession::new(config, 2);
+
+        // Which side of the board, clockwise from the top, each upcoming drop falls from
+        let sides = |session: &GameSession| {
+            let (width, height) = (session.board().width(), session.board().height());
+            let side = |slot: usize| {
+                [width, width + height, 2 * width + height].partition_point(|&end| end <= slot)
+            };
+
+            session
+                .upcoming()
+                .iter()
+                .map(|drops| side(drops[0].0))
+                .collect::<Vec<_>>()
+        };
+
+        let before = sides(&session);
+        session.step(GameAction::RotateRight).unwrap();
+        assert_eq!(session.board().width(), 6);
+
+        // Turning the board swaps its sides' lengths, but not which sides the bag dealt
+        assert_eq!(sides(&session), before);
+    }
+
+    #[test]
+    fn verify_mode_end() {
+        let mut config = SessionConfig::new(4, 4);
+        let endless = GameSession::new(config.clone(), 0);
+        assert!(!endless.finished());
+        assert_eq!(endless.time_left(), None);
+
+        config.mode = GameModeKind::Ultra;
+        let mut ultra = GameSession::new(config.clone(), 0);
+        ultra.tick(ULTRA_SECS - 1.0);
+        assert!(!ultra.finished());
+        assert_eq!(ultra.time_left(), Some(1.0));
+        ultra.tick(2.0);
+        assert!(ultra.finished());
+        assert_eq!(ultra.time_left(), Some(0.0));
+
+        // Sprints end on the line that reaches the target, however long that takes
+        config.mode = GameModeKind::Sprint;
+        let mut sprint = GameSession::new(config, 0);
+        let solver = Solver::default();
+        let options = Rotation::iter().collect::<Vec<_>>();
+        while !sprint.finished() {
+            let played = autoplay(&mut sprint, &solver, &options, 1);
+            assert_eq!(
+                played,
+                1,
+                "Lost after {} lines",
+                sprint.board().lines_cleared()
+            );
+        }
+        assert!(sprint.board().lines_cleared() >= SPRINT_LINES);
+    }
+
+    #[test]
+    fn verify_garbage() {
+        let garbage = |session: &GameSession| {
+            session
+                .board()
+                .board()
+                .iter()
+                .filter(|x| x.is_garbage())
+                .count()
+        };
+        let solver = Solver::default();
+        let options = Rotation::iter().collect::<Vec<_>>();
+
+        // Never injected unless enabled
+        let mut session = GameSession::new(SessionConfig::new(5, 5), 0);
+        assert_eq!(autoplay(&mut session, &solver, &options, 12), 12);
+        assert_eq!(garbage(&session), 0);
+
+        let mut config = SessionConfig::new(5, 5);
+        config.garbage = Some(GarbageRules {
+            base_interval: 3,
+            turns_per_rank: 0,
+            line_chance: 0.0,
+            ..Default::default()
+        });
+
+        let mut session = GameSession::new(config.clone(), 0);
+        assert_eq!(autoplay(&mut session, &solver, &options, 2), 2);
+        assert_eq!(garbage(&session), 0);
+        assert_eq!(autoplay(&mut session, &solver, &options, 1), 1);
+        assert_eq!(garbage(&session), 1);
+
+        // Injections come from the session's seed, so replays see the same garbage
+        let mut replay = GameSession::new(config, 0);
+        assert_eq!(autoplay(&mut replay, &solver, &options, 3), 3);
+        assert_eq!(replay.board().board(), session.board().board());
+
+        // Garbage sent by an opponent arrives whether or not injections are enabled
+        let mut session = GameSession::new(SessionConfig::new(5, 5), 0);
+        session.receive_garbage(0);
+        assert_eq!(garbage(&session), 0);
+        session.receive_garbage(1);
+        assert_eq!(garbage(&session), 4);
+        assert!(session.history().is_empty());
+    }
+
+    #[test]
+    fn verify_hold() {
+        let mut session = GameSession::new(SessionConfig::new(4, 4), 3);
+        assert_eq!(session.held(), None);
+
+        // The first hold stashes the slot and rolls a new one
+        session.step(GameAction::ShiftUp).unwrap();
+        let first = session.drop();
+        assert_eq!(session.step(GameAction::Hold), Ok(StepOutcome::Held(true)));
+        assert_eq!(session.held(), Some(first));
+        assert_ne!(session.drop(), first);
+
+        // Only once per turn
+        let second = session.drop();
+        assert_eq!(session.step(GameAction::Hold), Ok(StepOutcome::Held(false)));
+        assert_eq!(session.drop(), second);
+
+        session.step(GameAction::RotateRight).unwrap();
+        session.step(GameAction::Drop).unwrap();
+        assert!(session.can_hold());
+
+        // Later turns swap with the held slot
+        let third = session.drop();
+        assert_eq!(session.step(GameAction::Hold), Ok(StepOutcome::Held(true)));
+        assert_eq!(session.drop(), first);
+        assert_eq!(session.held(), Some(third));
+    }
+
+    #[test]
+    fn verify_multi_drop_turn() {
+        let mut config = SessionConfig::new(4, 4);
+        config.drops_per_turn = 3;
+        let mut session = GameSession::new(config, 5);
+
+        let drops = session.turn_drops();
+        assert_eq!(drops.len(), 3);
+        assert!(drops
+            .iter()
+            .all(|(slot, _)| drops.iter().filter(|(x, _)| x == slot).count() == 1));
+
+        session.step(GameAction::RotateRight).unwrap();
+        for left in (0..3).rev() {
+            assert!(matches!(
+                session.step(GameAction::Drop),
+                Ok(StepOutcome::Placed(_, _))
+            ));
+            assert_eq!(session.mid_turn(), left > 0);
+        }
+
+        // A new turn is rolled once every drop is done
+        assert_eq!(session.turn_drops().len(), 3);
+        assert_eq!(
+            session
+                .board()
+                .board()
+                .iter()
+                .filter(|x| x.is_filled())
+                .count(),
+            3
+        );
+        assert_eq!(session.history().len(), 4);
+    }
+
+    #[test]
+    fn verify_turn_fails_only_if_every_drop_fails() {
+        let mut config = SessionConfig::new(3, 3);
+        config.drops_per_turn = 2;
+        let mut session = GameSession::new(config, 7);
+
+        for slot in [10, 6] {
+            session.next_drop = slot;
+            session.queued_drops.clear();
+            session.step(GameAction::Drop).unwrap();
+        }
+
+        // Every drop failing fails the turn
+        let mut failing = session.clone();
+        failing.next_drop = 6;
+        failing.queued_drops = VecDeque::from([(6, Tile::FILLED)]);
+        assert_eq!(failing.step(GameAction::Drop), Ok(StepOutcome::Blocked));
+        assert_eq!(failing.step(GameAction::Drop), Err(GameError::NoSpace));
+
+        // Slot 6 is full, but the turn goes on to its other drop
+        session.next_drop = 6;
+        session.queued_drops = VecDeque::from([(0, Tile::FILLED)]);
+        assert_eq!(session.step(GameAction::Drop), Ok(StepOutcome::Blocked));
+        assert!(session.mid_turn());
+        assert!(matches!(
+            session.step(GameAction::Drop),
+            Ok(StepOutcome::Placed(_, _))
+        ));
+        assert!(!session.mid_turn());
+        // Blocked drops still move the turn along, so they're recorded
+        assert_eq!(session.history().len(), 4);
+    }
+}
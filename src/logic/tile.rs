@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use strum::{EnumCount, EnumIter};
+
+/// The colors a regular tile can be
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter, EnumCount, Serialize, Deserialize,
+)]
+pub enum TileColor {
+    #[default]
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+/// A single cell on the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Tile {
+    #[default]
+    Empty,
+    /// A regular tile
+    Color(TileColor),
+    /// Matches any color when checking if a line is a single color
+    Wild,
+    /// When cleared, also clears the line crossing through it
+    Bomb,
+    /// Garbage that's never cleared, only ever pushed around
+    Stone,
+    /// Garbage that takes two clears, cracking on the first
+    Armored,
+    /// Garbage that's cleared the next time its line is
+    Cracked,
+}
+
+impl Tile {
+    /// The tile placed when no color is given
+    pub const FILLED: Tile = Tile::Color(TileColor::Red);
+
+    /// Whether the cell is taken up by a tile
+    pub fn is_filled(&self) -> bool {
+        *self != Tile::Empty
+    }
+
+    /// Whether the tile was put on the board by the game rather than dropped by the player
+    pub fn is_garbage(&self) -> bool {
+        matches!(self, Tile::Stone | Tile::Armored | Tile::Cracked)
+    }
+
+    /// What's left of the tile once a line through it is cleared
+    pub fn cleared(&self) -> Tile {
+        match self {
+            Tile::Stone => Tile::Stone,
+            Tile::Armored => Tile::Cracked,
+            _ => Tile::Empty,
+        }
+    }
+
+    /// Reads a tile written with its [`Display`] character
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            '.' => Some(Tile::Empty),
+            'R' => Some(Tile::Color(TileColor::Red)),
+            'G' => Some(Tile::Color(TileColor::Green)),
+            'B' => Some(Tile::Color(TileColor::Blue)),
+            'Y' => Some(Tile::Color(TileColor::Yellow)),
+            'W' => Some(Tile::Wild),
+            '*' => Some(Tile::Bomb),
+            '#' => Some(Tile::Stone),
+            '@' => Some(Tile::Armored),
+            '%' => Some(Tile::Cracked),
+            _ => None,
+        }
+    }
+
+    /// The color of the tile, if it has one
+    pub fn color(&self) -> Option<TileColor> {
+        match self {
+            Tile::Color(color) => Some(*color),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Tile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tile::Empty => write!(f, "."),
+            Tile::Color(TileColor::Red) => write!(f, "R"),
+            Tile::Color(TileColor::Green) => write!(f, "G"),
+            Tile::Color(TileColor::Blue) => write!(f, "B"),
+            Tile::Color(TileColor::Yellow) => write!(f, "Y"),
+            Tile::Wild => write!(f, "W"),
+            Tile::Bomb => write!(f, "*"),
+            Tile::Stone => write!(f, "#"),
+            Tile::Armored => write!(f, "@"),
+            Tile::Cracked => write!(f, "%"),
+        }
+    }
+}
+
+/// Which tiles are rolled for drops
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TileSet {
+    /// Number of colors in play, from 1 up to every [`TileColor`]
+    pub colors: usize,
+    /// Chance from 0 to 1 for a drop to be a special tile instead of a color
+    pub special_chance: f64,
+}
+
+impl Default for TileSet {
+    fn default() -> Self {
+        Self {
+            colors: 1,
+            special_chance: 0.0,
+        }
+    }
+}
+
+/// How full lines are cleared and scored
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClearRules {
+    /// Points for each tile in a cleared line of a single color are multiplied by this
+    pub single_color_bonus: u32,
+    /// Whether full lines of mixed colors clear, or only single color lines do
+    pub mixed_lines_clear: bool,
+    /// Whether tiles slide away from the insertion side after a clear, possibly setting off chain clears
+    pub gravity: bool,
+}
+
+impl Default for ClearRules {
+    fn default() -> Self {
+        Self {
+            single_color_bonus: 1,
+            mixed_lines_clear: true,
+            gravity: false,
+        }
+    }
+}
+
+/// Whether every colored tile in a line shares a color. Special tiles and garbage match any color.
+pub fn is_single_color<'a>(line: impl IntoIterator<Item = &'a Tile>) -> bool {
+    let mut colors = line.into_iter().filter_map(|tile| tile.color());
+
+    match colors.next() {
+        Some(first) => colors.all(|color| color == first),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_single_color, Tile, TileColor};
+
+    #[test]
+    fn verify_single_color() {
+        let red = Tile::Color(TileColor::Red);
+        let blue = Tile::Color(TileColor::Blue);
+
+        assert!(is_single_color(&[red, red, red]));
+        assert!(is_single_color(&[red, Tile::Wild, red]));
+        assert!(is_single_color(&[Tile::Wild, Tile::Bomb]));
+        assert!(!is_single_color(&[red, blue, red]));
+        assert!(!is_single_color(&[Tile::Wild, blue, red]));
+    }
+
+    #[test]
+    fn verify_from_char() {
+        let tiles = [
+            Tile::Empty,
+            Tile::Color(TileColor::Red),
+            Tile::Color(TileColor::Green),
+            Tile::Color(TileColor::Blue),
+            Tile::Color(TileColor::Yellow),
+            Tile::Wild,
+            Tile::Bomb,
+            Tile::Stone,
+            Tile::Armored,
+            Tile::Cracked,
+        ];
+
+        for tile in tiles {
+            let c = tile.to_string().chars().next().unwrap();
+            assert_eq!(Tile::from_char(c), Some(tile));
+        }
+        assert_eq!(Tile::from_char('x'), None);
+    }
+}
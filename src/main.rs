@@ -1,22 +1,88 @@
+use std::net::TcpListener;
+
 use bevy::{
     core::FrameCount,
     prelude::*,
     window::{PresentMode, WindowTheme},
     winit::WinitSettings,
 };
-use game::{settings::Resolution, ui::UiPlugins, GamePlugins};
+use game::{
+    netplay::{self, NetplayMatch},
+    settings::{load_settings, Resolution},
+    ui::UiPlugins,
+    GamePlugins,
+};
+use logic::{
+    ai::{self, Rotation, Solver},
+    lockstep::{turn_actions, LockstepError, NetMatch, NetMessage},
+    score::ScoringPreset,
+    session::SessionConfig,
+};
 use menu::MenuPlugins;
-use state::AppState;
+use net::{NetError, Peer};
+use state::{AppState, PauseState};
+use strum::IntoEnumIterator;
 
 mod audio;
 mod game;
 mod logic;
 mod menu;
+mod net;
+mod persist;
 mod state;
 
+/// Turns a headless game can last before it's cut short
+const BENCHMARK_MAX_TURNS: usize = 1000;
+
+/// Where netplay matches are hosted and joined when no address is given
+const DEFAULT_NETPLAY_ADDR: &str = "127.0.0.1:7777";
+
 fn main() {
-    App::new()
-        .init_state::<AppState>()
+    // Plays headless games with the solver instead of opening the game, e.g. `quatrix --benchmark 50`
+    // Netplay connects before opening the game, e.g. `quatrix --host 0.0.0.0:7777` and `quatrix --join 192.168.1.2:7777`.
+    // Adding `--solver` plays the match headless with the solver instead.
+    let mut args = std::env::args().skip(1);
+    let mut netplay = None;
+    match args.next().as_deref() {
+        Some("--benchmark") => {
+            let games = args.next().and_then(|x| x.parse().ok()).unwrap_or(20);
+            run_benchmark(games);
+            return;
+        }
+        Some(flag @ ("--host" | "--join")) => {
+            let args = args.collect::<Vec<_>>();
+            let solver = args.iter().any(|x| x == "--solver");
+            let addr = args
+                .iter()
+                .find(|x| !x.starts_with("--"))
+                .map_or(DEFAULT_NETPLAY_ADDR, |x| x.as_str());
+            let host = flag == "--host";
+
+            let (peer, config, seed) = match connect(host, addr) {
+                Ok(connected) => connected,
+                Err(err) => {
+                    eprintln!("{err}");
+                    return;
+                }
+            };
+
+            if solver {
+                run_netplay_solver(peer, if host { 0 } else { 1 }, config, seed);
+                return;
+            }
+
+            netplay = Some(if host {
+                NetplayMatch::host(peer, config, seed)
+            } else {
+                NetplayMatch::join(peer, config, seed)
+            });
+        }
+        _ => {}
+    }
+
+    let mut app = App::new();
+    app.init_state::<AppState>()
+        .init_state::<PauseState>()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Quatrix".into(),
@@ -42,8 +108,110 @@ fn main() {
         .add_plugins(MenuPlugins)
         .add_systems(Startup, setup_camera)
         .add_systems(Update, make_visible)
-        .insert_resource(WinitSettings::game())
-        .run();
+        .insert_resource(WinitSettings::game());
+
+    if let Some(netplay) = netplay {
+        app.insert_resource(netplay);
+    }
+
+    app.run();
+}
+
+/// Connects to the other player for a netplay match, returning the config and seed it's played with
+fn connect(host: bool, addr: &str) -> Result<(Peer, SessionConfig, u64), NetError> {
+    if !host {
+        let mut peer = Peer::join(addr)?;
+        let (config, seed) = peer.wait_for_start()?;
+        return Ok((peer, config, seed));
+    }
+
+    let listener = TcpListener::bind(addr)?;
+    println!("Waiting for a player to join on {}", listener.local_addr()?);
+
+    let mut peer = Peer::accept(&listener)?;
+    let config = netplay::session_config(&load_settings().unwrap_or_default());
+    let seed = rand::random();
+    peer.start(&config, seed)?;
+
+    Ok((peer, config, seed))
+}
+
+/// Plays a netplay match with the solver on one seat, checking each of the other peer's turns as they arrive
+fn run_netplay_solver(mut peer: Peer, seat: usize, config: SessionConfig, seed: u64) {
+    let mut net_match = NetMatch::new(config, seed);
+
+    match play_netplay_solver(&mut peer, &mut net_match, seat) {
+        Ok(()) => {
+            for seat in 0..2 {
+                let session = net_match.session(seat);
+                println!(
+                    "Seat {seat}: score {}0 over {} turns{}",
+                    session.board().score().score(),
+                    session.turns_played(),
+                    if net_match.lost(seat) { ", lost" } else { "" }
+                );
+            }
+        }
+        Err(err) => eprintln!("Match ended after {} turns: {err}", net_match.turns(seat)),
+    }
+}
+
+fn play_netplay_solver(
+    peer: &mut Peer,
+    net_match: &mut NetMatch,
+    seat: usize,
+) -> Result<(), NetError> {
+    let solver = Solver::default();
+    let options = Rotation::iter().collect::<Vec<_>>();
+    let remote = 1 - seat;
+
+    loop {
+        if net_match.can_play(seat) && (net_match.turns(seat) as usize) < BENCHMARK_MAX_TURNS {
+            let session = net_match.session(seat);
+            let rotation = solver
+                .best_rotation_for(session, &options)
+                .unwrap_or(Rotation::None);
+            let actions = turn_actions(session, rotation.action());
+
+            let turn = net_match.play_turn(seat, &actions, None)?;
+            peer.send(&NetMessage::Turn(turn))?;
+            continue;
+        }
+
+        // The other player's turn is still needed once this seat is done, as they may have lost on it too
+        if net_match.lost(remote) || net_match.turns(remote) >= net_match.turns(seat) {
+            break;
+        }
+
+        match peer.recv()? {
+            NetMessage::Turn(turn) if turn.turn == net_match.turns(remote) => {
+                net_match.play_turn(remote, &turn.actions, Some(turn.checksum))?;
+            }
+            NetMessage::Turn(turn) => return Err(LockstepError::InvalidTurn(turn.turn).into()),
+            NetMessage::Start { .. } => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the scoring presets by letting the solver play each one on the saved settings
+fn run_benchmark(games: u64) {
+    let settings = load_settings().unwrap_or_default();
+    let solver = Solver::default();
+
+    for preset in ScoringPreset::iter() {
+        let config = SessionConfig {
+            scoring: preset.rules(),
+            ..settings.session_config()
+        };
+        let result = ai::benchmark(&config, 0..games, &solver, BENCHMARK_MAX_TURNS);
+
+        println!(
+            "{preset:?}: mean score {:.0}0, best {}0, mean turns {:.1} over {} games",
+            result.mean_score, result.best_score, result.mean_turns, result.games
+        );
+    }
 }
 
 fn setup_camera(mut commands: Commands) {
@@ -2,8 +2,11 @@ use bevy::prelude::*;
 
 use crate::{
     audio::{PlaySoundEffect, SoundEffect},
-    game::ui::DEFAULT_FONT_PATH,
-    state::AppState,
+    game::{
+        controls::RestartPressed, puzzle::PlayPuzzle, replay::WatchReplay, ui::DEFAULT_FONT_PATH,
+        versus::PlayVersus,
+    },
+    state::{AppState, PauseState},
 };
 
 use super::MainMenuElement;
@@ -11,35 +14,108 @@ use super::MainMenuElement;
 // Pulled from Tailwind Hex Values
 // https://tailwindcss.com/docs/customizing-colors
 
-const NORMAL_BUTTON: Color = Color::rgb(
+pub const NORMAL_BUTTON: Color = Color::rgb(
     0x6b as f32 / 255.0,
     0x72 as f32 / 255.0,
     0x80 as f32 / 255.0,
 );
-const HOVERED_BUTTON: Color = Color::rgb(
+pub const HOVERED_BUTTON: Color = Color::rgb(
     0x9c as f32 / 255.0,
     0xa3 as f32 / 255.0,
     0xaf as f32 / 255.0,
 );
-const PRESSED_BUTTON: Color = Color::rgb(
+pub const PRESSED_BUTTON: Color = Color::rgb(
     0x4b as f32 / 255.0,
     0x55 as f32 / 255.0,
     0x63 as f32 / 255.0,
 );
 
+/// What a menu button does when pressed
+#[derive(Component, Debug, Clone)]
+pub enum MenuButton {
+    Play,
+    /// Starts a local match between two players
+    Versus,
+    /// Opens the puzzle level select
+    Puzzles,
+    /// Plays a puzzle by its index in the level list
+    PlayPuzzle(usize),
+    /// Watches a replay by file name, or the latest one
+    WatchReplay(Option<String>),
+    Leaderboard,
+    Settings,
+    /// Opens the control rebinding screen
+    Controls,
+    /// Returns to another screen
+    Back(AppState),
+    /// Moves between the pause menu screens, or resumes the game
+    Pause(PauseState),
+    /// Restarts the game in progress
+    Restart,
+    /// Leaves the game in progress for the title screen
+    Quit,
+}
+
+impl MenuButton {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::Play | MenuButton::PlayPuzzle(_) => "Play",
+            MenuButton::Versus => "Versus",
+            MenuButton::Puzzles => "Puzzles",
+            MenuButton::WatchReplay(None) => "Replay",
+            MenuButton::WatchReplay(Some(_)) => "Watch",
+            MenuButton::Leaderboard => "Scores",
+            MenuButton::Settings => "Settings",
+            MenuButton::Controls => "Controls",
+            MenuButton::Back(_) | MenuButton::Pause(PauseState::Paused) => "Back",
+            MenuButton::Pause(PauseState::Running) => "Resume",
+            MenuButton::Pause(PauseState::Settings) => "Settings",
+            MenuButton::Restart => "Restart",
+            MenuButton::Quit => "Quit",
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn hover_buttons(
     mut next_state: ResMut<NextState<AppState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (&Interaction, &mut BackgroundColor, &MenuButton),
         (Changed<Interaction>, With<Button>),
     >,
     mut sfx: EventWriter<PlaySoundEffect>,
+    mut watch_replay: EventWriter<WatchReplay>,
+    mut play_puzzle: EventWriter<PlayPuzzle>,
+    mut play_versus: EventWriter<PlayVersus>,
+    mut restart: EventWriter<RestartPressed>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::InGame);
+                match button {
+                    MenuButton::Play => next_state.set(AppState::InGame),
+                    MenuButton::Versus => {
+                        play_versus.send_default();
+                    }
+                    MenuButton::Puzzles => next_state.set(AppState::LevelSelect),
+                    MenuButton::PlayPuzzle(index) => {
+                        play_puzzle.send(PlayPuzzle(*index));
+                    }
+                    MenuButton::WatchReplay(file) => {
+                        watch_replay.send(WatchReplay(file.clone()));
+                    }
+                    MenuButton::Leaderboard => next_state.set(AppState::Leaderboard),
+                    MenuButton::Settings => next_state.set(AppState::Settings),
+                    MenuButton::Controls => next_state.set(AppState::Controls),
+                    MenuButton::Back(state) => next_state.set(*state),
+                    MenuButton::Pause(state) => next_pause.set(*state),
+                    MenuButton::Restart => {
+                        restart.send_default();
+                    }
+                    MenuButton::Quit => next_state.set(AppState::MainMenu),
+                }
                 sfx.send(PlaySoundEffect(SoundEffect::UiClick));
             }
             Interaction::Hovered => {
@@ -53,6 +129,40 @@ pub fn hover_buttons(
     }
 }
 
+/// Spawns a menu button with a text label, sized to fit `font_size`
+pub fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    button: MenuButton,
+    font_size: f32,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Px(font_size * 3.75),
+                height: Val::Px(font_size * 1.625),
+                border: UiRect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            border_color: BorderColor(Color::BLACK),
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                button.label(),
+                TextStyle {
+                    font: asset_server.load(DEFAULT_FONT_PATH),
+                    font_size,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ));
+        })
+        .insert(button);
+}
+
 pub fn setup_main_menu_buttons(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn(NodeBundle {
@@ -60,37 +170,25 @@ pub fn setup_main_menu_buttons(mut commands: Commands, asset_server: Res<AssetSe
                 left: Val::Px(32.0),
                 width: Val::Auto,
                 height: Val::Percent(100.0),
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Start,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Start,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
                 ..default()
             },
             ..default()
         })
         .insert(MainMenuElement)
         .with_children(|parent| {
-            parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        width: Val::Px(150.0),
-                        height: Val::Px(65.0),
-                        border: UiRect::all(Val::Px(2.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    border_color: BorderColor(Color::BLACK),
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Play",
-                        TextStyle {
-                            font: asset_server.load(DEFAULT_FONT_PATH),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                        },
-                    ));
-                });
+            for button in [
+                MenuButton::Play,
+                MenuButton::Versus,
+                MenuButton::Puzzles,
+                MenuButton::WatchReplay(None),
+                MenuButton::Leaderboard,
+                MenuButton::Settings,
+            ] {
+                spawn_menu_button(parent, &asset_server, button, 40.0);
+            }
         });
 }
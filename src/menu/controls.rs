@@ -0,0 +1,270 @@
+use bevy::{
+    input::{gamepad::GamepadButtonInput, keyboard::KeyboardInput},
+    prelude::*,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    audio::{PlaySoundEffect, SoundEffect},
+    game::{
+        bindings::{save_action_map, ActionMap, InputBinding},
+        ui::{ControlIntention, DEFAULT_FONT_PATH},
+    },
+    state::AppState,
+};
+
+use super::button::{spawn_menu_button, MenuButton, HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON};
+
+#[derive(Component)]
+pub struct ControlsElement;
+
+/// The control waiting for its next key or button press to be bound
+#[derive(Resource, Default)]
+pub struct RebindTarget(Option<ControlIntention>);
+
+/// What a button on the controls screen does when pressed
+#[derive(Component, Clone, Copy)]
+pub enum BindingButton {
+    /// Waits for an input to add to the control
+    Bind(ControlIntention),
+    /// Removes every input from the control
+    Clear(ControlIntention),
+    /// Puts every control back to its default bindings
+    Reset,
+}
+
+impl BindingButton {
+    fn label(&self) -> &'static str {
+        match self {
+            BindingButton::Bind(_) => "Bind",
+            BindingButton::Clear(_) => "Clear",
+            BindingButton::Reset => "Reset",
+        }
+    }
+}
+
+/// Lists the inputs bound to a control
+#[derive(Component)]
+pub struct BindingsText(ControlIntention);
+
+/// Prompts for an input while a control is being rebound
+#[derive(Component)]
+pub struct RebindPrompt;
+
+fn bindings_label(action_map: &ActionMap, intention: ControlIntention) -> String {
+    let inputs = action_map.bindings(intention);
+
+    if inputs.is_empty() {
+        String::from("Unbound")
+    } else {
+        inputs
+            .iter()
+            .map(InputBinding::label)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn prompt_label(target: &RebindTarget) -> String {
+    match target.0 {
+        Some(intention) => format!("Press a key or button for {intention}"),
+        None => String::from("Inputs can be bound to as many controls as needed"),
+    }
+}
+
+pub fn setup_controls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    action_map: Res<ActionMap>,
+    target: Res<RebindTarget>,
+) {
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(DEFAULT_FONT_PATH),
+        font_size,
+        ..default()
+    };
+
+    let spawn_binding_button = |parent: &mut ChildBuilder, button: BindingButton| {
+        parent
+            .spawn(ButtonBundle {
+                style: Style {
+                    width: Val::Px(72.0),
+                    height: Val::Px(30.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                border_color: BorderColor(Color::BLACK),
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            })
+            .insert(button)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(button.label(), text_style(18.0)));
+            });
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(32.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ControlsElement)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Controls", text_style(48.0)));
+
+            for intention in ControlIntention::iter() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(
+                            TextBundle::from_section(intention.to_string(), text_style(16.0))
+                                .with_style(Style {
+                                    width: Val::Px(280.0),
+                                    ..default()
+                                }),
+                        );
+
+                        parent.spawn((
+                            TextBundle::from_section(
+                                bindings_label(&action_map, intention),
+                                text_style(16.0),
+                            )
+                            .with_style(Style {
+                                width: Val::Px(200.0),
+                                ..default()
+                            }),
+                            BindingsText(intention),
+                        ));
+
+                        spawn_binding_button(parent, BindingButton::Bind(intention));
+                        spawn_binding_button(parent, BindingButton::Clear(intention));
+                    });
+            }
+
+            parent.spawn((
+                TextBundle::from_section(prompt_label(&target), text_style(16.0)),
+                RebindPrompt,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_menu_button(
+                        parent,
+                        &asset_server,
+                        MenuButton::Back(AppState::Settings),
+                        24.0,
+                    );
+                    spawn_binding_button(parent, BindingButton::Reset);
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn press_binding_buttons(
+    mut action_map: ResMut<ActionMap>,
+    mut target: ResMut<RebindTarget>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &BindingButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut sfx: EventWriter<PlaySoundEffect>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match *button {
+                    BindingButton::Bind(intention) => target.0 = Some(intention),
+                    BindingButton::Clear(intention) => {
+                        action_map.clear(intention);
+                        target.0 = None;
+                    }
+                    BindingButton::Reset => {
+                        *action_map = ActionMap::default();
+                        target.0 = None;
+                    }
+                }
+                sfx.send(PlaySoundEffect(SoundEffect::UiClick));
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+                sfx.send(PlaySoundEffect(SoundEffect::UiHover));
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+/// Binds the first key or button pressed while a control is waiting for one
+pub fn capture_binding(
+    mut action_map: ResMut<ActionMap>,
+    mut target: ResMut<RebindTarget>,
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    mut gamepad_input_events: EventReader<GamepadButtonInput>,
+    mut sfx: EventWriter<PlaySoundEffect>,
+) {
+    let keys = keyboard_input_events
+        .read()
+        .filter(|event| event.state.is_pressed())
+        .map(|event| InputBinding::Key(event.key_code));
+    let buttons = gamepad_input_events
+        .read()
+        .filter(|event| event.state.is_pressed())
+        .map(|event| InputBinding::Button(event.button.button_type));
+
+    let pressed = keys.chain(buttons).next();
+
+    if let (Some(intention), Some(input)) = (target.0, pressed) {
+        action_map.bind(intention, input);
+        target.0 = None;
+        sfx.send(PlaySoundEffect(SoundEffect::UiClick));
+    }
+}
+
+pub fn display_bindings(
+    action_map: Res<ActionMap>,
+    target: Res<RebindTarget>,
+    mut bindings: Query<(&mut Text, &BindingsText), Without<RebindPrompt>>,
+    mut prompt: Query<&mut Text, With<RebindPrompt>>,
+) {
+    for (mut text, BindingsText(intention)) in &mut bindings {
+        text.sections[0].value = bindings_label(&action_map, *intention);
+    }
+
+    for mut text in &mut prompt {
+        text.sections[0].value = prompt_label(&target);
+    }
+}
+
+pub fn save_controls_on_exit(action_map: Res<ActionMap>, mut target: ResMut<RebindTarget>) {
+    target.0 = None;
+    save_action_map(&action_map);
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{leaderboard::HighScores, ui::DEFAULT_FONT_PATH},
+    logic::leaderboard::{format_date, format_time},
+    state::AppState,
+};
+
+use super::button::{spawn_menu_button, MenuButton};
+
+#[derive(Component)]
+pub struct LeaderboardElement;
+
+pub fn setup_leaderboard(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+) {
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(DEFAULT_FONT_PATH),
+        font_size,
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(32.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LeaderboardElement)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Leaderboard", text_style(48.0)));
+
+            let modes = high_scores.0.modes();
+            if modes.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    "No scores yet, go play!",
+                    text_style(24.0),
+                ));
+            }
+
+            // One column per mode
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        column_gap: Val::Px(32.0),
+                        row_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for mode in modes {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(4.0),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(mode, text_style(28.0)));
+
+                                for (place, entry) in high_scores.0.entries(mode).enumerate() {
+                                    // Timed modes lead with what they're ranked by
+                                    let result = if entry.kind.ranked_by_time() {
+                                        format!("{:>10}", format_time(entry.time))
+                                    } else {
+                                        format!("{:0>9}0", entry.score)
+                                    };
+
+                                    parent
+                                        .spawn(NodeBundle {
+                                            style: Style {
+                                                flex_direction: FlexDirection::Row,
+                                                align_items: AlignItems::Center,
+                                                column_gap: Val::Px(8.0),
+                                                ..default()
+                                            },
+                                            ..default()
+                                        })
+                                        .with_children(|parent| {
+                                            parent.spawn(TextBundle::from_section(
+                                                format!(
+                                                    "{:>2}. {} Rank {} {}x{} {}",
+                                                    place + 1,
+                                                    result,
+                                                    entry.rank,
+                                                    entry.board_width,
+                                                    entry.board_height,
+                                                    format_date(entry.date)
+                                                ),
+                                                text_style(16.0),
+                                            ));
+
+                                            if let Some(replay) = &entry.replay {
+                                                spawn_menu_button(
+                                                    parent,
+                                                    &asset_server,
+                                                    MenuButton::WatchReplay(Some(replay.clone())),
+                                                    12.0,
+                                                );
+                                            }
+                                        });
+                                }
+                            });
+                    }
+                });
+
+            spawn_menu_button(
+                parent,
+                &asset_server,
+                MenuButton::Back(AppState::MainMenu),
+                24.0,
+            );
+        });
+}
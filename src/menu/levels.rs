@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{puzzle::Puzzles, ui::DEFAULT_FONT_PATH},
+    state::AppState,
+};
+
+use super::button::{spawn_menu_button, MenuButton};
+
+#[derive(Component)]
+pub struct LevelSelectElement;
+
+pub fn setup_level_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    puzzles: Res<Puzzles>,
+) {
+    let text_style = |font_size: f32, color: Color| TextStyle {
+        font: asset_server.load(DEFAULT_FONT_PATH),
+        font_size,
+        color,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(32.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LevelSelectElement)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Puzzles",
+                text_style(48.0, Color::WHITE),
+            ));
+
+            if puzzles.levels.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    "No puzzles found",
+                    text_style(24.0, Color::WHITE),
+                ));
+            }
+
+            for (index, level) in puzzles.levels.iter().enumerate() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(16.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        spawn_menu_button(
+                            parent,
+                            &asset_server,
+                            MenuButton::PlayPuzzle(index),
+                            16.0,
+                        );
+
+                        parent.spawn(TextBundle::from_section(
+                            format!(
+                                "{:>2}. {} - {} in {} moves",
+                                index + 1,
+                                level.name(),
+                                level.goal().describe(),
+                                level.move_limit()
+                            ),
+                            text_style(20.0, Color::WHITE),
+                        ));
+
+                        let (status, color) = match puzzles.progress.best(level.name()) {
+                            Some(moves) => (format!("Solved in {moves}"), Color::GOLD),
+                            None => (String::from("Unsolved"), Color::GRAY),
+                        };
+                        parent.spawn(TextBundle::from_section(status, text_style(20.0, color)));
+                    });
+            }
+
+            spawn_menu_button(
+                parent,
+                &asset_server,
+                MenuButton::Back(AppState::MainMenu),
+                24.0,
+            );
+        });
+}
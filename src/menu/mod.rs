@@ -1,11 +1,29 @@
 mod button;
+mod controls;
+mod leaderboard;
+mod levels;
+mod pause;
+mod settings;
 mod title;
 
 use bevy::prelude::*;
 use button::{hover_buttons, setup_main_menu_buttons};
+use controls::{
+    capture_binding, display_bindings, press_binding_buttons, save_controls_on_exit,
+    setup_controls, ControlsElement, RebindTarget,
+};
+use leaderboard::{setup_leaderboard, LeaderboardElement};
+use levels::{setup_level_select, LevelSelectElement};
+use pause::{setup_pause_menu, PauseElement};
+use settings::{
+    display_setting_values, press_setting_buttons, save_on_exit, setup_settings, SettingsElement,
+};
 use title::setup_main_menu;
 
-use crate::state::AppState;
+use crate::{
+    game::{bindings::ActionMap, settings::GameSettings},
+    state::{AppState, PauseState},
+};
 
 #[derive(Component)]
 pub struct MainMenuElement;
@@ -18,12 +36,67 @@ impl Plugin for MenuPlugins {
             OnEnter(AppState::MainMenu),
             (setup_main_menu, setup_main_menu_buttons),
         )
-        .add_systems(OnExit(AppState::MainMenu), tear_down_main_menu)
-        .add_systems(Update, hover_buttons.run_if(in_state(AppState::MainMenu)));
+        .add_systems(OnExit(AppState::MainMenu), tear_down::<MainMenuElement>)
+        .add_systems(OnEnter(AppState::Leaderboard), setup_leaderboard)
+        .add_systems(
+            OnExit(AppState::Leaderboard),
+            tear_down::<LeaderboardElement>,
+        )
+        .add_systems(OnEnter(AppState::LevelSelect), setup_level_select)
+        .add_systems(
+            OnExit(AppState::LevelSelect),
+            tear_down::<LevelSelectElement>,
+        )
+        .add_systems(OnEnter(AppState::Settings), setup_settings)
+        .add_systems(
+            OnExit(AppState::Settings),
+            (tear_down::<SettingsElement>, save_on_exit),
+        )
+        .add_systems(OnEnter(PauseState::Paused), setup_pause_menu)
+        .add_systems(OnExit(PauseState::Paused), tear_down::<PauseElement>)
+        .add_systems(OnEnter(PauseState::Settings), setup_settings)
+        .add_systems(
+            OnExit(PauseState::Settings),
+            (tear_down::<SettingsElement>, save_on_exit),
+        )
+        .add_systems(
+            Update,
+            (
+                press_setting_buttons,
+                display_setting_values.run_if(resource_changed::<GameSettings>),
+            )
+                .chain()
+                .run_if(in_state(AppState::Settings).or_else(in_state(PauseState::Settings))),
+        )
+        .init_resource::<RebindTarget>()
+        .add_systems(OnEnter(AppState::Controls), setup_controls)
+        .add_systems(
+            OnExit(AppState::Controls),
+            (tear_down::<ControlsElement>, save_controls_on_exit),
+        )
+        .add_systems(
+            Update,
+            (
+                press_binding_buttons,
+                capture_binding,
+                display_bindings.run_if(
+                    resource_changed::<ActionMap>.or_else(resource_changed::<RebindTarget>),
+                ),
+            )
+                .chain()
+                .run_if(in_state(AppState::Controls)),
+        )
+        .add_systems(
+            Update,
+            hover_buttons.run_if(
+                not(in_state(AppState::InGame)).or_else(not(in_state(PauseState::Running))),
+            ),
+        );
     }
 }
 
-fn tear_down_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuElement>>) {
+/// Despawns every element of a menu screen
+fn tear_down<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
     for ent in &query {
         commands.entity(ent).despawn_recursive();
     }
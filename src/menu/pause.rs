@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::{game::ui::DEFAULT_FONT_PATH, state::PauseState};
+
+use super::button::{spawn_menu_button, MenuButton};
+
+/// Dims the game behind the pause screens
+pub const PAUSE_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+
+#[derive(Component)]
+pub struct PauseElement;
+
+pub fn setup_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            background_color: PAUSE_BACKGROUND.into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(PauseElement)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font: asset_server.load(DEFAULT_FONT_PATH),
+                    font_size: 64.0,
+                    ..default()
+                },
+            ));
+
+            for button in [
+                MenuButton::Pause(PauseState::Running),
+                MenuButton::Restart,
+                MenuButton::Pause(PauseState::Settings),
+                MenuButton::Quit,
+            ] {
+                spawn_menu_button(parent, &asset_server, button, 40.0);
+            }
+        });
+}
@@ -0,0 +1,358 @@
+use bevy::prelude::*;
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::{
+    audio::{PlaySoundEffect, SoundEffect},
+    game::{
+        settings::{
+            save_settings, Assist, GameSettings, WindowSize, MAX_BOARD_DIM, MAX_DROPS_PER_TURN,
+            MAX_PREVIEW_TURNS, MIN_BOARD_DIM,
+        },
+        ui::{ControlPlatform, DEFAULT_FONT_PATH},
+    },
+    logic::{generator::GeneratorKind, mode::GameModeKind},
+    state::{AppState, PauseState},
+};
+
+use super::{
+    button::{spawn_menu_button, MenuButton, HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON},
+    pause::PAUSE_BACKGROUND,
+};
+
+#[derive(Component)]
+pub struct SettingsElement;
+
+/// A setting that can be edited from the settings screen
+#[derive(Component, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingField {
+    Mode,
+    BoardWidth,
+    BoardHeight,
+    DropsPerTurn,
+    PreviewTurns,
+    DropGenerator,
+    Garbage,
+    Resolution,
+    Volume,
+    Controls,
+    Assist,
+    LandingPreview,
+}
+
+impl SettingField {
+    fn label(&self) -> &'static str {
+        match self {
+            SettingField::Mode => "Game Mode",
+            SettingField::BoardWidth => "Board Width",
+            SettingField::BoardHeight => "Board Height",
+            SettingField::DropsPerTurn => "Drops per Turn",
+            SettingField::PreviewTurns => "Drop Preview",
+            SettingField::DropGenerator => "Drop Generator",
+            SettingField::Garbage => "Garbage",
+            SettingField::Resolution => "Resolution",
+            SettingField::Volume => "Volume",
+            SettingField::Controls => "Control Hints",
+            SettingField::Assist => "Assist",
+            SettingField::LandingPreview => "Landing Preview",
+        }
+    }
+
+    fn value(&self, settings: &GameSettings) -> String {
+        match self {
+            SettingField::Mode => format!("{:?}", settings.mode),
+            SettingField::BoardWidth => settings.board_width.to_string(),
+            SettingField::BoardHeight => settings.board_height.to_string(),
+            SettingField::DropsPerTurn => settings.blocks_dropped_per_turn.to_string(),
+            SettingField::PreviewTurns => settings.preview_turns.to_string(),
+            SettingField::DropGenerator => format!("{:?}", settings.drop_generator),
+            SettingField::Garbage => String::from(if settings.garbage { "On" } else { "Off" }),
+            SettingField::Resolution => {
+                let resolution = settings.resolution();
+                format!("{}x{}", resolution.x, resolution.y)
+            }
+            SettingField::Volume => format!("{:.0}%", settings.volume * 100.0),
+            SettingField::Controls => settings
+                .control_platform
+                .map(|platform| format!("{platform:?}"))
+                .unwrap_or_else(|| String::from("Auto")),
+            SettingField::Assist => format!("{:?}", settings.assist),
+            SettingField::LandingPreview => String::from(if settings.landing_preview {
+                "On"
+            } else {
+                "Off"
+            }),
+        }
+    }
+
+    /// Steps the setting forwards or backwards, stopping at either end
+    fn adjust(&self, settings: &mut GameSettings, forwards: bool) {
+        match self {
+            SettingField::Mode => {
+                settings.mode = step_option(
+                    &GameModeKind::iter().collect::<Vec<_>>(),
+                    settings.mode,
+                    forwards,
+                );
+            }
+            SettingField::BoardWidth => {
+                settings.board_width = step_dim(settings.board_width, forwards);
+            }
+            SettingField::BoardHeight => {
+                settings.board_height = step_dim(settings.board_height, forwards);
+            }
+            SettingField::DropsPerTurn => {
+                let drops = settings.blocks_dropped_per_turn;
+                settings.blocks_dropped_per_turn = if forwards {
+                    drops.saturating_add(1).min(MAX_DROPS_PER_TURN)
+                } else {
+                    drops.saturating_sub(1).max(1)
+                };
+            }
+            SettingField::PreviewTurns => {
+                let turns = settings.preview_turns;
+                settings.preview_turns = if forwards {
+                    turns.saturating_add(1).min(MAX_PREVIEW_TURNS)
+                } else {
+                    turns.saturating_sub(1).max(1)
+                };
+            }
+            SettingField::DropGenerator => {
+                settings.drop_generator = step_option(
+                    &GeneratorKind::iter().collect::<Vec<_>>(),
+                    settings.drop_generator,
+                    forwards,
+                );
+            }
+            SettingField::Garbage => {
+                settings.garbage = forwards;
+            }
+            SettingField::Resolution => {
+                settings.window_size = step_option(
+                    &WindowSize::iter().collect::<Vec<_>>(),
+                    settings.window_size,
+                    forwards,
+                );
+            }
+            SettingField::Volume => {
+                let step = if forwards { 0.1 } else { -0.1 };
+                settings.volume = ((settings.volume + step) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+            }
+            SettingField::Controls => {
+                let options = [None]
+                    .into_iter()
+                    .chain(ControlPlatform::iter().map(Some))
+                    .collect::<Vec<_>>();
+                settings.control_platform =
+                    step_option(&options, settings.control_platform, forwards);
+            }
+            SettingField::Assist => {
+                settings.assist = step_option(
+                    &Assist::iter().collect::<Vec<_>>(),
+                    settings.assist,
+                    forwards,
+                );
+            }
+            SettingField::LandingPreview => {
+                settings.landing_preview = forwards;
+            }
+        }
+    }
+}
+
+fn step_dim(dim: u8, forwards: bool) -> u8 {
+    if forwards {
+        dim.saturating_add(1).min(MAX_BOARD_DIM)
+    } else {
+        dim.saturating_sub(1).max(MIN_BOARD_DIM)
+    }
+}
+
+fn step_option<T: PartialEq + Copy>(options: &[T], current: T, forwards: bool) -> T {
+    let index = options.iter().position(|x| *x == current).unwrap_or(0);
+    let index = if forwards {
+        (index + 1).min(options.len() - 1)
+    } else {
+        index.saturating_sub(1)
+    };
+
+    options[index]
+}
+
+/// Steps a setting when pressed
+#[derive(Component)]
+pub struct SettingButton {
+    field: SettingField,
+    forwards: bool,
+}
+
+/// Shows the current value of a setting
+#[derive(Component)]
+pub struct SettingValue(SettingField);
+
+pub fn setup_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<GameSettings>,
+    pause: Res<State<PauseState>>,
+) {
+    // Opened from the pause menu, so it's shown over the game and can't leave it
+    let paused = *pause.get() == PauseState::Settings;
+
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(DEFAULT_FONT_PATH),
+        font_size,
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(32.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            background_color: if paused {
+                PAUSE_BACKGROUND.into()
+            } else {
+                Color::NONE.into()
+            },
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(SettingsElement)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Settings", text_style(48.0)));
+
+            for field in SettingField::iter() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(
+                            TextBundle::from_section(field.label(), text_style(24.0)).with_style(
+                                Style {
+                                    width: Val::Px(200.0),
+                                    ..default()
+                                },
+                            ),
+                        );
+
+                        for (label, forwards) in [("<", false), (">", true)] {
+                            parent
+                                .spawn(ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(36.0),
+                                        height: Val::Px(36.0),
+                                        border: UiRect::all(Val::Px(2.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    border_color: BorderColor(Color::BLACK),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                })
+                                .insert(SettingButton { field, forwards })
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(label, text_style(24.0)));
+                                });
+
+                            // The value sits between the two buttons
+                            if !forwards {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        field.value(&settings),
+                                        text_style(24.0),
+                                    )
+                                    .with_text_justify(JustifyText::Center)
+                                    .with_style(Style {
+                                        width: Val::Px(160.0),
+                                        ..default()
+                                    }),
+                                    SettingValue(field),
+                                ));
+                            }
+                        }
+                    });
+            }
+
+            parent.spawn(TextBundle::from_section(
+                "Board size applies when the next game starts",
+                text_style(16.0),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    if paused {
+                        spawn_menu_button(
+                            parent,
+                            &asset_server,
+                            MenuButton::Pause(PauseState::Paused),
+                            24.0,
+                        );
+                    } else {
+                        for button in [MenuButton::Back(AppState::MainMenu), MenuButton::Controls] {
+                            spawn_menu_button(parent, &asset_server, button, 24.0);
+                        }
+                    }
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn press_setting_buttons(
+    mut settings: ResMut<GameSettings>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &SettingButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut sfx: EventWriter<PlaySoundEffect>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                button.field.adjust(&mut settings, button.forwards);
+                sfx.send(PlaySoundEffect(SoundEffect::UiClick));
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+                sfx.send(PlaySoundEffect(SoundEffect::UiHover));
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+pub fn display_setting_values(
+    settings: Res<GameSettings>,
+    mut values: Query<(&mut Text, &SettingValue)>,
+) {
+    for (mut text, SettingValue(field)) in &mut values {
+        text.sections[0].value = field.value(&settings);
+    }
+}
+
+pub fn save_on_exit(settings: Res<GameSettings>) {
+    save_settings(&settings);
+}
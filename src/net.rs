@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    thread,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::logic::{
+    lockstep::{LockstepError, NetMessage, PROTOCOL_VERSION},
+    session::SessionConfig,
+};
+
+/// How long to wait between checks for a message while blocking on one
+const RECV_POLL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Error)]
+pub enum NetError {
+    #[error("Connection failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to write a message: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to read a message: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+    #[error("The other player disconnected")]
+    Disconnected,
+    #[error("The other player is on protocol version {0}, this is version {PROTOCOL_VERSION}")]
+    Version(u32),
+    #[error("Expected the host to start the match")]
+    NotStarted,
+    #[error(transparent)]
+    Lockstep(#[from] LockstepError),
+}
+
+/// A connection to the other player, exchanging [`NetMessage`]s as lines of RON over TCP.
+///
+/// Reads never block, so the connection can be checked every frame.
+pub struct Peer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// A message that has only partly arrived
+    partial: Vec<u8>,
+    /// Messages that have arrived but haven't been taken yet
+    received: VecDeque<NetMessage>,
+}
+
+impl Peer {
+    fn new(stream: TcpStream) -> Result<Self, NetError> {
+        // Messages are tiny and sent once a turn, so they shouldn't wait to be batched
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            partial: Vec::new(),
+            received: VecDeque::new(),
+        })
+    }
+
+    /// Waits for the other player to connect
+    pub fn accept(listener: &TcpListener) -> Result<Self, NetError> {
+        let (stream, _) = listener.accept()?;
+        Self::new(stream)
+    }
+
+    /// Connects to a player that's hosting
+    pub fn join(addr: impl ToSocketAddrs) -> Result<Self, NetError> {
+        Self::new(TcpStream::connect(addr)?)
+    }
+
+    pub fn send(&mut self, message: &NetMessage) -> Result<(), NetError> {
+        let mut line = ron::to_string(message)?.into_bytes();
+        line.push(b'\n');
+
+        let mut sent = 0;
+        while sent < line.len() {
+            match self.writer.write(&line[sent..]) {
+                Ok(0) => return Err(NetError::Disconnected),
+                Ok(n) => sent += n,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => thread::yield_now(),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every message that has arrived, without waiting on any more
+    pub fn poll(&mut self) -> Result<Vec<NetMessage>, NetError> {
+        self.read()?;
+        Ok(self.received.drain(..).collect())
+    }
+
+    /// Waits for the next message
+    pub fn recv(&mut self) -> Result<NetMessage, NetError> {
+        loop {
+            if let Some(message) = self.received.pop_front() {
+                return Ok(message);
+            }
+
+            self.read()?;
+            if self.received.is_empty() {
+                thread::sleep(RECV_POLL);
+            }
+        }
+    }
+
+    /// Reads whatever has arrived, queueing each message that has arrived in full
+    fn read(&mut self) -> Result<(), NetError> {
+        loop {
+            match self.reader.read_until(b'\n', &mut self.partial) {
+                // Messages sent before hanging up are still taken first
+                Ok(0) if self.received.is_empty() => return Err(NetError::Disconnected),
+                Ok(0) => return Ok(()),
+                Ok(_) if self.partial.ends_with(b"\n") => {
+                    let line = std::mem::take(&mut self.partial);
+                    self.received.push_back(ron::de::from_bytes(&line)?);
+                }
+                // The rest of the line hasn't arrived yet
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Starts the match as the host, both boards being played with the config and seed
+    pub fn start(&mut self, config: &SessionConfig, seed: u64) -> Result<(), NetError> {
+        self.send(&NetMessage::Start {
+            version: PROTOCOL_VERSION,
+            config: Box::new(config.clone()),
+            seed,
+        })
+    }
+
+    /// Waits for the host to start the match, returning the config and seed it's played with
+    pub fn wait_for_start(&mut self) -> Result<(SessionConfig, u64), NetError> {
+        match self.recv()? {
+            NetMessage::Start {
+                version,
+                config,
+                seed,
+            } if version == PROTOCOL_VERSION => Ok((*config, seed)),
+            NetMessage::Start { version, .. } => Err(NetError::Version(version)),
+            NetMessage::Turn(_) => Err(NetError::NotStarted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use crate::logic::{
+        lockstep::{NetMessage, TurnInputs},
+        session::{GameAction, SessionConfig},
+    };
+
+    use super::{NetError, Peer};
+
+    #[test]
+    fn verify_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = SessionConfig::new(5, 4);
+
+        let joined = thread::spawn(move || {
+            let mut peer = Peer::join(addr).unwrap();
+            let start = peer.wait_for_start().unwrap();
+
+            let NetMessage::Turn(turn) = peer.recv().unwrap() else {
+                panic!("Expected a turn");
+            };
+            peer.send(&NetMessage::Turn(turn)).unwrap();
+
+            start
+        });
+
+        let mut host = Peer::accept(&listener).unwrap();
+        host.start(&config, 42).unwrap();
+
+        let turn = TurnInputs {
+            turn: 0,
+            actions: vec![GameAction::ShiftUp, GameAction::Flip, GameAction::Drop],
+            checksum: 7,
+        };
+        host.send(&NetMessage::Turn(turn.clone())).unwrap();
+
+        assert_eq!(host.recv().unwrap(), NetMessage::Turn(turn));
+        assert_eq!(joined.join().unwrap(), (config, 42));
+
+        // The joined thread has hung up
+        assert!(matches!(host.recv(), Err(NetError::Disconnected)));
+    }
+}
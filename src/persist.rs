@@ -0,0 +1,57 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PersistError {
+    #[error("Failed to access save data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to write save data: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to read save data: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("app", "quatrix", "Quatrix")
+}
+
+/// The directory save data is kept in, falling back to the working directory
+/// if the platform doesn't provide one.
+pub fn data_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The directory config files are kept in, falling back to the working directory
+/// if the platform doesn't provide one.
+pub fn config_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Writes a value to a RON file, creating any missing parent directories
+pub fn save_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), PersistError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    fs::write(path, text)?;
+
+    Ok(())
+}
+
+/// Reads a value from a RON file
+pub fn load_ron<T: DeserializeOwned>(path: &Path) -> Result<T, PersistError> {
+    let text = fs::read_to_string(path)?;
+
+    Ok(ron::from_str(&text)?)
+}
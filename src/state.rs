@@ -5,4 +5,24 @@ pub enum AppState {
     #[default]
     MainMenu,
     InGame,
+    /// Viewing the local leaderboard
+    Leaderboard,
+    /// Editing the settings
+    Settings,
+    /// Rebinding controls
+    Controls,
+    /// Picking a puzzle level
+    LevelSelect,
+}
+
+/// Whether a game in progress is paused, and which pause screen is open.
+/// Always `Running` outside of [`AppState::InGame`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    /// Showing the pause menu
+    Paused,
+    /// Editing the settings from the pause menu
+    Settings,
 }